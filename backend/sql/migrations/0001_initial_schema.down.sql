@@ -0,0 +1,2 @@
+drop table thought.thought;
+drop schema thought;
@@ -0,0 +1,17 @@
+create extension if not exists ltree with schema public;
+create extension if not exists "uuid-ossp" with schema public;
+
+create schema if not exists thought;
+
+create table if not exists thought.thought (
+    thought_id uuid default public.uuid_generate_v4() not null,
+    parent_thought_id uuid,
+    keywords text[] default array[]::text[] not null,
+    categories public.ltree[] default array[]::public.ltree[] not null,
+    sources jsonb default '[]'::jsonb not null,
+    created_at timestamp without time zone default now() not null,
+    content jsonb not null,
+    constraint thought_pkey primary key (thought_id),
+    constraint thought_parent_thought_id_fkey foreign key (parent_thought_id)
+        references thought.thought (thought_id)
+);
@@ -0,0 +1,7 @@
+drop index thought.thought_search_vector_idx;
+drop trigger thought_search_vector on thought.thought;
+drop function thought.update_search_vector();
+
+alter table thought.thought
+    drop column search_vector,
+    drop column search_language;
@@ -0,0 +1,21 @@
+alter table thought.thought
+    add column search_language regconfig default 'simple' not null,
+    add column search_vector tsvector;
+
+create function thought.update_search_vector() returns trigger language plpgsql as $$
+begin
+    new.search_vector :=
+        setweight(to_tsvector(new.search_language, coalesce(new.content #>> '{}', '')), 'A')
+        || setweight(to_tsvector(new.search_language, array_to_string(new.keywords, ' ')), 'B');
+
+    return new;
+end;
+$$;
+
+create trigger thought_search_vector
+    before insert or update of content, keywords, search_language on thought.thought
+    for each row execute function thought.update_search_vector();
+
+update thought.thought set search_language = search_language;
+
+create index thought_search_vector_idx on thought.thought using gin (search_vector);
@@ -0,0 +1,2 @@
+drop index thought.thought_created_at_idx;
+drop index thought.thought_parent_thought_id_idx;
@@ -0,0 +1,2 @@
+create index thought_parent_thought_id_idx on thought.thought (parent_thought_id);
+create index thought_created_at_idx on thought.thought (created_at, thought_id);
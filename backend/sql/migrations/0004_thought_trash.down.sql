@@ -0,0 +1,3 @@
+drop index thought.thought_deleted_at_idx;
+
+alter table thought.thought drop column deleted_at;
@@ -0,0 +1,3 @@
+alter table thought.thought add column deleted_at timestamp without time zone;
+
+create index thought_deleted_at_idx on thought.thought (deleted_at) where deleted_at is not null;
@@ -0,0 +1,4 @@
+drop trigger thought_revision on thought.thought;
+drop function thought.record_revision();
+
+drop table thought.thought_revision;
@@ -0,0 +1,29 @@
+create table thought.thought_revision (
+    thought_id uuid not null,
+    revision integer not null,
+    keywords text[] not null,
+    categories public.ltree[] not null,
+    sources jsonb not null,
+    content jsonb not null,
+    superseded_at timestamp without time zone default now() not null,
+    constraint thought_revision_pkey primary key (thought_id, revision),
+    constraint thought_revision_thought_id_fkey foreign key (thought_id)
+        references thought.thought (thought_id) on delete cascade
+);
+
+create function thought.record_revision() returns trigger language plpgsql as $$
+begin
+    insert into thought.thought_revision (thought_id, revision, keywords, categories, sources, content)
+    select old.thought_id, coalesce(max(revision), 0) + 1, old.keywords, old.categories, old.sources, old.content
+    from thought.thought_revision where thought_id = old.thought_id;
+
+    return new;
+end;
+$$;
+
+create trigger thought_revision
+    after update of content, keywords, categories, sources on thought.thought
+    for each row
+    when ((old.content, old.keywords, old.categories, old.sources)
+        is distinct from (new.content, new.keywords, new.categories, new.sources))
+    execute function thought.record_revision();
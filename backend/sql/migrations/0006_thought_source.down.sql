@@ -0,0 +1,48 @@
+drop trigger thought_revision on thought.thought;
+drop index thought.thought_source_ids_idx;
+
+alter table thought.thought add column sources jsonb default '[]'::jsonb not null;
+alter table thought.thought_revision add column sources jsonb default '[]'::jsonb not null;
+
+-- embed the referenced sources back, in their order
+create function pg_temp.sources(source_ids uuid[]) returns jsonb language sql as $$
+    select coalesce(
+        jsonb_agg(
+            jsonb_build_object(
+                'name', source.name,
+                'authors', to_jsonb(source.authors),
+                'description', source.description
+            )
+            order by reference.position
+        ),
+        '[]'::jsonb
+    )
+    from unnest(source_ids) with ordinality as reference(source_id, position)
+    join thought.source as source on source.source_id = reference.source_id;
+$$;
+
+update thought.thought set sources = pg_temp.sources(source_ids);
+update thought.thought_revision set sources = pg_temp.sources(source_ids);
+
+alter table thought.thought drop column source_ids;
+alter table thought.thought_revision drop column source_ids;
+
+drop table thought.source;
+drop function thought.name_key(text);
+
+create or replace function thought.record_revision() returns trigger language plpgsql as $$
+begin
+    insert into thought.thought_revision (thought_id, revision, keywords, categories, sources, content)
+    select old.thought_id, coalesce(max(revision), 0) + 1, old.keywords, old.categories, old.sources, old.content
+    from thought.thought_revision where thought_id = old.thought_id;
+
+    return new;
+end;
+$$;
+
+create trigger thought_revision
+    after update of content, keywords, categories, sources on thought.thought
+    for each row
+    when ((old.content, old.keywords, old.categories, old.sources)
+        is distinct from (new.content, new.keywords, new.categories, new.sources))
+    execute function thought.record_revision();
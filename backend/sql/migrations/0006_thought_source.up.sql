@@ -0,0 +1,99 @@
+-- same normalization as the application, see `normalize_name`: lowercase words separated by a
+-- single space. Only the ASCII characters are lowercased and split on, the collation is fixed so
+-- the keys do not depend on the locale of the database.
+create function thought.name_key(name text) returns text language sql immutable strict as $$
+    select trim(regexp_replace(
+        lower(name collate "C"),
+        '[\x01-\x2f\x3a-\x40\x5b-\x60\x7b-\x7f]+',
+        ' ',
+        'g'
+    ));
+$$;
+
+create table thought.source (
+    source_id uuid default public.uuid_generate_v4() not null,
+    name text not null,
+    authors text[] default array[]::text[] not null,
+    description text default '' not null,
+    created_at timestamp without time zone default now() not null,
+    -- key shared by the sources considered as duplicates, see `ThoughtSource::duplicate_key`
+    duplicate_key text,
+    constraint source_pkey primary key (source_id),
+    constraint source_duplicate_key_key unique (duplicate_key)
+);
+
+-- the revision trigger watches the embedded sources, it is recreated once they are replaced
+drop trigger thought_revision on thought.thought;
+
+alter table thought.thought add column source_ids uuid[] default array[]::uuid[] not null;
+alter table thought.thought_revision add column source_ids uuid[] default array[]::uuid[] not null;
+
+-- register every distinct embedded source once
+insert into thought.source (name, authors, description)
+select distinct
+    coalesce(embedded.source ->> 'name', ''),
+    array(select jsonb_array_elements_text(embedded.source -> 'authors')),
+    coalesce(embedded.source ->> 'description', '')
+from (
+    select jsonb_array_elements(sources) as source from thought.thought
+    union all
+    select jsonb_array_elements(sources) as source from thought.thought_revision
+) as embedded;
+
+-- the embedded sources may already be duplicates, only the first one of each key holds it, the
+-- others are found by the duplicates listing and merged
+update thought.source as source set duplicate_key = keyed.duplicate_key
+from (
+    select distinct on (candidate.duplicate_key) candidate.source_id, candidate.duplicate_key
+    from (
+        select
+            source_id,
+            created_at,
+            thought.name_key(name) || '|' || array_to_string(
+                array(
+                    select thought.name_key(author)
+                    from unnest(authors) as author
+                    order by thought.name_key(author) collate "C"
+                ),
+                '|'
+            ) as duplicate_key
+        from thought.source
+    ) as candidate
+    order by candidate.duplicate_key, candidate.created_at, candidate.source_id
+) as keyed
+where keyed.source_id = source.source_id;
+
+-- reference the registered sources, in their original order
+create function pg_temp.source_ids(sources jsonb) returns uuid[] language sql as $$
+    select coalesce(array_agg(source.source_id order by embedded.position), array[]::uuid[])
+    from jsonb_array_elements(sources) with ordinality as embedded(value, position)
+    join thought.source as source
+        on source.name = coalesce(embedded.value ->> 'name', '')
+        and source.authors = array(select jsonb_array_elements_text(embedded.value -> 'authors'))
+        and source.description = coalesce(embedded.value ->> 'description', '');
+$$;
+
+update thought.thought set source_ids = pg_temp.source_ids(sources);
+update thought.thought_revision set source_ids = pg_temp.source_ids(sources);
+
+alter table thought.thought drop column sources;
+alter table thought.thought_revision drop column sources;
+
+create index thought_source_ids_idx on thought.thought using gin (source_ids);
+
+create or replace function thought.record_revision() returns trigger language plpgsql as $$
+begin
+    insert into thought.thought_revision (thought_id, revision, keywords, categories, source_ids, content)
+    select old.thought_id, coalesce(max(revision), 0) + 1, old.keywords, old.categories, old.source_ids, old.content
+    from thought.thought_revision where thought_id = old.thought_id;
+
+    return new;
+end;
+$$;
+
+create trigger thought_revision
+    after update of content, keywords, categories, source_ids on thought.thought
+    for each row
+    when ((old.content, old.keywords, old.categories, old.source_ids)
+        is distinct from (new.content, new.keywords, new.categories, new.source_ids))
+    execute function thought.record_revision();
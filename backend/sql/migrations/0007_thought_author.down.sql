@@ -0,0 +1,3 @@
+drop table thought.source_author;
+drop table thought.author_alias;
+drop table thought.author;
@@ -0,0 +1,46 @@
+create table thought.author (
+    author_id uuid default public.uuid_generate_v4() not null,
+    name text not null,
+    created_at timestamp without time zone default now() not null,
+    constraint author_pkey primary key (author_id)
+);
+
+-- names an author is known by, the key is the normalized name used for lookups
+create table thought.author_alias (
+    alias_key text not null,
+    alias text not null,
+    author_id uuid not null,
+    constraint author_alias_pkey primary key (alias_key),
+    constraint author_alias_author_id_fkey foreign key (author_id)
+        references thought.author (author_id) on delete cascade
+);
+create index author_alias_author_id_idx on thought.author_alias (author_id);
+
+create table thought.source_author (
+    source_id uuid not null,
+    author_id uuid not null,
+    position integer not null,
+    constraint source_author_pkey primary key (source_id, author_id),
+    constraint source_author_source_id_fkey foreign key (source_id)
+        references thought.source (source_id) on delete cascade,
+    constraint source_author_author_id_fkey foreign key (author_id)
+        references thought.author (author_id) on delete cascade
+);
+create index source_author_author_id_idx on thought.source_author (author_id);
+
+-- one author per normalized name cited by the sources
+insert into thought.author (name)
+select distinct on (thought.name_key(cited.name)) cited.name
+from (select unnest(authors) as name from thought.source) as cited
+where thought.name_key(cited.name) <> ''
+order by thought.name_key(cited.name), cited.name;
+
+insert into thought.author_alias (alias_key, alias, author_id)
+select thought.name_key(name), name, author_id from thought.author;
+
+insert into thought.source_author (source_id, author_id, position)
+select source.source_id, alias.author_id, min(cited.position)
+from thought.source as source
+cross join unnest(source.authors) with ordinality as cited(name, position)
+join thought.author_alias as alias on alias.alias_key = thought.name_key(cited.name)
+group by source.source_id, alias.author_id;
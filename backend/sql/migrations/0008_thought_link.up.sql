@@ -0,0 +1,17 @@
+create table thought.thought_link (
+    link_id uuid default public.uuid_generate_v4() not null,
+    from_thought_id uuid not null,
+    to_thought_id uuid not null,
+    kind text not null,
+    created_at timestamp without time zone default now() not null,
+    constraint thought_link_pkey primary key (link_id),
+    constraint thought_link_from_thought_id_fkey foreign key (from_thought_id)
+        references thought.thought (thought_id) on delete cascade,
+    constraint thought_link_to_thought_id_fkey foreign key (to_thought_id)
+        references thought.thought (thought_id) on delete cascade,
+    constraint thought_link_kind_check check (kind in ('see_also', 'contradicts', 'supersedes')),
+    constraint thought_link_not_self check (from_thought_id <> to_thought_id),
+    constraint thought_link_unique unique (from_thought_id, to_thought_id, kind)
+);
+
+create index thought_link_to_thought_id_idx on thought.thought_link (to_thought_id);
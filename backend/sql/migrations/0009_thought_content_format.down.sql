@@ -0,0 +1,42 @@
+drop trigger thought_revision on thought.thought;
+
+alter table thought.thought_revision drop column content_format;
+
+create or replace function thought.record_revision() returns trigger language plpgsql as $$
+begin
+    insert into thought.thought_revision (thought_id, revision, keywords, categories, source_ids, content)
+    select old.thought_id, coalesce(max(revision), 0) + 1, old.keywords, old.categories, old.source_ids, old.content
+    from thought.thought_revision where thought_id = old.thought_id;
+
+    return new;
+end;
+$$;
+
+create trigger thought_revision
+    after update of content, keywords, categories, source_ids on thought.thought
+    for each row
+    when ((old.content, old.keywords, old.categories, old.source_ids)
+        is distinct from (new.content, new.keywords, new.categories, new.source_ids))
+    execute function thought.record_revision();
+
+drop trigger thought_search_vector on thought.thought;
+
+create or replace function thought.update_search_vector() returns trigger language plpgsql as $$
+begin
+    new.search_vector :=
+        setweight(to_tsvector(new.search_language, coalesce(new.content #>> '{}', '')), 'A')
+        || setweight(to_tsvector(new.search_language, array_to_string(new.keywords, ' ')), 'B');
+
+    return new;
+end;
+$$;
+
+create trigger thought_search_vector
+    before insert or update of content, keywords, search_language on thought.thought
+    for each row execute function thought.update_search_vector();
+
+alter table thought.thought
+    drop column search_text,
+    drop column content_format;
+
+update thought.thought set search_language = search_language;
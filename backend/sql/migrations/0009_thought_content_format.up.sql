@@ -0,0 +1,45 @@
+alter table thought.thought
+    add column content_format text default 'plain' not null,
+    add column search_text text,
+    add constraint thought_content_format_check check (content_format in ('plain', 'markdown'));
+
+-- the search indexes the text extracted from the content when it is not plain
+create or replace function thought.update_search_vector() returns trigger language plpgsql as $$
+begin
+    new.search_vector :=
+        setweight(to_tsvector(new.search_language, coalesce(new.search_text, new.content #>> '{}', '')), 'A')
+        || setweight(to_tsvector(new.search_language, array_to_string(new.keywords, ' ')), 'B');
+
+    return new;
+end;
+$$;
+
+drop trigger thought_search_vector on thought.thought;
+
+create trigger thought_search_vector
+    before insert or update of content, search_text, keywords, search_language on thought.thought
+    for each row execute function thought.update_search_vector();
+
+-- the revisions keep the format their content was written in
+alter table thought.thought_revision
+    add column content_format text default 'plain' not null,
+    add constraint thought_revision_content_format_check check (content_format in ('plain', 'markdown'));
+
+drop trigger thought_revision on thought.thought;
+
+create or replace function thought.record_revision() returns trigger language plpgsql as $$
+begin
+    insert into thought.thought_revision (thought_id, revision, keywords, categories, source_ids, content, content_format)
+    select old.thought_id, coalesce(max(revision), 0) + 1, old.keywords, old.categories, old.source_ids, old.content, old.content_format
+    from thought.thought_revision where thought_id = old.thought_id;
+
+    return new;
+end;
+$$;
+
+create trigger thought_revision
+    after update of content, content_format, keywords, categories, source_ids on thought.thought
+    for each row
+    when ((old.content, old.content_format, old.keywords, old.categories, old.source_ids)
+        is distinct from (new.content, new.content_format, new.keywords, new.categories, new.source_ids))
+    execute function thought.record_revision();
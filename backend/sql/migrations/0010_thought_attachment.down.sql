@@ -0,0 +1 @@
+drop table thought.attachment;
@@ -0,0 +1,18 @@
+create table thought.attachment (
+    attachment_id uuid default public.uuid_generate_v4() not null,
+    thought_id uuid not null,
+    file_name text not null,
+    mime_type text not null,
+    size bigint not null,
+    hash text not null,
+    created_at timestamp without time zone default now() not null,
+    constraint attachment_pkey primary key (attachment_id),
+    constraint attachment_thought_id_fkey foreign key (thought_id)
+        references thought.thought (thought_id) on delete cascade,
+    constraint attachment_file_name_check check (file_name <> ''),
+    constraint attachment_size_check check (size >= 0),
+    constraint attachment_hash_check check (hash ~ '^[0-9a-f]{64}$')
+);
+
+create index attachment_thought_id_idx on thought.attachment (thought_id);
+create index attachment_hash_idx on thought.attachment (hash);
@@ -5,15 +5,24 @@ use flat_config::{pool::FlatPool, ConfigBuilder, ConfigError, TryUnwrap};
 
 use crate::StdResult;
 
+/// Maximum size of an uploaded attachment when none is configured, in mebibytes.
+const DEFAULT_MAX_UPLOAD_SIZE_MIB: isize = 32;
+
 pub struct BackendHttpConfig {
     http_address: IpAddr,
     http_port: u16,
+    max_upload_size: u64,
 }
 
 impl BackendHttpConfig {
     pub fn get_listen_address(&self) -> String {
         format!("{}:{}", self.http_address, self.http_port)
     }
+
+    /// Maximum size of an uploaded attachment, in bytes.
+    pub fn get_max_upload_size(&self) -> u64 {
+        self.max_upload_size
+    }
 }
 
 #[derive(Debug, Default)]
@@ -32,6 +41,20 @@ impl BackendHttpConfigBuilder {
 
         Ok(ip_address)
     }
+
+    fn build_max_upload_size(&self, config_pool: &impl FlatPool) -> Result<u64, ConfigError> {
+        let size: isize = match config_pool.require("max_upload_size") {
+            Ok(value) => value.try_unwrap()?,
+            Err(_) => DEFAULT_MAX_UPLOAD_SIZE_MIB,
+        };
+
+        match u64::try_from(size) {
+            Ok(size) if size > 0 => Ok(size * 1024 * 1024),
+            _ => Err(ConfigError::IncorrectValue(format!(
+                "MAX_UPLOAD_SIZE: invalid size '{size}', it must be a strictly positive number of MiB."
+            ))),
+        }
+    }
 }
 
 impl ConfigBuilder<BackendHttpConfig> for BackendHttpConfigBuilder {
@@ -59,12 +82,15 @@ impl ConfigBuilder<BackendHttpConfig> for BackendHttpConfigBuilder {
         Ok(BackendHttpConfig {
             http_address,
             http_port,
+            max_upload_size: self.build_max_upload_size(config_pool)?,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use flat_config::pool::SimpleFlatPool;
+
     use super::*;
 
     #[test]
@@ -96,4 +122,33 @@ mod tests {
 
         config_builder.parse_ip_address(bad_ip).unwrap_err();
     }
+
+    #[test]
+    fn max_upload_size() {
+        let mut flat_pool = SimpleFlatPool::default();
+        flat_pool
+            .add("http_address", "127.0.0.1".into())
+            .add("http_port", 8080_isize.into());
+        let config_builder = BackendHttpConfigBuilder::default();
+
+        assert_eq!(
+            32 * 1024 * 1024,
+            config_builder
+                .build(&flat_pool)
+                .unwrap()
+                .get_max_upload_size()
+        );
+
+        flat_pool.add("max_upload_size", 2_isize.into());
+        assert_eq!(
+            2 * 1024 * 1024,
+            config_builder
+                .build(&flat_pool)
+                .unwrap()
+                .get_max_upload_size()
+        );
+
+        flat_pool.add("max_upload_size", 0_isize.into());
+        assert!(config_builder.build(&flat_pool).is_err());
+    }
 }
@@ -0,0 +1,439 @@
+//! Data transfer objects exchanged with the HTTP clients
+use chrono::{DateTime, Utc};
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::thoughts::model::{
+    Attachment, Author, CategoryNode, DiffChange, Page, RevisionDiff, TextChunk, ThoughtContent,
+    ThoughtEnvelope, ThoughtLink, ThoughtRevision, ThoughtSearchResult, ThoughtSource, ThoughtTree,
+    ThreadMerge, ThreadSummary,
+};
+
+/// Content of a thought, tagged with its `type`.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThoughtContentDto {
+    /// Root of a thread.
+    Thread { title: String },
+    /// Thought answering another one.
+    Node {
+        parent_thought_id: Uuid,
+        thought: String,
+    },
+}
+
+impl From<ThoughtContent> for ThoughtContentDto {
+    fn from(value: ThoughtContent) -> Self {
+        match value {
+            ThoughtContent::Thread { title } => Self::Thread { title },
+            ThoughtContent::Node {
+                parent_thought_id,
+                thought,
+            } => Self::Node {
+                parent_thought_id,
+                thought,
+            },
+        }
+    }
+}
+
+/// Thought as exposed by the API.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThoughtDto {
+    pub thought_id: Uuid,
+    /// None when the thought is the root of a thread.
+    pub parent_thought_id: Option<Uuid>,
+    /// Content of a node or title of a thread.
+    pub text: String,
+    pub content: ThoughtContentDto,
+    /// Format of the text, `plain` or `markdown`.
+    pub content_format: String,
+    /// Text rendered as sanitized HTML.
+    pub html: String,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub source_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ThoughtEnvelope> for ThoughtDto {
+    fn from(value: ThoughtEnvelope) -> Self {
+        Self {
+            thought_id: value.thought_id,
+            parent_thought_id: value.parent_thought_id(),
+            text: value.text().to_string(),
+            content_format: value.content_format.as_str().to_string(),
+            html: value.html(),
+            content: value.content.into(),
+            keywords: value.keywords,
+            categories: value.categories,
+            source_ids: value.source_ids,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// Thought sent to `POST /thoughts`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewThoughtDto {
+    /// Generated when not given.
+    pub thought_id: Option<Uuid>,
+    /// A new thread is created when no parent is given, the text is its title.
+    pub parent_thought_id: Option<Uuid>,
+    pub text: String,
+    /// `plain` when not given, or `markdown`.
+    pub content_format: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub source_ids: Vec<Uuid>,
+}
+
+/// New version of a thought sent to `PUT /thoughts/<thought_id>`. The thought stays where it is
+/// in its thread.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ThoughtUpdateDto {
+    pub text: String,
+    /// `plain` when not given, or `markdown`.
+    pub content_format: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub source_ids: Vec<Uuid>,
+}
+
+/// Thread root with its activity.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadSummaryDto {
+    #[serde(flatten)]
+    pub thread: ThoughtDto,
+    pub last_activity: DateTime<Utc>,
+    pub thought_count: u64,
+}
+
+impl From<ThreadSummary> for ThreadSummaryDto {
+    fn from(value: ThreadSummary) -> Self {
+        Self {
+            thread: value.thread.into(),
+            last_activity: value.last_activity,
+            thought_count: value.thought_count,
+        }
+    }
+}
+
+/// Page of a listing, `next_cursor` is given to get the next page when there is one.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PageDto<T: ToSchema + 'static> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T: ToSchema + 'static, U: Into<T>> From<Page<U>> for PageDto<T> {
+    fn from(value: Page<U>) -> Self {
+        Self {
+            items: value.items.into_iter().map(|item| item.into()).collect(),
+            next_cursor: value.next_cursor,
+        }
+    }
+}
+
+/// Thought matching a search query.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThoughtSearchResultDto {
+    pub thought_id: Uuid,
+    pub parent_thought_id: Option<Uuid>,
+    pub rank: f32,
+    /// HTML excerpt of the thought, the text is escaped and the matching terms are enclosed in
+    /// `<mark>` tags.
+    pub snippet: String,
+}
+
+impl From<ThoughtSearchResult> for ThoughtSearchResultDto {
+    fn from(value: ThoughtSearchResult) -> Self {
+        Self {
+            thought_id: value.thought.thought_id,
+            parent_thought_id: value.thought.parent_thought_id(),
+            rank: value.rank,
+            snippet: value.snippet,
+        }
+    }
+}
+
+/// Node of the category tree.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CategoryNodeDto {
+    pub name: String,
+    pub path: String,
+    /// Number of thoughts filed under this category or one of its sub categories.
+    pub thought_count: u64,
+    pub children: Vec<CategoryNodeDto>,
+}
+
+impl From<CategoryNode> for CategoryNodeDto {
+    fn from(value: CategoryNode) -> Self {
+        Self {
+            name: value.name,
+            path: value.path,
+            thought_count: value.thought_count,
+            children: value.children.into_iter().map(|c| c.into()).collect(),
+        }
+    }
+}
+
+/// Registered source cited by thoughts.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SourceDto {
+    pub source_id: Uuid,
+    pub name: String,
+    /// Authors as cited by the source.
+    pub authors: Vec<String>,
+    pub description: String,
+    /// Indexed authors of the source.
+    pub author_ids: Vec<Uuid>,
+}
+
+impl From<ThoughtSource> for SourceDto {
+    fn from(value: ThoughtSource) -> Self {
+        Self {
+            source_id: value.source_id,
+            name: value.name,
+            authors: value.authors,
+            description: value.description,
+            author_ids: value.author_ids,
+        }
+    }
+}
+
+/// Source sent to `POST /sources`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewSourceDto {
+    pub name: String,
+    /// Authors as cited by the source.
+    #[serde(default)]
+    pub authors: Vec<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Author indexed across the sources.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthorDto {
+    pub author_id: Uuid,
+    pub name: String,
+    /// Names the author is known by, its name included.
+    pub aliases: Vec<String>,
+}
+
+impl From<Author> for AuthorDto {
+    fn from(value: Author) -> Self {
+        Self {
+            author_id: value.author_id,
+            name: value.name,
+            aliases: value.aliases,
+        }
+    }
+}
+
+/// Thought with its children, nested.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThoughtTreeDto {
+    #[serde(flatten)]
+    pub thought: ThoughtDto,
+    pub children: Vec<ThoughtTreeDto>,
+    /// True when the thought has children left out by the depth limit.
+    pub truncated: bool,
+}
+
+impl From<ThoughtTree> for ThoughtTreeDto {
+    fn from(value: ThoughtTree) -> Self {
+        Self {
+            thought: value.thought.into(),
+            children: value.children.into_iter().map(|c| c.into()).collect(),
+            truncated: value.truncated,
+        }
+    }
+}
+
+/// Changes made by a thread merge.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadMergeDto {
+    pub updated_thought_ids: Vec<Uuid>,
+    pub deleted_thought_id: Option<Uuid>,
+    pub redirected_link_ids: Vec<Uuid>,
+    pub removed_link_ids: Vec<Uuid>,
+    pub moved_attachment_ids: Vec<Uuid>,
+}
+
+impl From<ThreadMerge> for ThreadMergeDto {
+    fn from(value: ThreadMerge) -> Self {
+        Self {
+            updated_thought_ids: value.updated_thought_ids,
+            deleted_thought_id: value.deleted_thought_id,
+            redirected_link_ids: value.redirected_link_ids,
+            removed_link_ids: value.removed_link_ids,
+            moved_attachment_ids: value.moved_attachment_ids,
+        }
+    }
+}
+
+/// Typed link between two thoughts.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThoughtLinkDto {
+    pub link_id: Uuid,
+    pub from_thought_id: Uuid,
+    pub to_thought_id: Uuid,
+    /// One of `see_also`, `contradicts` or `supersedes`.
+    pub kind: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ThoughtLink> for ThoughtLinkDto {
+    fn from(value: ThoughtLink) -> Self {
+        Self {
+            link_id: value.link_id,
+            from_thought_id: value.from_thought_id,
+            to_thought_id: value.to_thought_id,
+            kind: value.kind.as_str().to_string(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// File attached to a thought, its content is downloaded from `/attachments/<attachment_id>`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttachmentDto {
+    pub attachment_id: Uuid,
+    pub thought_id: Uuid,
+    pub file_name: String,
+    pub mime_type: String,
+    /// Size in bytes.
+    pub size: i64,
+    /// SHA-256 hash of the content.
+    pub hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Attachment> for AttachmentDto {
+    fn from(value: Attachment) -> Self {
+        Self {
+            attachment_id: value.attachment_id,
+            thought_id: value.thought_id,
+            file_name: value.file_name,
+            mime_type: value.mime_type,
+            size: value.size,
+            hash: value.hash,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// Multipart form sent to `POST /thoughts/<thought_id>/attachments`. It only documents the
+/// upload, the file is read from the request as it is spooled.
+#[derive(Debug, ToSchema)]
+pub struct AttachmentUploadDto {
+    /// Content of the file, its name and MIME type are those of the form part.
+    #[salvo(schema(format = Binary))]
+    pub file: String,
+}
+
+/// Version of a thought.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThoughtRevisionDto {
+    pub thought_id: Uuid,
+    pub revision: u32,
+    pub text: String,
+    /// Format of the text, `plain` or `markdown`.
+    pub content_format: String,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub source_ids: Vec<Uuid>,
+    pub written_at: DateTime<Utc>,
+    /// None for the current version.
+    pub superseded_at: Option<DateTime<Utc>>,
+}
+
+impl From<ThoughtRevision> for ThoughtRevisionDto {
+    fn from(value: ThoughtRevision) -> Self {
+        Self {
+            thought_id: value.thought_id,
+            revision: value.revision,
+            text: value.text,
+            content_format: value.content_format.as_str().to_string(),
+            keywords: value.keywords,
+            categories: value.categories,
+            source_ids: value.source_ids,
+            written_at: value.written_at,
+            superseded_at: value.superseded_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffChangeDto {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+impl From<DiffChange> for DiffChangeDto {
+    fn from(value: DiffChange) -> Self {
+        match value {
+            DiffChange::Unchanged => Self::Unchanged,
+            DiffChange::Added => Self::Added,
+            DiffChange::Removed => Self::Removed,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TextChunkDto {
+    pub change: DiffChangeDto,
+    pub text: String,
+}
+
+impl From<TextChunk> for TextChunkDto {
+    fn from(value: TextChunk) -> Self {
+        Self {
+            change: value.change.into(),
+            text: value.text,
+        }
+    }
+}
+
+/// Differences between two versions of a thought.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevisionDiffDto {
+    pub thought_id: Uuid,
+    pub from_revision: u32,
+    pub to_revision: u32,
+    pub text: Vec<TextChunkDto>,
+    pub added_keywords: Vec<String>,
+    pub removed_keywords: Vec<String>,
+    pub added_categories: Vec<String>,
+    pub removed_categories: Vec<String>,
+    pub added_source_ids: Vec<Uuid>,
+    pub removed_source_ids: Vec<Uuid>,
+}
+
+impl From<RevisionDiff> for RevisionDiffDto {
+    fn from(value: RevisionDiff) -> Self {
+        Self {
+            thought_id: value.thought_id,
+            from_revision: value.from_revision,
+            to_revision: value.to_revision,
+            text: value.text.into_iter().map(|c| c.into()).collect(),
+            added_keywords: value.added_keywords,
+            removed_keywords: value.removed_keywords,
+            added_categories: value.added_categories,
+            removed_categories: value.removed_categories,
+            added_source_ids: value.added_source_ids,
+            removed_source_ids: value.removed_source_ids,
+        }
+    }
+}
@@ -1,6 +1,12 @@
 //! HTTP server
 mod config;
+mod dto;
+mod problem;
 mod runtime;
+mod upload;
 
 pub use config::*;
+pub use dto::*;
+pub use problem::*;
 pub use runtime::*;
+pub use upload::*;
@@ -5,20 +5,51 @@ use anyhow::anyhow;
 use anyhow::Context;
 use log::{debug, info};
 use salvo::affix;
+use salvo::fs::NamedFile;
+use salvo::http::{
+    header::{CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+    mime::Mime,
+    HeaderValue,
+};
+use salvo::oapi::{endpoint, swagger_ui::SwaggerUi, OpenApi, ToSchema};
 use salvo::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{ServicesContainer, StdResult};
+use crate::{
+    thoughts::{
+        model::{ContentFormat, SiblingOrder, ThreadMergeMode, ThreadOrder},
+        ThoughtDraft, MAX_PAGE_SIZE, MAX_SEARCH_RESULTS,
+    },
+    ServicesContainer, StdResult,
+};
 
-use super::BackendHttpConfig;
+use super::{
+    ApiError, AttachmentDto, AttachmentUploadDto, AuthorDto, BackendHttpConfig, CategoryNodeDto,
+    LimitedUpload, NewSourceDto, NewThoughtDto, PageDto, ProblemDetails, RequestError,
+    RevisionDiffDto, SourceDto, ThoughtDto, ThoughtLinkDto, ThoughtRevisionDto,
+    ThoughtSearchResultDto, ThoughtTreeDto, ThoughtUpdateDto, ThreadMergeDto, ThreadSummaryDto,
+    UploadLimit,
+};
+
+/// Number of search results returned when no limit is given.
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Number of items in a listing page when no limit is given.
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Route of the OpenAPI specification of the API.
+const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// Route of the interactive documentation of the API.
+const API_DOCS_PATH: &str = "/api/docs";
 
 pub struct BackendHttpRuntime {
     config: Arc<BackendHttpConfig>,
     services_container: Arc<ServicesContainer>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ApiVersion {
     version: String,
 }
@@ -31,31 +62,1215 @@ impl Default for ApiVersion {
     }
 }
 
-#[handler]
+/// Read the `limit` query parameter, capped to the given maximum. The default is used when it
+/// is not given, an error is returned if it is not a strictly positive number.
+fn get_limit(request: &Request, default: usize, max: usize) -> Result<usize, RequestError> {
+    match request.queries().get("limit") {
+        None => Ok(default),
+        Some(limit) => match limit.parse::<usize>() {
+            Ok(limit) if limit > 0 => Ok(limit.min(max)),
+            _ => Err(RequestError::InvalidParameter(format!(
+                "Invalid limit '{limit}', expected a strictly positive number."
+            ))),
+        },
+    }
+}
+
+/// Read the `thought_id` path parameter. Like the services do, an identifier that cannot be
+/// parsed points to nothing.
+fn get_thought_id(request: &mut Request) -> Result<Uuid, RequestError> {
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+
+    Uuid::parse_str(&thought_id)
+        .map_err(|_| RequestError::NotFound(format!("No thought found for ID='{thought_id}'.")))
+}
+
+/// Get the services container injected in the depot.
+fn get_services(depot: &Depot) -> StdResult<Arc<ServicesContainer>> {
+    depot
+        .obtain::<Arc<ServicesContainer>>()
+        .cloned()
+        .map_err(|_| anyhow!("Could not obtain services container.".to_string()))
+}
+
+/// Version of the API: `GET /`
+#[endpoint(
+    tags("root"),
+    responses((status_code = 200, description = "Version of the API.", body = ApiVersion))
+)]
 async fn index(
     _request: &mut Request,
-    depot: &mut Depot,
+    _depot: &mut Depot,
     response: &mut Response,
-) -> StdResult<()> {
+) -> Result<(), ApiError> {
     info!("ROUTE: index ('/').");
-    let services = depot
-        .obtain::<Arc<ServicesContainer>>()
-        .map_err(|_| anyhow!("Could not obtain services container.".to_string()))?
-        .clone();
-    let thought_id = Uuid::parse_str("40b5b09f-04d3-4340-b794-c4afe9b4f6d1")?;
-    let thought = services.thought_service.get_thought(&thought_id).await?;
+    response.render(Json(ApiVersion::default()));
+
+    Ok(())
+}
+
+/// Read the content format of a thought sent by a client, `plain` when none is given. None is
+/// returned if the format is unknown.
+fn get_content_format(content_format: Option<&str>) -> Option<ContentFormat> {
+    match content_format {
+        Some(content_format) => ContentFormat::parse(content_format),
+        None => Some(ContentFormat::default()),
+    }
+}
+
+/// One thought: `GET /thoughts/<thought_id>`
+#[endpoint(
+    tags("thoughts"),
+    parameters(("thought_id" = Uuid, Path, description = "Identifier of the thought")),
+    responses((status_code = 200, description = "The thought.", body = ThoughtDto))
+)]
+async fn get_thought(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought ('/thoughts/<thought_id>').");
+    let services = get_services(depot)?;
+    let thought_id = get_thought_id(request)?;
+    let thought: Option<ThoughtDto> = services
+        .thought_service
+        .get_thought(&thought_id)
+        .await?
+        .map(|t| t.into());
+    render_or_not_found(response, thought);
 
-    match thought {
-        Some(t) => {
-            debug!("Found thought ID='{}'.", t.thought_id);
-            response.render(format!("There is a thought: {t:?}"));
+    Ok(())
+}
+
+/// Create a thought: `POST /thoughts` with a [NewThoughtDto] JSON body. A thread is created
+/// when no parent is given. The created thought is returned.
+#[endpoint(
+    tags("thoughts"),
+    request_body = NewThoughtDto,
+    status_codes(
+        StatusCode::CREATED,
+        StatusCode::BAD_REQUEST,
+        StatusCode::CONFLICT,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        StatusCode::SERVICE_UNAVAILABLE
+    ),
+    responses(
+        (status_code = 201, description = "The created thought.", body = ThoughtDto),
+        (status_code = 400, description = "The body is not a thought.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status_code = 409, description = "A thought with this identifier already exists.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status_code = 422, description = "The content format is unknown or the parent or a cited source does not exist.", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn create_thought(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thoughts ('/thoughts').");
+    let services = get_services(depot)?;
+    let thought = match request.parse_json::<NewThoughtDto>().await {
+        Ok(thought) => thought,
+        Err(e) => return Err(RequestError::MalformedBody(e.to_string()).into()),
+    };
+    let content_format = match get_content_format(thought.content_format.as_deref()) {
+        Some(content_format) => content_format,
+        None => {
+            return Err(RequestError::InvalidParameter(
+                "Invalid content format, expected 'plain' or 'markdown'.".to_string(),
+            )
+            .into())
+        }
+    };
+    let thought_id = thought.thought_id.unwrap_or_else(Uuid::new_v4);
+    let created = services
+        .thought_service
+        .create_thought(
+            thought_id.to_string(),
+            thought.parent_thought_id.map(|id| id.to_string()),
+            ThoughtDraft {
+                content: thought.text,
+                content_format,
+                keywords: thought.keywords,
+                categories: thought.categories,
+                source_ids: thought.source_ids.iter().map(|id| id.to_string()).collect(),
+            },
+        )
+        .await?;
+    response.status_code(StatusCode::CREATED);
+    response.render(Json(ThoughtDto::from(created)));
+
+    Ok(())
+}
+
+/// Update a thought: `PUT /thoughts/<thought_id>` with a [ThoughtUpdateDto] JSON body. The
+/// updated thought is returned.
+#[endpoint(
+    tags("thoughts"),
+    parameters(("thought_id" = Uuid, Path, description = "Identifier of the thought")),
+    request_body = ThoughtUpdateDto,
+    responses((status_code = 200, description = "The updated thought.", body = ThoughtDto))
+)]
+async fn update_thought(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought ('/thoughts/<thought_id>').");
+    let services = get_services(depot)?;
+    let thought_id = get_thought_id(request)?;
+    let update = match request.parse_json::<ThoughtUpdateDto>().await {
+        Ok(update) => update,
+        Err(e) => return Err(RequestError::MalformedBody(e.to_string()).into()),
+    };
+    let content_format = match get_content_format(update.content_format.as_deref()) {
+        Some(content_format) => content_format,
+        None => {
+            return Err(RequestError::InvalidParameter(
+                "Invalid content format, expected 'plain' or 'markdown'.".to_string(),
+            )
+            .into())
+        }
+    };
+    let current = match services.thought_service.get_thought(&thought_id).await? {
+        Some(current) => current,
+        None => {
+            return Err(
+                RequestError::NotFound(format!("No thought found for ID='{thought_id}'.")).into(),
+            )
+        }
+    };
+    let updated = services
+        .thought_service
+        .post_thought(
+            thought_id.to_string(),
+            current.parent_thought_id().map(|id| id.to_string()),
+            ThoughtDraft {
+                content: update.text,
+                content_format,
+                keywords: update.keywords,
+                categories: update.categories,
+                source_ids: update.source_ids.iter().map(|id| id.to_string()).collect(),
+            },
+        )
+        .await?;
+    response.render(Json(ThoughtDto::from(updated)));
+
+    Ok(())
+}
+
+/// Thread of a thought, from its root down to the thought: `GET /thoughts/<thought_id>/thread`
+#[endpoint(
+    tags("threads"),
+    parameters(("thought_id" = Uuid, Path, description = "Identifier of the thought")),
+    responses(
+        (
+            status_code = 200,
+            description = "Thoughts from the root of the thread down to the thought.",
+            body = Vec<ThoughtDto>
+        )
+    )
+)]
+async fn get_thought_thread(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought thread ('/thoughts/<thought_id>/thread').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let thread = services.thought_service.get_thread(&thought_id).await?;
+    let thread: Option<Vec<ThoughtDto>> =
+        thread.map(|thoughts| thoughts.into_iter().map(|t| t.into()).collect());
+    render_or_not_found(response, thread);
+
+    Ok(())
+}
+
+/// Threads, most recent first:
+/// `GET /threads?order=<created_at|last_activity>&cursor=<cursor>&limit=20`. The cursor of
+/// the next page is given with each page. Threads ordered by last activity move as thoughts
+/// are added, that order is not paged and only its first page is listed.
+#[endpoint(
+    tags("threads"),
+    parameters(
+        (
+            "order" = Option<String>,
+            Query,
+            description = "`created_at` (default) or `last_activity`"
+        ),
+        (
+            "cursor" = Option<String>,
+            Query,
+            description = "Cursor of the page, given with the previous page, `created_at` order only"
+        ),
+        ("limit" = Option<usize>, Query, description = "Number of threads in the page"),
+    ),
+    responses((status_code = 200, description = "Page of threads.", body = PageDto<ThreadSummaryDto>))
+)]
+async fn list_threads(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: threads ('/threads').");
+    let services = get_services(depot)?;
+    let order = match request.query::<String>("order") {
+        Some(order) => ThreadOrder::parse(&order),
+        None => Some(ThreadOrder::default()),
+    };
+    let order = match order {
+        Some(order) => order,
+        None => {
+            return Err(RequestError::InvalidParameter(
+                "Invalid thread order, expected 'created_at' or 'last_activity'.".to_string(),
+            )
+            .into())
+        }
+    };
+    let cursor = request.query::<String>("cursor");
+    let limit = get_limit(request, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE)?;
+    let page = services
+        .thought_service
+        .list_threads(order, cursor.as_deref(), limit)
+        .await?;
+    let page: PageDto<ThreadSummaryDto> = page.into();
+    debug!("Listed {} threads.", page.items.len());
+    response.render(Json(page));
+
+    Ok(())
+}
+
+/// Full text search: `GET /thoughts/search?q=terms&limit=20`
+#[endpoint(
+    tags("thoughts"),
+    parameters(
+        ("q" = Option<String>, Query, description = "Search terms"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of results"),
+    ),
+    responses(
+        (
+            status_code = 200,
+            description = "Matching thoughts, the most relevant first.",
+            body = Vec<ThoughtSearchResultDto>
+        )
+    )
+)]
+async fn search_thoughts(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: search thoughts ('/thoughts/search').");
+    let services = get_services(depot)?;
+    let query = match request.query::<String>("q") {
+        Some(query) if !query.trim().is_empty() => query,
+        _ => {
+            return Err(RequestError::InvalidParameter("No search query given.".to_string()).into())
+        }
+    };
+    let limit = get_limit(request, DEFAULT_SEARCH_LIMIT, MAX_SEARCH_RESULTS)?;
+    let results: Vec<ThoughtSearchResultDto> = services
+        .thought_service
+        .search(&query, limit)
+        .await?
+        .into_iter()
+        .map(|result| result.into())
+        .collect();
+    debug!("Found {} thoughts matching '{query}'.", results.len());
+    response.render(Json(results));
+
+    Ok(())
+}
+
+/// Category tree: `GET /categories`
+#[endpoint(
+    tags("categories"),
+    responses(
+        (
+            status_code = 200,
+            description = "Root categories with their sub categories.",
+            body = Vec<CategoryNodeDto>
+        )
+    )
+)]
+async fn get_categories(
+    _request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: categories ('/categories').");
+    let services = get_services(depot)?;
+    let tree: Vec<CategoryNodeDto> = services
+        .thought_service
+        .get_category_tree()
+        .await?
+        .into_iter()
+        .map(|node| node.into())
+        .collect();
+    response.render(Json(tree));
+
+    Ok(())
+}
+
+/// Thoughts of a category and its sub categories: `GET /categories/<path>/thoughts`. When the
+/// `match` parameter is given, it is used as an `lquery` pattern instead of the path:
+/// `GET /categories/_/thoughts?match=*.stoicism.*`
+#[endpoint(
+    tags("categories"),
+    parameters(
+        ("path" = String, Path, description = "Category path"),
+        (
+            "match" = Option<String>,
+            Query,
+            description = "`lquery` pattern used instead of the path"
+        ),
+    ),
+    responses(
+        (status_code = 200, description = "Thoughts of the category.", body = Vec<ThoughtDto>)
+    )
+)]
+async fn get_category_thoughts(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: category thoughts ('/categories/<path>/thoughts').");
+    let services = get_services(depot)?;
+    let result = match request.query::<String>("match") {
+        Some(pattern) => {
+            services
+                .thought_service
+                .match_category_thoughts(&pattern)
+                .await
         }
         None => {
-            debug!("No thought found for ID='{thought_id}'.");
-            response.status_code(StatusCode::NOT_FOUND);
+            let path = request.param::<String>("path").unwrap_or_default();
+
+            services.thought_service.get_category_thoughts(&path).await
         }
+    };
+    let thoughts = result?;
+    let thoughts: Vec<ThoughtDto> = thoughts.into_iter().map(|t| t.into()).collect();
+    debug!("Found {} thoughts in category.", thoughts.len());
+    response.render(Json(thoughts));
+
+    Ok(())
+}
+
+/// Render the given value as JSON or answer 404 if there is none.
+fn render_or_not_found<T: Serialize + Send>(response: &mut Response, value: Option<T>) {
+    match value {
+        Some(value) => response.render(Json(value)),
+        None => response.render(ProblemDetails::new(StatusCode::NOT_FOUND, None)),
     }
-    // response.render(serde_json::to_string(&ApiVersion::default()).unwrap());
+}
+
+/// Read the `depth` and `order` parameters of the tree routes. The depth is capped by the
+/// service when too deep or not given, the order is `oldest` or `newest`. None is returned if
+/// the order is invalid.
+fn get_tree_options(request: &mut Request) -> Option<(Option<u32>, SiblingOrder)> {
+    let order = match request.query::<String>("order") {
+        Some(order) => SiblingOrder::parse(&order)?,
+        None => SiblingOrder::default(),
+    };
+
+    Some((request.query::<u32>("depth"), order))
+}
+
+/// Tree of the thread a thought belongs to:
+/// `GET /thoughts/<thought_id>/tree?depth=<depth>&order=<oldest|newest>`
+#[endpoint(
+    tags("threads"),
+    parameters(
+        ("thought_id" = Uuid, Path, description = "Identifier of the thought"),
+        ("depth" = Option<u32>, Query, description = "Number of levels below the root, at most 100"),
+        ("order" = Option<String>, Query, description = "`oldest` (default) or `newest` first"),
+    ),
+    responses((status_code = 200, description = "Tree of the thread.", body = ThoughtTreeDto))
+)]
+async fn get_thread_tree(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thread tree ('/thoughts/<thought_id>/tree').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let (max_depth, order) = match get_tree_options(request) {
+        Some(options) => options,
+        None => {
+            return Err(RequestError::InvalidParameter(
+                "Invalid sibling order, expected 'oldest' or 'newest'.".to_string(),
+            )
+            .into())
+        }
+    };
+    let tree: Option<ThoughtTreeDto> = services
+        .thought_service
+        .get_thread_tree(&thought_id, max_depth, order)
+        .await?
+        .map(|t| t.into());
+    render_or_not_found(response, tree);
+
+    Ok(())
+}
+
+/// Tree below a thought:
+/// `GET /thoughts/<thought_id>/subtree?depth=<depth>&order=<oldest|newest>`
+#[endpoint(
+    tags("threads"),
+    parameters(
+        ("thought_id" = Uuid, Path, description = "Identifier of the thought"),
+        ("depth" = Option<u32>, Query, description = "Number of levels below the thought, at most 100"),
+        ("order" = Option<String>, Query, description = "`oldest` (default) or `newest` first"),
+    ),
+    responses((status_code = 200, description = "Tree below the thought.", body = ThoughtTreeDto))
+)]
+async fn get_subtree(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: subtree ('/thoughts/<thought_id>/subtree').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let (max_depth, order) = match get_tree_options(request) {
+        Some(options) => options,
+        None => {
+            return Err(RequestError::InvalidParameter(
+                "Invalid sibling order, expected 'oldest' or 'newest'.".to_string(),
+            )
+            .into())
+        }
+    };
+    let tree: Option<ThoughtTreeDto> = services
+        .thought_service
+        .get_subtree(&thought_id, max_depth, order)
+        .await?
+        .map(|t| t.into());
+    render_or_not_found(response, tree);
+
+    Ok(())
+}
+
+/// Merge a thread into the thread of another thought:
+/// `POST /thoughts/<thread_id>/merge?into=<thought_id>&mode=<graft|combine>`. The changes made
+/// are returned.
+#[endpoint(
+    tags("threads"),
+    parameters(
+        ("thread_id" = Uuid, Path, description = "Root of the merged thread"),
+        ("into" = Option<Uuid>, Query, description = "Thought to merge the thread into"),
+        ("mode" = Option<String>, Query, description = "`graft` (default) or `combine`"),
+    ),
+    responses(
+        (status_code = 200, description = "Changes made by the merge.", body = ThreadMergeDto)
+    )
+)]
+async fn merge_threads(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: merge threads ('/thoughts/<thread_id>/merge').");
+    let services = get_services(depot)?;
+    let thread_id = request.param::<String>("thread_id").unwrap_or_default();
+    let into_thought_id = request.query::<String>("into");
+    let mode = match request.query::<String>("mode") {
+        Some(mode) => ThreadMergeMode::parse(&mode),
+        None => Some(ThreadMergeMode::default()),
+    };
+    let (into_thought_id, mode) = match (into_thought_id, mode) {
+        (Some(into_thought_id), Some(mode)) => (into_thought_id, mode),
+        _ => {
+            return Err(RequestError::InvalidParameter(
+                "A thought to merge into and a mode, 'graft' or 'combine', must be given."
+                    .to_string(),
+            )
+            .into())
+        }
+    };
+    let merge = services
+        .thought_service
+        .merge_threads(&thread_id, &into_thought_id, mode)
+        .await?;
+    render_or_not_found(response, merge.map(ThreadMergeDto::from));
+
+    Ok(())
+}
+
+/// All the versions of a thought: `GET /thoughts/<thought_id>/revisions`
+#[endpoint(
+    tags("revisions"),
+    parameters(("thought_id" = Uuid, Path, description = "Identifier of the thought")),
+    responses(
+        (
+            status_code = 200,
+            description = "Versions of the thought, the oldest first.",
+            body = Vec<ThoughtRevisionDto>
+        )
+    )
+)]
+async fn get_thought_revisions(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought revisions ('/thoughts/<thought_id>/revisions').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let revisions: Option<Vec<ThoughtRevisionDto>> = services
+        .thought_service
+        .get_revisions(&thought_id)
+        .await?
+        .map(|revisions| revisions.into_iter().map(|r| r.into()).collect());
+    render_or_not_found(response, revisions);
+
+    Ok(())
+}
+
+/// One version of a thought: `GET /thoughts/<thought_id>/revisions/<revision>`
+#[endpoint(
+    tags("revisions"),
+    parameters(
+        ("thought_id" = Uuid, Path, description = "Identifier of the thought"),
+        ("revision" = u32, Path, description = "Revision number, from 1"),
+    ),
+    responses(
+        (status_code = 200, description = "The version of the thought.", body = ThoughtRevisionDto)
+    )
+)]
+async fn get_thought_revision(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought revision ('/thoughts/<thought_id>/revisions/<revision>').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let revision = match request.param::<u32>("revision") {
+        Some(revision) => revision,
+        None => {
+            return Err(
+                RequestError::InvalidParameter("Invalid revision number.".to_string()).into(),
+            )
+        }
+    };
+    let revision: Option<ThoughtRevisionDto> = services
+        .thought_service
+        .get_revision(&thought_id, revision)
+        .await?
+        .map(|r| r.into());
+    render_or_not_found(response, revision);
+
+    Ok(())
+}
+
+/// Differences between two versions of a thought:
+/// `GET /thoughts/<thought_id>/revisions/diff?from=1&to=2`
+#[endpoint(
+    tags("revisions"),
+    parameters(
+        ("thought_id" = Uuid, Path, description = "Identifier of the thought"),
+        ("from" = Option<u32>, Query, description = "Older revision"),
+        ("to" = Option<u32>, Query, description = "Newer revision"),
+    ),
+    responses(
+        (
+            status_code = 200,
+            description = "Differences between the revisions.",
+            body = RevisionDiffDto
+        )
+    )
+)]
+async fn diff_thought_revisions(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought revisions diff ('/thoughts/<thought_id>/revisions/diff').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let (from, to) = match (request.query::<u32>("from"), request.query::<u32>("to")) {
+        (Some(from), Some(to)) => (from, to),
+        _ => {
+            return Err(RequestError::InvalidParameter(
+                "Both 'from' and 'to' revisions must be given.".to_string(),
+            )
+            .into())
+        }
+    };
+    let diff: Option<RevisionDiffDto> = services
+        .thought_service
+        .diff_revisions(&thought_id, from, to)
+        .await?
+        .map(|d| d.into());
+    render_or_not_found(response, diff);
+
+    Ok(())
+}
+
+/// Registered sources: `GET /sources`
+#[endpoint(
+    tags("sources"),
+    responses((status_code = 200, description = "Registered sources.", body = Vec<SourceDto>))
+)]
+async fn get_sources(
+    _request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: sources ('/sources').");
+    let services = get_services(depot)?;
+    let sources: Vec<SourceDto> = services
+        .thought_service
+        .get_sources()
+        .await?
+        .into_iter()
+        .map(|s| s.into())
+        .collect();
+    response.render(Json(sources));
+
+    Ok(())
+}
+
+/// Register a source: `POST /sources` with a [NewSourceDto] JSON body. If a registered source
+/// has the same name and authors, ignoring case, punctuation and the order of the authors, it is
+/// returned and nothing is created. Otherwise the new source is returned.
+#[endpoint(
+    tags("sources"),
+    request_body = NewSourceDto,
+    responses((status_code = 200, description = "The registered source.", body = SourceDto))
+)]
+async fn register_source(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: sources ('/sources').");
+    let services = get_services(depot)?;
+    let source = match request.parse_json::<NewSourceDto>().await {
+        Ok(source) => source,
+        Err(e) => return Err(RequestError::MalformedBody(e.to_string()).into()),
+    };
+    let registered = services
+        .thought_service
+        .register_source(source.name, source.authors, source.description)
+        .await?;
+    response.render(Json(SourceDto::from(registered)));
+
+    Ok(())
+}
+
+/// Groups of sources looking like duplicates: `GET /sources/duplicates`
+#[endpoint(
+    tags("sources"),
+    responses(
+        (
+            status_code = 200,
+            description = "Groups of sources looking like duplicates.",
+            body = Vec<Vec<SourceDto>>
+        )
+    )
+)]
+async fn get_duplicate_sources(
+    _request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: duplicate sources ('/sources/duplicates').");
+    let services = get_services(depot)?;
+    let groups: Vec<Vec<SourceDto>> = services
+        .thought_service
+        .find_duplicate_sources()
+        .await?
+        .into_iter()
+        .map(|group| group.into_iter().map(|s| s.into()).collect())
+        .collect();
+    debug!("Found {} groups of duplicate sources.", groups.len());
+    response.render(Json(groups));
+
+    Ok(())
+}
+
+/// One registered source: `GET /sources/<source_id>`
+#[endpoint(
+    tags("sources"),
+    parameters(("source_id" = Uuid, Path, description = "Identifier of the source")),
+    responses((status_code = 200, description = "The source.", body = SourceDto))
+)]
+async fn get_source(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: source ('/sources/<source_id>').");
+    let services = get_services(depot)?;
+    let source_id = request.param::<String>("source_id").unwrap_or_default();
+    let source: Option<SourceDto> = services
+        .thought_service
+        .get_source(&source_id)
+        .await?
+        .map(|s| s.into());
+    render_or_not_found(response, source);
+
+    Ok(())
+}
+
+/// Thoughts citing a source: `GET /sources/<source_id>/thoughts`
+#[endpoint(
+    tags("sources"),
+    parameters(("source_id" = Uuid, Path, description = "Identifier of the source")),
+    responses(
+        (status_code = 200, description = "Thoughts citing the source.", body = Vec<ThoughtDto>)
+    )
+)]
+async fn get_source_thoughts(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: source thoughts ('/sources/<source_id>/thoughts').");
+    let services = get_services(depot)?;
+    let source_id = request.param::<String>("source_id").unwrap_or_default();
+    let thoughts: Option<Vec<ThoughtDto>> = services
+        .thought_service
+        .get_source_thoughts(&source_id)
+        .await?
+        .map(|thoughts| thoughts.into_iter().map(|t| t.into()).collect());
+    render_or_not_found(response, thoughts);
+
+    Ok(())
+}
+
+/// Merge a source into another one: `POST /sources/<source_id>/merge?into=<source_id>`. The
+/// kept source is returned.
+#[endpoint(
+    tags("sources"),
+    parameters(
+        ("source_id" = Uuid, Path, description = "Identifier of the absorbed source"),
+        ("into" = Option<Uuid>, Query, description = "Identifier of the kept source"),
+    ),
+    responses((status_code = 200, description = "The kept source.", body = SourceDto))
+)]
+async fn merge_sources(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: merge sources ('/sources/<source_id>/merge').");
+    let services = get_services(depot)?;
+    let source_id = request.param::<String>("source_id").unwrap_or_default();
+    let into_source_id = match request.query::<String>("into") {
+        Some(into_source_id) => into_source_id,
+        None => {
+            return Err(RequestError::InvalidParameter(
+                "No source to merge into given.".to_string(),
+            )
+            .into())
+        }
+    };
+    let kept = services
+        .thought_service
+        .merge_sources(&source_id, &into_source_id)
+        .await?;
+    render_or_not_found(response, kept.map(SourceDto::from));
+
+    Ok(())
+}
+
+/// Indexed authors: `GET /authors`
+#[endpoint(
+    tags("authors"),
+    responses((status_code = 200, description = "Indexed authors.", body = Vec<AuthorDto>))
+)]
+async fn get_authors(
+    _request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: authors ('/authors').");
+    let services = get_services(depot)?;
+    let authors: Vec<AuthorDto> = services
+        .thought_service
+        .get_authors()
+        .await?
+        .into_iter()
+        .map(|a| a.into())
+        .collect();
+    response.render(Json(authors));
+
+    Ok(())
+}
+
+/// One indexed author: `GET /authors/<author_id>`
+#[endpoint(
+    tags("authors"),
+    parameters(("author_id" = Uuid, Path, description = "Identifier of the author")),
+    responses((status_code = 200, description = "The author.", body = AuthorDto))
+)]
+async fn get_author(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: author ('/authors/<author_id>').");
+    let services = get_services(depot)?;
+    let author_id = request.param::<String>("author_id").unwrap_or_default();
+    let author: Option<AuthorDto> = services
+        .thought_service
+        .get_author(&author_id)
+        .await?
+        .map(|a| a.into());
+    render_or_not_found(response, author);
+
+    Ok(())
+}
+
+/// Make an author known by another name: `POST /authors/<author_id>/aliases?alias=<name>`. The
+/// updated author is returned.
+#[endpoint(
+    tags("authors"),
+    parameters(
+        ("author_id" = Uuid, Path, description = "Identifier of the author"),
+        ("alias" = Option<String>, Query, description = "Other name of the author"),
+    ),
+    responses((status_code = 200, description = "The updated author.", body = AuthorDto))
+)]
+async fn add_author_alias(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: author aliases ('/authors/<author_id>/aliases').");
+    let services = get_services(depot)?;
+    let author_id = request.param::<String>("author_id").unwrap_or_default();
+    let alias = request.query::<String>("alias").unwrap_or_default();
+    let author = services
+        .thought_service
+        .add_author_alias(&author_id, &alias)
+        .await?;
+    render_or_not_found(response, author.map(AuthorDto::from));
+
+    Ok(())
+}
+
+/// Sources of an author: `GET /authors/<author_id>/sources`
+#[endpoint(
+    tags("authors"),
+    parameters(("author_id" = Uuid, Path, description = "Identifier of the author")),
+    responses((status_code = 200, description = "Sources of the author.", body = Vec<SourceDto>))
+)]
+async fn get_author_sources(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: author sources ('/authors/<author_id>/sources').");
+    let services = get_services(depot)?;
+    let author_id = request.param::<String>("author_id").unwrap_or_default();
+    let sources: Option<Vec<SourceDto>> = services
+        .thought_service
+        .get_author_sources(&author_id)
+        .await?
+        .map(|sources| sources.into_iter().map(|s| s.into()).collect());
+    render_or_not_found(response, sources);
+
+    Ok(())
+}
+
+/// Thoughts citing the sources of an author: `GET /authors/<author_id>/thoughts`
+#[endpoint(
+    tags("authors"),
+    parameters(("author_id" = Uuid, Path, description = "Identifier of the author")),
+    responses(
+        (
+            status_code = 200,
+            description = "Thoughts citing the sources of the author.",
+            body = Vec<ThoughtDto>
+        )
+    )
+)]
+async fn get_author_thoughts(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: author thoughts ('/authors/<author_id>/thoughts').");
+    let services = get_services(depot)?;
+    let author_id = request.param::<String>("author_id").unwrap_or_default();
+    let thoughts: Option<Vec<ThoughtDto>> = services
+        .thought_service
+        .get_author_thoughts(&author_id)
+        .await?
+        .map(|thoughts| thoughts.into_iter().map(|t| t.into()).collect());
+    render_or_not_found(response, thoughts);
+
+    Ok(())
+}
+
+/// Links from a thought: `GET /thoughts/<thought_id>/links`
+#[endpoint(
+    tags("links"),
+    parameters(("thought_id" = Uuid, Path, description = "Identifier of the thought")),
+    responses(
+        (status_code = 200, description = "Links from the thought.", body = Vec<ThoughtLinkDto>)
+    )
+)]
+async fn get_thought_links(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought links ('/thoughts/<thought_id>/links').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let links: Option<Vec<ThoughtLinkDto>> = services
+        .thought_service
+        .get_links(&thought_id)
+        .await?
+        .map(|links| links.into_iter().map(|l| l.into()).collect());
+    render_or_not_found(response, links);
+
+    Ok(())
+}
+
+/// Link a thought to another one:
+/// `POST /thoughts/<thought_id>/links?to=<thought_id>&kind=<kind>`. The link is returned.
+#[endpoint(
+    tags("links"),
+    parameters(
+        ("thought_id" = Uuid, Path, description = "Identifier of the thought"),
+        ("to" = Option<Uuid>, Query, description = "Linked thought"),
+        ("kind" = Option<String>, Query, description = "`see_also`, `contradicts` or `supersedes`"),
+    ),
+    responses((status_code = 200, description = "The created link.", body = ThoughtLinkDto))
+)]
+async fn create_thought_link(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought links ('/thoughts/<thought_id>/links').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let to_thought_id = request.query::<String>("to").unwrap_or_default();
+    let kind = request.query::<String>("kind").unwrap_or_default();
+    let link = services
+        .thought_service
+        .create_link(&thought_id, &to_thought_id, &kind)
+        .await?;
+    render_or_not_found(response, link.map(ThoughtLinkDto::from));
+
+    Ok(())
+}
+
+/// Links pointing to a thought: `GET /thoughts/<thought_id>/backlinks`
+#[endpoint(
+    tags("links"),
+    parameters(("thought_id" = Uuid, Path, description = "Identifier of the thought")),
+    responses(
+        (
+            status_code = 200,
+            description = "Links pointing to the thought.",
+            body = Vec<ThoughtLinkDto>
+        )
+    )
+)]
+async fn get_thought_backlinks(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought backlinks ('/thoughts/<thought_id>/backlinks').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let links: Option<Vec<ThoughtLinkDto>> = services
+        .thought_service
+        .get_backlinks(&thought_id)
+        .await?
+        .map(|links| links.into_iter().map(|l| l.into()).collect());
+    render_or_not_found(response, links);
+
+    Ok(())
+}
+
+/// Remove a link: `DELETE /links/<link_id>`. The removed link is returned.
+#[endpoint(
+    tags("links"),
+    parameters(("link_id" = Uuid, Path, description = "Identifier of the link")),
+    responses((status_code = 200, description = "The removed link.", body = ThoughtLinkDto))
+)]
+async fn remove_link(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: link ('/links/<link_id>').");
+    let services = get_services(depot)?;
+    let link_id = request.param::<String>("link_id").unwrap_or_default();
+    let link: Option<ThoughtLinkDto> = services
+        .thought_service
+        .remove_link(&link_id)
+        .await?
+        .map(|l| l.into());
+    render_or_not_found(response, link);
+
+    Ok(())
+}
+
+/// Files attached to a thought: `GET /thoughts/<thought_id>/attachments`
+#[endpoint(
+    tags("attachments"),
+    parameters(("thought_id" = Uuid, Path, description = "Identifier of the thought")),
+    responses(
+        (
+            status_code = 200,
+            description = "Files attached to the thought.",
+            body = Vec<AttachmentDto>
+        )
+    )
+)]
+async fn get_thought_attachments(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought attachments ('/thoughts/<thought_id>/attachments').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let attachments: Option<Vec<AttachmentDto>> = services
+        .thought_service
+        .get_attachments(&thought_id)
+        .await?
+        .map(|attachments| attachments.into_iter().map(|a| a.into()).collect());
+    render_or_not_found(response, attachments);
+
+    Ok(())
+}
+
+/// Attach a file to a thought: `POST /thoughts/<thought_id>/attachments`. The file is sent as
+/// the `file` field of a multipart form, salvo spools it to a temporary file while it is
+/// uploaded. Requests larger than the configured maximum upload size are refused before being
+/// read when they announce their length, the others are stopped as soon as they go past it.
+/// The attachment is returned.
+#[endpoint(
+    tags("attachments"),
+    parameters(("thought_id" = Uuid, Path, description = "Identifier of the thought")),
+    request_body(
+        content = AttachmentUploadDto,
+        content_type = "multipart/form-data",
+        description = "The file to attach, in the `file` field."
+    ),
+    responses(
+        (status_code = 200, description = "The created attachment.", body = AttachmentDto),
+        (status_code = 413, description = "The file is larger than the maximum upload size.", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn add_thought_attachment(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: thought attachments ('/thoughts/<thought_id>/attachments').");
+    let services = get_services(depot)?;
+    let thought_id = request.param::<String>("thought_id").unwrap_or_default();
+    let upload = depot.obtain::<LimitedUpload>().ok().cloned();
+    let file = match request.file("file").await {
+        Some(file) => file,
+        None => match upload.filter(|upload| upload.is_exceeded()) {
+            Some(upload) => return Err(upload.too_large().into()),
+            None => {
+                return Err(RequestError::InvalidParameter(
+                    "No file given in the 'file' field of the form.".to_string(),
+                )
+                .into())
+            }
+        },
+    };
+    let file_name = file.name().unwrap_or_default().to_string();
+    let mime_type = file
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let attachment = services
+        .thought_service
+        .add_attachment(&thought_id, &file_name, &mime_type, file.path())
+        .await?;
+    render_or_not_found(response, attachment.map(AttachmentDto::from));
+
+    Ok(())
+}
+
+/// Content of an attachment: `GET /attachments/<attachment_id>`. It is streamed from the blob
+/// store as a download, range requests are supported.
+#[endpoint(
+    tags("attachments"),
+    parameters(("attachment_id" = Uuid, Path, description = "Identifier of the attachment")),
+    responses((status_code = 200, description = "Content of the attached file."))
+)]
+async fn download_attachment(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: attachment ('/attachments/<attachment_id>').");
+    let services = get_services(depot)?;
+    let attachment_id = request.param::<String>("attachment_id").unwrap_or_default();
+    let (attachment, path) = match services
+        .thought_service
+        .get_attachment(&attachment_id)
+        .await?
+    {
+        Some(found) => found,
+        None => {
+            return Err(RequestError::NotFound(format!(
+                "No attachment found for ID='{attachment_id}'."
+            ))
+            .into())
+        }
+    };
+    let mime_type = attachment
+        .mime_type
+        .parse::<Mime>()
+        .unwrap_or(salvo::http::mime::APPLICATION_OCTET_STREAM);
+    // the browser must not guess another type, an uploaded file could be run as a page
+    response
+        .headers_mut()
+        .insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    NamedFile::builder(path)
+        .attached_name(attachment.file_name)
+        .content_type(mime_type)
+        .send(request.headers(), response)
+        .await;
+
+    Ok(())
+}
+
+/// Remove an attachment: `DELETE /attachments/<attachment_id>`. The removed attachment is
+/// returned.
+#[endpoint(
+    tags("attachments"),
+    parameters(("attachment_id" = Uuid, Path, description = "Identifier of the attachment")),
+    responses((status_code = 200, description = "The removed attachment.", body = AttachmentDto))
+)]
+async fn remove_attachment(
+    request: &mut Request,
+    depot: &mut Depot,
+    response: &mut Response,
+) -> Result<(), ApiError> {
+    info!("ROUTE: attachment ('/attachments/<attachment_id>').");
+    let services = get_services(depot)?;
+    let attachment_id = request.param::<String>("attachment_id").unwrap_or_default();
+    let attachment: Option<AttachmentDto> = services
+        .thought_service
+        .remove_attachment(&attachment_id)
+        .await?
+        .map(|a| a.into());
+    render_or_not_found(response, attachment);
 
     Ok(())
 }
@@ -68,11 +1283,91 @@ impl BackendHttpRuntime {
         }
     }
 
+    /// Routes of the API, they are all endpoints so the OpenAPI specification describes them.
+    /// The uploaded attachments are limited to the given size, in bytes.
+    pub fn api_router(max_upload_size: u64) -> Router {
+        Router::new()
+            .get(index)
+            .push(Router::with_path("thoughts/search").get(search_thoughts))
+            .push(Router::with_path("thoughts").post(create_thought))
+            .push(
+                Router::with_path("thoughts/<thought_id>")
+                    .get(get_thought)
+                    .put(update_thought),
+            )
+            .push(Router::with_path("thoughts/<thought_id>/thread").get(get_thought_thread))
+            .push(Router::with_path("threads").get(list_threads))
+            .push(
+                Router::with_path("thoughts/<thought_id>/revisions")
+                    .get(get_thought_revisions)
+                    .push(Router::with_path("diff").get(diff_thought_revisions))
+                    .push(Router::with_path("<revision:num>").get(get_thought_revision)),
+            )
+            .push(
+                Router::with_path("thoughts/<thought_id>/links")
+                    .get(get_thought_links)
+                    .post(create_thought_link),
+            )
+            .push(Router::with_path("thoughts/<thought_id>/backlinks").get(get_thought_backlinks))
+            .push(Router::with_path("thoughts/<thought_id>/tree").get(get_thread_tree))
+            .push(Router::with_path("thoughts/<thought_id>/subtree").get(get_subtree))
+            .push(Router::with_path("thoughts/<thread_id>/merge").post(merge_threads))
+            .push(Router::with_path("links/<link_id>").delete(remove_link))
+            .push(
+                Router::with_path("thoughts/<thought_id>/attachments")
+                    .get(get_thought_attachments)
+                    .push(
+                        Router::new()
+                            .hoop(UploadLimit(max_upload_size))
+                            .post(add_thought_attachment),
+                    ),
+            )
+            .push(
+                Router::with_path("attachments/<attachment_id>")
+                    .get(download_attachment)
+                    .delete(remove_attachment),
+            )
+            .push(
+                Router::with_path("categories")
+                    .get(get_categories)
+                    .push(Router::with_path("<path>/thoughts").get(get_category_thoughts)),
+            )
+            .push(
+                Router::with_path("sources")
+                    .get(get_sources)
+                    .post(register_source)
+                    .push(Router::with_path("duplicates").get(get_duplicate_sources))
+                    .push(
+                        Router::with_path("<source_id>")
+                            .get(get_source)
+                            .push(Router::with_path("thoughts").get(get_source_thoughts))
+                            .push(Router::with_path("merge").post(merge_sources)),
+                    ),
+            )
+            .push(
+                Router::with_path("authors").get(get_authors).push(
+                    Router::with_path("<author_id>")
+                        .get(get_author)
+                        .push(Router::with_path("aliases").post(add_author_alias))
+                        .push(Router::with_path("sources").get(get_author_sources))
+                        .push(Router::with_path("thoughts").get(get_author_thoughts)),
+                ),
+            )
+    }
+
+    /// OpenAPI specification generated from the endpoints of the given router.
+    pub fn api_doc(router: &Router) -> OpenApi {
+        OpenApi::new("Omstasher API", env!("CARGO_PKG_VERSION")).merge_router(router)
+    }
+
     pub async fn run(&self) -> StdResult<()> {
         //tracing_subscriber::fmt().init();
-        let router = Router::new()
+        let router = Self::api_router(self.config.get_max_upload_size());
+        let api_doc = Self::api_doc(&router);
+        let router = router
             .hoop(affix::inject(self.services_container.clone()))
-            .get(index);
+            .push(api_doc.into_router(OPENAPI_PATH))
+            .push(SwaggerUi::new(OPENAPI_PATH).into_router(API_DOCS_PATH));
         let acceptor = TcpListener::new(&self.config.get_listen_address())
             .try_bind()
             .await
@@ -91,3 +1386,316 @@ impl BackendHttpRuntime {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use flat_config::{pool::SimpleFlatPool, ConfigBuilder};
+    use salvo::http::body::ReqBody;
+    use salvo::test::{ResponseExt, TestClient};
+    use serde_json::{json, Value};
+    use tokio::sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver},
+        Mutex,
+    };
+
+    use super::*;
+    use crate::{
+        http::upload::tests::ChunkedBody,
+        thoughts::{model::MemoryThoughtStore, BackendThoughtService, ThoughtServiceConfigBuilder},
+        EventMessage,
+    };
+
+    /// Routes backed by a thought service keeping its thoughts in memory. The events are sent
+    /// to the returned receiver, it must be kept for the service to work.
+    fn get_service() -> (Service, UnboundedReceiver<EventMessage>) {
+        let mut flat_pool = SimpleFlatPool::default();
+        flat_pool
+            .add("database_dsn", "memory://".into())
+            .add("database_pool_size", 1_isize.into())
+            .add("database_pool_timeout", 1_isize.into())
+            .add("database_pool_recycling", "fast".into())
+            .add(
+                "attachment_directory",
+                std::env::temp_dir()
+                    .join(format!("omstasher-attachments-{}", Uuid::new_v4()))
+                    .display()
+                    .to_string()
+                    .as_str()
+                    .into(),
+            );
+        let config = ThoughtServiceConfigBuilder::default()
+            .build(&flat_pool)
+            .unwrap();
+        let (sender, receiver) = unbounded_channel::<EventMessage>();
+        let thought_service = BackendThoughtService::new(
+            Arc::new(config),
+            Arc::new(MemoryThoughtStore::default()),
+            Arc::new(Mutex::new(sender)),
+        );
+        let services = Arc::new(ServicesContainer::new(Arc::new(thought_service)));
+        let router = BackendHttpRuntime::api_router(1024).hoop(affix::inject(services));
+
+        (Service::new(router), receiver)
+    }
+
+    /// Operations described by the OpenAPI specification. Changing a route changes the
+    /// specification, this list must be updated with it.
+    const OPERATIONS: &[(&str, &str)] = &[
+        ("get", "/"),
+        ("get", "/thoughts/search"),
+        ("post", "/thoughts"),
+        ("get", "/thoughts/{thought_id}"),
+        ("put", "/thoughts/{thought_id}"),
+        ("get", "/thoughts/{thought_id}/thread"),
+        ("get", "/threads"),
+        ("get", "/thoughts/{thought_id}/revisions"),
+        ("get", "/thoughts/{thought_id}/revisions/diff"),
+        ("get", "/thoughts/{thought_id}/revisions/{revision}"),
+        ("get", "/thoughts/{thought_id}/links"),
+        ("post", "/thoughts/{thought_id}/links"),
+        ("get", "/thoughts/{thought_id}/backlinks"),
+        ("get", "/thoughts/{thought_id}/tree"),
+        ("get", "/thoughts/{thought_id}/subtree"),
+        ("post", "/thoughts/{thread_id}/merge"),
+        ("delete", "/links/{link_id}"),
+        ("get", "/thoughts/{thought_id}/attachments"),
+        ("post", "/thoughts/{thought_id}/attachments"),
+        ("get", "/attachments/{attachment_id}"),
+        ("delete", "/attachments/{attachment_id}"),
+        ("get", "/categories"),
+        ("get", "/categories/{path}/thoughts"),
+        ("get", "/sources"),
+        ("post", "/sources"),
+        ("get", "/sources/duplicates"),
+        ("get", "/sources/{source_id}"),
+        ("get", "/sources/{source_id}/thoughts"),
+        ("post", "/sources/{source_id}/merge"),
+        ("get", "/authors"),
+        ("get", "/authors/{author_id}"),
+        ("post", "/authors/{author_id}/aliases"),
+        ("get", "/authors/{author_id}/sources"),
+        ("get", "/authors/{author_id}/thoughts"),
+    ];
+
+    /// Operations of the specification generated from the routes, sorted.
+    fn get_documented_operations() -> Vec<(String, String)> {
+        let spec = serde_json::to_value(BackendHttpRuntime::api_doc(
+            &BackendHttpRuntime::api_router(1024),
+        ))
+        .unwrap();
+        let mut operations: Vec<(String, String)> = spec["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .flat_map(|(path, item)| {
+                item.as_object()
+                    .unwrap()
+                    .keys()
+                    .filter(|key| ["get", "put", "post", "delete"].contains(&key.as_str()))
+                    .map(|method| (method.to_string(), path.to_string()))
+            })
+            .collect();
+        operations.sort();
+
+        operations
+    }
+
+    /// Specification committed with the sources, a change of the generated specification must
+    /// be reviewed. Run the tests with `OMSTASHER_UPDATE_OPENAPI=1` to write it again.
+    const OPENAPI_SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    #[test]
+    fn specification_matches_the_snapshot() {
+        let spec = serde_json::to_value(BackendHttpRuntime::api_doc(
+            &BackendHttpRuntime::api_router(1024),
+        ))
+        .unwrap();
+        if std::env::var_os("OMSTASHER_UPDATE_OPENAPI").is_some() {
+            let content = serde_json::to_string_pretty(&spec).unwrap();
+            std::fs::write(OPENAPI_SNAPSHOT, format!("{content}\n")).unwrap();
+        }
+        let snapshot: Value =
+            serde_json::from_str(&std::fs::read_to_string(OPENAPI_SNAPSHOT).unwrap()).unwrap();
+
+        assert!(
+            snapshot == spec,
+            "The generated specification differs from '{OPENAPI_SNAPSHOT}', run the tests with \
+            OMSTASHER_UPDATE_OPENAPI=1 to update it."
+        );
+    }
+
+    #[test]
+    fn specification_describes_the_routes() {
+        let mut expected: Vec<(String, String)> = OPERATIONS
+            .iter()
+            .map(|(method, path)| (method.to_string(), path.to_string()))
+            .collect();
+        expected.sort();
+
+        assert_eq!(expected, get_documented_operations());
+    }
+
+    #[tokio::test]
+    async fn documented_operations_are_routed() {
+        let service = Service::new(BackendHttpRuntime::api_router(1024));
+
+        for (method, path) in OPERATIONS {
+            // every path parameter accepts a number, the handlers fail later on the missing
+            // services container
+            let url = format!(
+                "http://127.0.0.1{}",
+                path.split('/')
+                    .map(|segment| if segment.starts_with('{') {
+                        "1"
+                    } else {
+                        segment
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/")
+            );
+            let client = match *method {
+                "get" => TestClient::get(&url),
+                "put" => TestClient::put(&url),
+                "post" => TestClient::post(&url),
+                _ => TestClient::delete(&url),
+            };
+            let response = client.send(&service).await;
+
+            assert_ne!(
+                Some(StatusCode::NOT_FOUND),
+                response.status_code,
+                "{method} {path} is not routed."
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn thoughts_are_created_read_and_updated() {
+        let (service, _receiver) = get_service();
+        let thought_id = Uuid::new_v4();
+        let url = "http://127.0.0.1/thoughts";
+
+        let mut response = TestClient::post(url)
+            .json(&json!({"thought_id": thought_id, "text": "Stoicism"}))
+            .send(&service)
+            .await;
+        assert_eq!(Some(StatusCode::CREATED), response.status_code);
+        let created: Value = response.take_json().await.unwrap();
+        assert_eq!("Stoicism", created["text"]);
+        assert_eq!(Value::Null, created["parent_thought_id"]);
+        assert_eq!("thread", created["content"]["type"]);
+
+        let response = TestClient::post(url)
+            .json(&json!({"thought_id": thought_id, "text": "Epicureanism"}))
+            .send(&service)
+            .await;
+        assert_eq!(Some(StatusCode::CONFLICT), response.status_code);
+
+        let response = TestClient::post(url)
+            .raw_json("{\"text\": ")
+            .send(&service)
+            .await;
+        assert_eq!(Some(StatusCode::BAD_REQUEST), response.status_code);
+
+        let mut response = TestClient::put(format!("{url}/{thought_id}"))
+            .json(&json!({"text": "Stoic philosophy", "keywords": ["ethics"]}))
+            .send(&service)
+            .await;
+        assert_eq!(Some(StatusCode::OK), response.status_code);
+        let updated: Value = response.take_json().await.unwrap();
+        assert_eq!("Stoic philosophy", updated["text"]);
+        assert_eq!(json!(["ethics"]), updated["keywords"]);
+
+        let mut response = TestClient::get(format!("{url}/{thought_id}"))
+            .send(&service)
+            .await;
+        assert_eq!(Some(StatusCode::OK), response.status_code);
+        let read: Value = response.take_json().await.unwrap();
+        assert_eq!(created["created_at"], read["created_at"]);
+        assert_eq!("Stoic philosophy", read["text"]);
+
+        let response = TestClient::get(format!("{url}/{}", Uuid::new_v4()))
+            .send(&service)
+            .await;
+        assert_eq!(Some(StatusCode::NOT_FOUND), response.status_code);
+    }
+
+    #[tokio::test]
+    async fn malformed_identifiers_are_not_found() {
+        let (service, _receiver) = get_service();
+
+        for client in [
+            TestClient::get("http://127.0.0.1/thoughts/stoicism"),
+            TestClient::put("http://127.0.0.1/thoughts/stoicism").json(&json!({"text": "t"})),
+            TestClient::get("http://127.0.0.1/thoughts/stoicism/thread"),
+            TestClient::get("http://127.0.0.1/thoughts/stoicism/links"),
+            TestClient::get("http://127.0.0.1/sources/stoicism"),
+            TestClient::get("http://127.0.0.1/attachments/stoicism"),
+        ] {
+            let response = client.send(&service).await;
+
+            assert_eq!(Some(StatusCode::NOT_FOUND), response.status_code);
+        }
+    }
+
+    #[tokio::test]
+    async fn listing_limits_are_checked() {
+        let (service, _receiver) = get_service();
+        TestClient::post("http://127.0.0.1/thoughts")
+            .json(&json!({"text": "Stoicism"}))
+            .send(&service)
+            .await;
+
+        for url in [
+            "http://127.0.0.1/threads?limit=many",
+            "http://127.0.0.1/threads?limit=0",
+            "http://127.0.0.1/thoughts/search?q=stoicism&limit=-1",
+        ] {
+            let response = TestClient::get(url).send(&service).await;
+
+            assert_eq!(
+                Some(StatusCode::UNPROCESSABLE_ENTITY),
+                response.status_code,
+                "{url} is accepted."
+            );
+        }
+
+        let mut response = TestClient::get("http://127.0.0.1/threads?limit=1")
+            .send(&service)
+            .await;
+        assert_eq!(Some(StatusCode::OK), response.status_code);
+        let page: Value = response.take_json().await.unwrap();
+        assert_eq!(1, page["items"].as_array().unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn chunked_uploads_are_limited() {
+        const HEAD: &[u8] = b"--boundary\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"letter.txt\"\r\n\
+            Content-Type: text/plain\r\n\r\n";
+        const TAIL: &[u8] = b"\r\n--boundary--\r\n";
+        let (service, _receiver) = get_service();
+        let mut response = TestClient::post("http://127.0.0.1/thoughts")
+            .json(&json!({"text": "Stoicism"}))
+            .send(&service)
+            .await;
+        let thought: Value = response.take_json().await.unwrap();
+        let url = format!(
+            "http://127.0.0.1/thoughts/{}/attachments",
+            thought["thought_id"].as_str().unwrap()
+        );
+        let upload = |content: &'static [u8]| {
+            TestClient::post(&url)
+                .add_header(CONTENT_TYPE, "multipart/form-data; boundary=boundary", true)
+                .body(ReqBody::Inner(Box::pin(ChunkedBody::new(&[
+                    HEAD, content, TAIL,
+                ]))))
+        };
+
+        let response = upload(b"Letters to Lucilius").send(&service).await;
+        assert_eq!(Some(StatusCode::OK), response.status_code);
+
+        let response = upload(&[b'a'; 2048]).send(&service).await;
+        assert_eq!(Some(StatusCode::PAYLOAD_TOO_LARGE), response.status_code);
+    }
+}
@@ -0,0 +1,208 @@
+//! Size limit of the uploaded files
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use salvo::http::body::{Body, ReqBody, SizeHint};
+use salvo::hyper::body::{Bytes, Frame};
+use salvo::prelude::*;
+use salvo::BoxedError;
+use thiserror::Error;
+
+use super::{ProblemDetails, RequestError};
+
+/// Error of a request body read past the upload limit.
+#[derive(Debug, Error)]
+#[error("The request body is larger than {0} bytes")]
+struct BodyTooLarge(u64);
+
+/// Limit of the size of the uploads, in bytes. A request announcing a larger body is refused
+/// before it is read. The body of the others is counted while it streams and fails as soon as
+/// it goes past the limit, a chunked upload is then never spooled whole. The handlers find a
+/// `LimitedUpload` in the depot telling whether the body was cut.
+pub struct UploadLimit(pub u64);
+
+#[async_trait]
+impl Handler for UploadLimit {
+    async fn handle(
+        &self,
+        request: &mut Request,
+        depot: &mut Depot,
+        response: &mut Response,
+        ctrl: &mut FlowCtrl,
+    ) {
+        let upload = LimitedUpload {
+            max_size: self.0,
+            exceeded: Arc::new(AtomicBool::new(false)),
+        };
+
+        if request.body().size_hint().lower() > self.0 {
+            response.render(ProblemDetails::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Some(upload.too_large().to_string()),
+            ));
+            ctrl.skip_rest();
+
+            return;
+        }
+        let body = LimitedBody {
+            body: request.take_body(),
+            remaining: self.0,
+            max_size: self.0,
+            exceeded: upload.exceeded.clone(),
+        };
+        request.replace_body(ReqBody::Inner(Box::pin(body)));
+        depot.inject(upload);
+        ctrl.call_next(request, depot, response).await;
+    }
+}
+
+/// Upload limit of a request, given to the handlers by `UploadLimit`.
+#[derive(Debug, Clone)]
+pub struct LimitedUpload {
+    max_size: u64,
+    exceeded: Arc<AtomicBool>,
+}
+
+impl LimitedUpload {
+    /// Whether the body was cut because it went past the limit.
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::Relaxed)
+    }
+
+    /// Error answered when the upload is too large.
+    pub fn too_large(&self) -> RequestError {
+        RequestError::PayloadTooLarge(format!(
+            "The file is larger than the maximum upload size of {} bytes.",
+            self.max_size
+        ))
+    }
+}
+
+/// Request body failing once more than `max_size` bytes were read.
+struct LimitedBody {
+    body: ReqBody,
+    remaining: u64,
+    max_size: u64,
+    exceeded: Arc<AtomicBool>,
+}
+
+impl Body for LimitedBody {
+    type Data = Bytes;
+    type Error = BoxedError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = match Pin::new(&mut self.body).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => frame,
+            Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e.into()))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+        let size = frame.data_ref().map(|data| data.len() as u64).unwrap_or(0);
+
+        if size > self.remaining {
+            self.exceeded.store(true, Ordering::Relaxed);
+
+            return Poll::Ready(Some(Err(BodyTooLarge(self.max_size).into())));
+        }
+        self.remaining -= size;
+
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::VecDeque;
+
+    use salvo::test::{ResponseExt, TestClient};
+
+    use super::*;
+
+    /// Body streamed in chunks without announcing its size, like a chunked upload.
+    pub struct ChunkedBody(VecDeque<Bytes>);
+
+    impl ChunkedBody {
+        pub fn new(chunks: &[&'static [u8]]) -> Self {
+            Self(
+                chunks
+                    .iter()
+                    .map(|chunk| Bytes::from_static(chunk))
+                    .collect(),
+            )
+        }
+    }
+
+    impl Body for ChunkedBody {
+        type Data = Bytes;
+        type Error = BoxedError;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+            Poll::Ready(self.0.pop_front().map(|chunk| Ok(Frame::data(chunk))))
+        }
+    }
+
+    #[handler]
+    async fn read_body(request: &mut Request, depot: &mut Depot, response: &mut Response) {
+        let read = request.payload_with_max_size(usize::MAX).await.is_ok();
+        let exceeded = depot
+            .obtain::<LimitedUpload>()
+            .map(|upload| upload.is_exceeded())
+            .unwrap_or_default();
+
+        response.render(format!("read={read} exceeded={exceeded}"));
+    }
+
+    async fn send(body: ReqBody) -> salvo::Response {
+        let service = Service::new(Router::new().hoop(UploadLimit(8)).post(read_body));
+
+        TestClient::post("http://127.0.0.1/")
+            .body(body)
+            .send(&service)
+            .await
+    }
+
+    #[tokio::test]
+    async fn chunked_bodies_are_cut_past_the_limit() {
+        let body = ReqBody::Inner(Box::pin(ChunkedBody::new(&[b"1234", b"5678"])));
+        let mut response = send(body).await;
+        assert_eq!(
+            "read=true exceeded=false",
+            response.take_string().await.unwrap()
+        );
+
+        let body = ReqBody::Inner(Box::pin(ChunkedBody::new(&[b"1234", b"5678", b"9"])));
+        let mut response = send(body).await;
+        assert_eq!(
+            "read=false exceeded=true",
+            response.take_string().await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn announced_bodies_are_refused_before_being_read() {
+        let response = send(ReqBody::from(vec![0_u8; 9])).await;
+
+        assert_eq!(Some(StatusCode::PAYLOAD_TOO_LARGE), response.status_code);
+    }
+}
@@ -2,8 +2,11 @@ mod configuration;
 mod dependencies;
 mod event_dispatcher;
 pub mod http;
+mod migrations;
 mod runtime;
 mod services_container;
+#[cfg(test)]
+mod test_database;
 pub mod thoughts;
 
 pub type StdError = anyhow::Error;
@@ -12,5 +15,6 @@ pub type StdResult<T> = anyhow::Result<T>;
 pub use configuration::{ConfigurationBuilder, ConfigurationFileParser};
 pub use dependencies::*;
 pub use event_dispatcher::*;
+pub use migrations::*;
 pub use runtime::*;
 pub use services_container::*;
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use anyhow::anyhow;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use clap_verbosity_flag::Verbosity;
 use flat_config::{
     pool::{FlatPool, LayeredFlatPool, SimpleFlatPool},
@@ -13,10 +13,36 @@ use signal_hook::consts::*;
 use signal_hook_tokio::Signals;
 
 use backend::{
+    thoughts::{model::ThoughtStoreError, ThoughtServiceError},
     ConfigurationBuilder, ConfigurationFileParser, DependenciesBuilder, EventDispatcherLoop,
-    StdResult,
+    Migrator, StdError, StdResult,
 };
 
+/// Commands run instead of the backend server
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage the database schema
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// Permanently remove the thoughts deleted for longer than the trash retention period
+    PurgeTrash,
+    /// Remove the attachment files no longer referenced by any thought
+    CollectBlobs,
+}
+
+/// Database schema migration actions
+#[derive(Debug, Subcommand)]
+pub enum MigrateAction {
+    /// Apply all pending migrations
+    Up,
+    /// Display the state of every migration
+    Status,
+    /// Revert the last applied migration
+    Down,
+}
+
 /// Possible command line options and arguments
 #[derive(Debug, Parser)]
 pub struct CommandLineParameters {
@@ -36,9 +62,65 @@ pub struct CommandLineParameters {
     #[arg(long, env = "OMSTASHER_DATABASE_DSN")]
     database_dsn: Option<String>,
 
+    /// Maximum number of database connections
+    #[arg(long, env = "OMSTASHER_DATABASE_POOL_SIZE")]
+    database_pool_size: Option<u16>,
+
+    /// Seconds to wait for a database connection to be available
+    #[arg(long, env = "OMSTASHER_DATABASE_POOL_TIMEOUT")]
+    database_pool_timeout: Option<u16>,
+
+    /// Database connection recycling method (fast, verified, clean)
+    #[arg(long, env = "OMSTASHER_DATABASE_POOL_RECYCLING")]
+    database_pool_recycling: Option<String>,
+
+    /// Database TLS mode (disable, prefer, require, verify-full)
+    ///
+    /// `prefer` uses TLS when the server supports it but accepts any server certificate.
+    /// `require` only verifies the server certificate when a root certificate is given and never
+    /// checks its host name. `verify-full` verifies both.
+    #[arg(long, env = "OMSTASHER_DATABASE_SSLMODE")]
+    database_sslmode: Option<String>,
+
+    /// Certificate authorities bundle used to verify the database server certificate
+    #[arg(long, env = "OMSTASHER_DATABASE_SSLROOTCERT")]
+    database_sslrootcert: Option<PathBuf>,
+
+    /// Client certificate presented to the database server
+    #[arg(long, env = "OMSTASHER_DATABASE_SSLCERT")]
+    database_sslcert: Option<PathBuf>,
+
+    /// Private key of the client certificate
+    #[arg(long, env = "OMSTASHER_DATABASE_SSLKEY")]
+    database_sslkey: Option<PathBuf>,
+
+    /// PostgreSQL text search configuration used to index thoughts (simple, english, french…)
+    #[arg(long, env = "OMSTASHER_SEARCH_LANGUAGE")]
+    search_language: Option<String>,
+
+    /// Number of days deleted thoughts are kept in the trash
+    #[arg(long, env = "OMSTASHER_TRASH_RETENTION_DAYS")]
+    trash_retention_days: Option<u16>,
+
+    /// Directory the attachment files are stored in
+    #[arg(long, env = "OMSTASHER_ATTACHMENT_DIRECTORY")]
+    attachment_directory: Option<PathBuf>,
+
+    /// Maximum size of an uploaded attachment file, in MiB
+    #[arg(long, env = "OMSTASHER_MAX_UPLOAD_SIZE")]
+    max_upload_size: Option<u32>,
+
+    /// What to do with corrupt thoughts read from the database (strict, tolerant)
+    #[arg(long, env = "OMSTASHER_HYDRATION_MODE")]
+    hydration_mode: Option<String>,
+
     /// Verbose mode (-q, -v, -vv, -vvv, etc)
     #[command(flatten)]
     verbose: Verbosity,
+
+    /// Command to run, the backend server is launched if none is given
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
 impl CommandLineParameters {
@@ -58,6 +140,49 @@ impl CommandLineParameters {
             flat_pool.add("http_address", http_address.as_str().into());
         }
 
+        if let Some(pool_size) = self.database_pool_size {
+            flat_pool.add("database_pool_size", (pool_size as isize).into());
+        }
+
+        if let Some(pool_timeout) = self.database_pool_timeout {
+            flat_pool.add("database_pool_timeout", (pool_timeout as isize).into());
+        }
+
+        if let Some(pool_recycling) = &self.database_pool_recycling {
+            flat_pool.add("database_pool_recycling", pool_recycling.as_str().into());
+        }
+
+        if let Some(sslmode) = &self.database_sslmode {
+            flat_pool.add("database_sslmode", sslmode.as_str().into());
+        }
+
+        if let Some(search_language) = &self.search_language {
+            flat_pool.add("search_language", search_language.as_str().into());
+        }
+
+        if let Some(retention_days) = self.trash_retention_days {
+            flat_pool.add("trash_retention_days", (retention_days as isize).into());
+        }
+
+        if let Some(max_upload_size) = self.max_upload_size {
+            flat_pool.add("max_upload_size", (max_upload_size as isize).into());
+        }
+
+        if let Some(hydration_mode) = &self.hydration_mode {
+            flat_pool.add("hydration_mode", hydration_mode.as_str().into());
+        }
+
+        for (name, path) in [
+            ("database_sslrootcert", &self.database_sslrootcert),
+            ("database_sslcert", &self.database_sslcert),
+            ("database_sslkey", &self.database_sslkey),
+            ("attachment_directory", &self.attachment_directory),
+        ] {
+            if let Some(path) = path {
+                flat_pool.add(name, path.display().to_string().as_str().into());
+            }
+        }
+
         flat_pool
     }
 }
@@ -86,6 +211,20 @@ fn default_parameters() -> SimpleFlatPool {
         None => "127.0.0.1",
     };
 
+    /// default maximum number of database connections
+    const DEFAULT_DATABASE_POOL_SIZE: &str =
+        match option_env!("OMSTASHER_BACKEND_DEFAULT_DATABASE_POOL_SIZE") {
+            Some(s) => s,
+            None => "16",
+        };
+
+    /// default number of seconds to wait for a database connection
+    const DEFAULT_DATABASE_POOL_TIMEOUT: &str =
+        match option_env!("OMSTASHER_BACKEND_DEFAULT_DATABASE_POOL_TIMEOUT") {
+            Some(s) => s,
+            None => "5",
+        };
+
     let mut flat_pool = SimpleFlatPool::default();
     flat_pool
         .add("http_address", DEFAULT_HTTP_ADDRESS.into())
@@ -93,6 +232,18 @@ fn default_parameters() -> SimpleFlatPool {
             "http_port",
             DEFAULT_HTTP_PORT.parse::<isize>().unwrap().into(),
         )
+        .add(
+            "database_pool_size",
+            DEFAULT_DATABASE_POOL_SIZE.parse::<isize>().unwrap().into(),
+        )
+        .add(
+            "database_pool_timeout",
+            DEFAULT_DATABASE_POOL_TIMEOUT
+                .parse::<isize>()
+                .unwrap()
+                .into(),
+        )
+        .add("database_pool_recycling", "fast".into())
         // the name 'default_config_file' is therefore reserved for our usage
         // and will be EXPECTED to be present at runtime.
         .add("default_config_file", DEFAULT_CONFIG_FILE.into());
@@ -100,6 +251,50 @@ fn default_parameters() -> SimpleFlatPool {
     flat_pool
 }
 
+/// Run the given migration action and display its outcome.
+async fn run_migrate(migrator: Migrator, action: MigrateAction) -> StdResult<()> {
+    match action {
+        MigrateAction::Up => {
+            let migrations = migrator.up().await?;
+
+            if migrations.is_empty() {
+                println!("Database schema is up to date.");
+            }
+            for migration in migrations {
+                println!("applied   {:04} {}", migration.version, migration.name);
+            }
+        }
+        MigrateAction::Status => {
+            for status in migrator.status().await? {
+                let state = if status.applied { "applied" } else { "pending" };
+                println!(
+                    "{state:<9} {:04} {}",
+                    status.migration.version, status.migration.name
+                );
+            }
+        }
+        MigrateAction::Down => match migrator.down().await? {
+            Some(migration) => {
+                println!("reverted  {:04} {}", migration.version, migration.name)
+            }
+            None => println!("No migration to revert."),
+        },
+    }
+
+    Ok(())
+}
+
+/// Tell the operator what to check when a command fails because of its environment.
+fn explain_service_error(error: ThoughtServiceError) -> StdError {
+    match error {
+        ThoughtServiceError::Store(ThoughtStoreError::Backend(_)) => anyhow!(error)
+            .context("The database could not be reached, check its connection settings."),
+        ThoughtServiceError::BlobStore(_) => anyhow!(error)
+            .context("The attachment directory could not be used, check its permissions."),
+        error => anyhow!(error),
+    }
+}
+
 /// OS signal handler (only Linux for now)
 pub struct OsSignalHandler;
 
@@ -121,7 +316,7 @@ impl OsSignalHandler {
 #[tokio::main]
 async fn main() -> StdResult<()> {
     // Read parameters from command line and environment.
-    let parameters = CommandLineParameters::parse();
+    let mut parameters = CommandLineParameters::parse();
 
     // logger initialization
     stderrlog::new()
@@ -135,6 +330,8 @@ async fn main() -> StdResult<()> {
     );
     debug!("Command line parameters: {parameters:?}");
 
+    let command = parameters.command.take();
+
     trace!("manage configuration");
     let mut flat_pools: Vec<Box<dyn FlatPool>> = Vec::new();
     // 1 - Add default parameters first
@@ -160,6 +357,38 @@ async fn main() -> StdResult<()> {
     let dependencies =
         DependenciesBuilder::new(ConfigurationBuilder::new(LayeredFlatPool::new(flat_pools)));
 
+    match command {
+        Some(Command::Migrate { action }) => {
+            trace!("run migrate command");
+            return run_migrate(dependencies.build_migrator().await?, action).await;
+        }
+        Some(Command::PurgeTrash) => {
+            trace!("run purge trash command");
+            let purged = dependencies
+                .get_thought_service()
+                .await?
+                .purge_trash()
+                .await
+                .map_err(explain_service_error)?;
+            println!("{} thoughts purged from the trash.", purged.len());
+
+            return Ok(());
+        }
+        Some(Command::CollectBlobs) => {
+            trace!("run collect blobs command");
+            let removed = dependencies
+                .get_thought_service()
+                .await?
+                .collect_orphan_blobs()
+                .await
+                .map_err(explain_service_error)?;
+            println!("{} orphaned attachment files removed.", removed.len());
+
+            return Ok(());
+        }
+        None => (),
+    }
+
     trace!("HTTP server runtime initialization");
     let http_runtime = dependencies.build_http_runtime().await?;
 
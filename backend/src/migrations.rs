@@ -0,0 +1,380 @@
+//! Database schema migrations
+//!
+//! Migrations are compiled into the binary. The versions applied to a database are recorded in
+//! the bookkeeping table so the application can tell if the schema is up to date.
+use anyhow::anyhow;
+use deadpool_postgres::{GenericClient, Object, Pool, Transaction};
+use log::{info, trace};
+
+use crate::StdResult;
+
+/// Table recording the migrations applied to the database.
+const BOOKKEEPING_TABLE: &str = "public.omstasher_migration";
+
+/// Key of the advisory lock taken while migrating, so concurrent processes apply or revert the
+/// migrations one at a time.
+const MIGRATION_LOCK_KEY: i64 = 0x6f6d_7374_6173_6865;
+
+/// A versioned schema modification and the way to revert it.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+/// All the migrations, sorted by version.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        up: include_str!("../sql/migrations/0001_initial_schema.up.sql"),
+        down: include_str!("../sql/migrations/0001_initial_schema.down.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "thought_search",
+        up: include_str!("../sql/migrations/0002_thought_search.up.sql"),
+        down: include_str!("../sql/migrations/0002_thought_search.down.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "thought_listing",
+        up: include_str!("../sql/migrations/0003_thought_listing.up.sql"),
+        down: include_str!("../sql/migrations/0003_thought_listing.down.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "thought_trash",
+        up: include_str!("../sql/migrations/0004_thought_trash.up.sql"),
+        down: include_str!("../sql/migrations/0004_thought_trash.down.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "thought_revision",
+        up: include_str!("../sql/migrations/0005_thought_revision.up.sql"),
+        down: include_str!("../sql/migrations/0005_thought_revision.down.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "thought_source",
+        up: include_str!("../sql/migrations/0006_thought_source.up.sql"),
+        down: include_str!("../sql/migrations/0006_thought_source.down.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "thought_author",
+        up: include_str!("../sql/migrations/0007_thought_author.up.sql"),
+        down: include_str!("../sql/migrations/0007_thought_author.down.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "thought_link",
+        up: include_str!("../sql/migrations/0008_thought_link.up.sql"),
+        down: include_str!("../sql/migrations/0008_thought_link.down.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "thought_content_format",
+        up: include_str!("../sql/migrations/0009_thought_content_format.up.sql"),
+        down: include_str!("../sql/migrations/0009_thought_content_format.down.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "thought_attachment",
+        up: include_str!("../sql/migrations/0010_thought_attachment.up.sql"),
+        down: include_str!("../sql/migrations/0010_thought_attachment.down.sql"),
+    },
+];
+
+/// State of a migration in the database.
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub migration: &'static Migration,
+    pub applied: bool,
+}
+
+/// Apply, revert and check the migrations against a database.
+pub struct Migrator {
+    pool: Pool,
+}
+
+impl Migrator {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    /// Check out a database connection from the pool.
+    async fn get_client(&self) -> StdResult<Object> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| anyhow!(e).context("Could not get a database connection from the pool."))
+    }
+
+    /// Open a transaction holding the migration lock, it is released when the transaction ends.
+    async fn lock<'a>(&self, client: &'a mut Object) -> StdResult<Transaction<'a>> {
+        let transaction = client.transaction().await?;
+        transaction
+            .execute("select pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK_KEY])
+            .await
+            .map_err(|e| anyhow!(e).context("Could not take the migration lock."))?;
+
+        Ok(transaction)
+    }
+
+    /// Create the bookkeeping table if it does not exist.
+    async fn init(&self, client: &impl GenericClient) -> StdResult<()> {
+        let sql = format!(
+            "create table if not exists {BOOKKEEPING_TABLE} (\
+            version integer not null primary key, \
+            name text not null, \
+            applied_at timestamp with time zone not null default now())"
+        );
+
+        client
+            .batch_execute(&sql)
+            .await
+            .map_err(|e| anyhow!(e).context("Could not create the migrations bookkeeping table."))
+    }
+
+    /// Versions applied to the database, in ascending order. Nothing is applied if the
+    /// bookkeeping table does not exist yet, it is not created so reading the state of the
+    /// database never modifies it.
+    async fn get_applied_versions(&self, client: &impl GenericClient) -> StdResult<Vec<i32>> {
+        let initialized: bool = client
+            .query_one("select to_regclass($1) is not null", &[&BOOKKEEPING_TABLE])
+            .await
+            .map_err(|e| {
+                anyhow!(e).context("Could not look for the migrations bookkeeping table.")
+            })?
+            .get(0);
+
+        if !initialized {
+            return Ok(Vec::new());
+        }
+
+        let rows = client
+            .query(
+                &format!("select version from {BOOKKEEPING_TABLE} order by version"),
+                &[],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context("Could not read the applied migrations."))?;
+
+        Ok(rows.iter().map(|row| row.get("version")).collect())
+    }
+
+    /// Return the state of every known migration.
+    pub async fn status(&self) -> StdResult<Vec<MigrationStatus>> {
+        let client = self.get_client().await?;
+        let applied = self.get_applied_versions(&client).await?;
+
+        Ok(MIGRATIONS
+            .iter()
+            .map(|migration| MigrationStatus {
+                migration,
+                applied: applied.contains(&migration.version),
+            })
+            .collect())
+    }
+
+    /// Apply all the pending migrations, each one in its own transaction. The applied migrations
+    /// are returned.
+    pub async fn up(&self) -> StdResult<Vec<&'static Migration>> {
+        let mut client = self.get_client().await?;
+        let mut migrated = Vec::new();
+
+        // The pending migrations are read again under the lock before each one is applied, the
+        // ones applied meanwhile by another process are skipped.
+        loop {
+            let transaction = self.lock(&mut client).await?;
+            self.init(&transaction).await?;
+            let applied = self.get_applied_versions(&transaction).await?;
+            let migration = match MIGRATIONS.iter().find(|m| !applied.contains(&m.version)) {
+                Some(migration) => migration,
+                None => break,
+            };
+            trace!(
+                "applying migration {} '{}'",
+                migration.version,
+                migration.name
+            );
+            let sql = format!(
+                "{}\ninsert into {BOOKKEEPING_TABLE} (version, name) values ({}, '{}');",
+                migration.up, migration.version, migration.name
+            );
+            Self::execute_in_transaction(transaction, &sql)
+                .await
+                .map_err(|e| {
+                    e.context(format!(
+                        "Could not apply migration {} '{}'.",
+                        migration.version, migration.name
+                    ))
+                })?;
+            info!(
+                "Migration {} '{}' applied.",
+                migration.version, migration.name
+            );
+            migrated.push(migration);
+        }
+
+        Ok(migrated)
+    }
+
+    /// Revert the last applied migration, if any.
+    pub async fn down(&self) -> StdResult<Option<&'static Migration>> {
+        let mut client = self.get_client().await?;
+        let transaction = self.lock(&mut client).await?;
+        let last_version = match self.get_applied_versions(&transaction).await?.pop() {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.version == last_version)
+            .ok_or_else(|| {
+                anyhow!("Migration {last_version} is applied but unknown to this version of the application.")
+            })?;
+        let sql = format!(
+            "{}\ndelete from {BOOKKEEPING_TABLE} where version = {};",
+            migration.down, migration.version
+        );
+        Self::execute_in_transaction(transaction, &sql)
+            .await
+            .map_err(|e| {
+                e.context(format!(
+                    "Could not revert migration {} '{}'.",
+                    migration.version, migration.name
+                ))
+            })?;
+        info!(
+            "Migration {} '{}' reverted.",
+            migration.version, migration.name
+        );
+
+        Ok(Some(migration))
+    }
+
+    /// Execute the given SQL in the transaction and commit it, it is rolled back on error.
+    async fn execute_in_transaction(transaction: Transaction<'_>, sql: &str) -> StdResult<()> {
+        transaction.batch_execute(sql).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Ensure the database schema matches the migrations compiled in this binary. The database is
+    /// only read.
+    pub async fn check(&self) -> StdResult<()> {
+        let client = self.get_client().await?;
+        let applied = self.get_applied_versions(&client).await?;
+        let known: Vec<i32> = MIGRATIONS.iter().map(|m| m.version).collect();
+
+        if applied.iter().any(|version| !known.contains(version)) {
+            return Err(anyhow!(
+                "Database schema is newer than this binary (applied migrations: {applied:?}, known: {known:?}), upgrade the application."
+            ));
+        }
+
+        if applied != known {
+            return Err(anyhow!(
+                "Database schema is out of date (applied migrations: {applied:?}, expected: {known:?}), run the 'migrate up' command."
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_database::TestDatabase;
+
+    use super::*;
+
+    #[test]
+    fn migrations_are_sorted_and_unique() {
+        let versions: Vec<i32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let mut sorted = versions.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        assert_eq!(sorted, versions);
+    }
+
+    #[tokio::test]
+    async fn check_does_not_create_the_bookkeeping_table() -> StdResult<()> {
+        let database = match TestDatabase::create().await? {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let migrator = database.get_dependencies().build_migrator().await?;
+
+        let error = migrator.check().await.unwrap_err();
+
+        assert!(error.to_string().contains("run the 'migrate up' command"));
+        assert!(migrator
+            .status()
+            .await?
+            .iter()
+            .all(|status| !status.applied));
+        let client = migrator.get_client().await?;
+        let row = client
+            .query_one("select to_regclass($1) is null", &[&BOOKKEEPING_TABLE])
+            .await?;
+        assert!(row.get::<_, bool>(0));
+
+        database.drop().await
+    }
+
+    #[tokio::test]
+    async fn check_tells_a_newer_schema() -> StdResult<()> {
+        let database = match TestDatabase::create().await? {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let migrator = database.get_dependencies().build_migrator().await?;
+        migrator.up().await?;
+        migrator.check().await?;
+
+        let client = migrator.get_client().await?;
+        client
+            .execute(
+                &format!("insert into {BOOKKEEPING_TABLE} (version, name) values ($1, 'future')"),
+                &[&i32::MAX],
+            )
+            .await?;
+        let error = migrator.check().await.unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("Database schema is newer than this binary"));
+
+        database.drop().await
+    }
+
+    #[tokio::test]
+    async fn concurrent_migrations_are_applied_once() -> StdResult<()> {
+        let database = match TestDatabase::create().await? {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let first = database.get_dependencies().build_migrator().await?;
+        let second = database.get_dependencies().build_migrator().await?;
+
+        let (first_migrated, second_migrated) = tokio::try_join!(first.up(), second.up())?;
+
+        assert_eq!(
+            MIGRATIONS.len(),
+            first_migrated.len() + second_migrated.len()
+        );
+        first.check().await?;
+
+        while first.down().await?.is_some() {}
+        assert!(first.status().await?.iter().all(|status| !status.applied));
+
+        database.drop().await
+    }
+}
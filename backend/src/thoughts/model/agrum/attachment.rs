@@ -0,0 +1,195 @@
+use agrum::{
+    core::{
+        HydrationError, Projection, Provider, SourceAliases, SqlDefinition, SqlEntity, Structure,
+        Structured, WhereCondition,
+    },
+    params,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use tokio_postgres::{Client, Row};
+use uuid::Uuid;
+
+use crate::StdResult;
+
+use super::get_timestamp;
+
+/// File attached to a thought, its content lives in the blob store.
+#[derive(Debug)]
+pub struct AttachmentEntity {
+    pub attachment_id: Uuid,
+    pub thought_id: Uuid,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Structured for AttachmentEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[
+            ("attachment_id", "text"),
+            ("thought_id", "text"),
+            ("file_name", "text"),
+            ("mime_type", "text"),
+            ("size", "bigint"),
+            ("hash", "text"),
+            ("created_at", "timestamp"),
+        ])
+    }
+}
+
+impl SqlEntity for AttachmentEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        let created_at = get_timestamp(&row, "created_at")?;
+
+        Ok(Self {
+            attachment_id: row.get("attachment_id"),
+            thought_id: row.get("thought_id"),
+            file_name: row.get("file_name"),
+            mime_type: row.get("mime_type"),
+            size: row.get("size"),
+            hash: row.get("hash"),
+            created_at,
+        })
+    }
+}
+
+/// Attachments whatever the state of their thought, oldest first.
+#[derive(Debug, Default)]
+pub struct AttachmentSqlDefinition {
+    projection: Projection<AttachmentEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for AttachmentSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "select {projection} from thought.attachment \
+            where {condition} order by created_at, attachment_id"
+        )
+    }
+}
+
+pub struct AttachmentRepository<'client> {
+    client: &'client Client,
+}
+
+impl<'client> AttachmentRepository<'client> {
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    fn provider(&self) -> Provider<'client, AttachmentEntity> {
+        Provider::new(self.client, Box::new(AttachmentSqlDefinition::default()))
+    }
+
+    pub async fn get_attachment(
+        &self,
+        attachment_id: &Uuid,
+    ) -> StdResult<Option<AttachmentEntity>> {
+        let condition = WhereCondition::new("attachment_id = $?", params![attachment_id]);
+        let entity = self
+            .provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .pop();
+
+        Ok(entity)
+    }
+
+    /// Fetch the attachments of the given thought.
+    pub async fn get_attachments(&self, thought_id: &Uuid) -> StdResult<Vec<AttachmentEntity>> {
+        let condition = WhereCondition::new("thought_id = $?", params![thought_id]);
+
+        self.provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Insert a new attachment, the creation date is set by the database.
+    pub async fn insert_attachment(
+        &self,
+        attachment: &AttachmentEntity,
+    ) -> StdResult<AttachmentEntity> {
+        let sql = format!(
+            "insert into thought.attachment \
+            (attachment_id, thought_id, file_name, mime_type, size, hash) \
+            values ($1, $2, $3, $4, $5, $6) \
+            returning {}",
+            Projection::<AttachmentEntity>::default().expand(&SourceAliases::default())
+        );
+        let row = self
+            .client
+            .query_one(
+                &sql,
+                &[
+                    &attachment.attachment_id,
+                    &attachment.thought_id,
+                    &attachment.file_name,
+                    &attachment.mime_type,
+                    &attachment.size,
+                    &attachment.hash,
+                ],
+            )
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!(
+                    "Could not insert attachment '{}'.",
+                    attachment.attachment_id
+                ))
+            })?;
+
+        AttachmentEntity::hydrate(row).map_err(|e| anyhow!(e))
+    }
+
+    /// Remove an attachment, return false if it did not exist.
+    pub async fn delete_attachment(&self, attachment_id: &Uuid) -> StdResult<bool> {
+        let deleted = self
+            .client
+            .execute(
+                "delete from thought.attachment where attachment_id = $1",
+                &[attachment_id],
+            )
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!("Could not delete attachment '{attachment_id}'."))
+            })?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Hashes of the contents referenced by at least one attachment.
+    pub async fn get_attachment_hashes(&self) -> StdResult<Vec<String>> {
+        let rows = self
+            .client
+            .query("select distinct hash from thought.attachment", &[])
+            .await
+            .map_err(|e| anyhow!(e).context("Could not fetch the attachment hashes."))?;
+
+        Ok(rows.into_iter().map(|row| row.get("hash")).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attachment_sql_definition() {
+        let definition = AttachmentSqlDefinition::default();
+
+        assert_eq!(
+            "select attachment_id as attachment_id, thought_id as thought_id, file_name as file_name, mime_type as mime_type, size as size, hash as hash, created_at as created_at from thought.attachment where true order by created_at, attachment_id".to_string(),
+            definition.expand("true")
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use agrum::{
+    core::{
+        HydrationError, Projection, Provider, SourceAliases, SqlDefinition, SqlEntity, Structure,
+        Structured, WhereCondition,
+    },
+    params,
+};
+use anyhow::anyhow;
+use tokio_postgres::{Client, Row, Transaction};
+use uuid::Uuid;
+
+use crate::StdResult;
+
+/// Author indexed across the sources with the names it is known by.
+#[derive(Debug)]
+pub struct AuthorEntity {
+    pub author_id: Uuid,
+    pub name: String,
+    pub aliases: Vec<String>,
+}
+
+impl Structured for AuthorEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[
+            ("author_id", "text"),
+            ("name", "text"),
+            ("aliases", "text[]"),
+        ])
+    }
+}
+
+impl SqlEntity for AuthorEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            author_id: row.get("author_id"),
+            name: row.get("name"),
+            aliases: row.get("aliases"),
+        })
+    }
+}
+
+/// Authors with their aliases, sorted by name.
+#[derive(Debug, Default)]
+pub struct AuthorSqlDefinition {
+    projection: Projection<AuthorEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for AuthorSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "select {projection} from (\
+            select author.*, array(\
+            select alias.alias from thought.author_alias as alias \
+            where alias.author_id = author.author_id order by alias.alias\
+            ) as aliases \
+            from thought.author as author\
+            ) as author \
+            where {condition} order by name, author_id"
+        )
+    }
+}
+
+pub struct AuthorRepository<'client> {
+    client: &'client Client,
+}
+
+impl<'client> AuthorRepository<'client> {
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    fn provider(&self) -> Provider<'client, AuthorEntity> {
+        Provider::new(self.client, Box::new(AuthorSqlDefinition::default()))
+    }
+
+    pub async fn get_author(&self, author_id: &Uuid) -> StdResult<Option<AuthorEntity>> {
+        let condition = WhereCondition::new("author_id = $?", params![author_id]);
+        let entity = self
+            .provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .pop();
+
+        Ok(entity)
+    }
+
+    pub async fn get_authors(&self) -> StdResult<Vec<AuthorEntity>> {
+        self.provider()
+            .fetch(WhereCondition::default())
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Fetch the author known by the alias having the given key.
+    pub async fn find_author(&self, alias_key: &str) -> StdResult<Option<AuthorEntity>> {
+        let condition = WhereCondition::new(
+            "author_id = (select author_id from thought.author_alias where alias_key = $?)",
+            params![alias_key],
+        );
+        let entity = self
+            .provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .pop();
+
+        Ok(entity)
+    }
+
+    /// Index a new author within the given transaction, its name is registered as its first
+    /// alias under the given key. If the key is already taken, None is returned and the
+    /// transaction must be rolled back to discard the author.
+    pub async fn insert_author(
+        transaction: &Transaction<'_>,
+        author: &AuthorEntity,
+        alias_key: &str,
+    ) -> StdResult<Option<AuthorEntity>> {
+        let sql = format!(
+            "with author as (\
+            insert into thought.author (author_id, name) values ($1, $2) returning *\
+            ), \
+            alias as (\
+            insert into thought.author_alias (alias_key, alias, author_id) values ($3, $2, $1) \
+            on conflict (alias_key) do nothing \
+            returning author_id\
+            ) \
+            select {} from (\
+            select author.*, array[$2] as aliases from author join alias using (author_id)\
+            ) as author",
+            Projection::<AuthorEntity>::default().expand(&SourceAliases::default())
+        );
+        let row = transaction
+            .query_opt(&sql, &[&author.author_id, &author.name, &alias_key])
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!("Could not insert author '{}'.", author.author_id))
+            })?;
+
+        row.map(|row| AuthorEntity::hydrate(row).map_err(|e| anyhow!(e)))
+            .transpose()
+    }
+
+    /// Register an alias of an author within the given transaction. If another author is known
+    /// by this alias, it is merged into the given one: its sources and aliases are moved over
+    /// and it is removed.
+    pub async fn add_author_alias(
+        transaction: &Transaction<'_>,
+        author_id: &Uuid,
+        alias: &str,
+        alias_key: &str,
+    ) -> StdResult<()> {
+        let context = || format!("Could not add alias '{alias}' to author '{author_id}'.");
+        let inserted = transaction
+            .execute(
+                "insert into thought.author_alias (alias_key, alias, author_id) \
+                values ($1, $2, $3) \
+                on conflict (alias_key) do nothing",
+                &[&alias_key, &alias, author_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+
+        if inserted > 0 {
+            return Ok(());
+        }
+        let absorbed_id: Uuid = transaction
+            .query_one(
+                "select author_id from thought.author_alias where alias_key = $1 for update",
+                &[&alias_key],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?
+            .get("author_id");
+
+        if absorbed_id != *author_id {
+            transaction
+                .execute(
+                    "insert into thought.source_author (source_id, author_id, position) \
+                    select source_id, $1, position from thought.source_author \
+                    where author_id = $2 \
+                    on conflict (source_id, author_id) do nothing",
+                    &[author_id, &absorbed_id],
+                )
+                .await
+                .map_err(|e| anyhow!(e).context(context()))?;
+            transaction
+                .execute(
+                    "update thought.author_alias set author_id = $1 where author_id = $2",
+                    &[author_id, &absorbed_id],
+                )
+                .await
+                .map_err(|e| anyhow!(e).context(context()))?;
+            transaction
+                .execute(
+                    "delete from thought.author where author_id = $1",
+                    &[&absorbed_id],
+                )
+                .await
+                .map_err(|e| anyhow!(e).context(context()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn author_sql_definition() {
+        let definition = AuthorSqlDefinition::default();
+
+        assert_eq!(
+            "select author_id as author_id, name as name, aliases as aliases from (select author.*, array(select alias.alias from thought.author_alias as alias where alias.author_id = author.author_id order by alias.alias) as aliases from thought.author as author) as author where true order by name, author_id".to_string(),
+            definition.expand("true")
+        );
+    }
+}
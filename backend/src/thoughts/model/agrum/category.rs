@@ -0,0 +1,97 @@
+use agrum::core::{
+    HydrationError, Projection, Provider, SourceAliases, SqlDefinition, SqlEntity, Structure,
+    Structured, WhereCondition,
+};
+use anyhow::anyhow;
+use tokio_postgres::{Client, Row};
+
+use crate::StdResult;
+
+/// Number of thoughts filed under a category or one of its sub categories.
+#[derive(Debug)]
+pub struct CategoryCountEntity {
+    pub path: String,
+    pub thought_count: i64,
+}
+
+impl Structured for CategoryCountEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[("path", "text"), ("thought_count", "bigint")])
+    }
+}
+
+impl SqlEntity for CategoryCountEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            path: row.get("path"),
+            thought_count: row.get("thought_count"),
+        })
+    }
+}
+
+/// Every node of the category tree, including the intermediate paths no thought is directly
+/// filed under, with the number of thoughts filed under the node or one of its descendants.
+/// The condition filters the thoughts taken into account. Nodes are sorted by path so parents
+/// come before their children.
+#[derive(Debug, Default)]
+pub struct CategoryCountSqlDefinition {
+    projection: Projection<CategoryCountEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for CategoryCountSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "with filed as (select thought.thought_id, thought.categories from thought.thought as thought where thought.deleted_at is null and ({condition})), \
+            node as (\
+            select distinct subpath(category, 0, depth) as path \
+            from filed, unnest(filed.categories) as category, generate_series(1, nlevel(category)) as depth\
+            ), \
+            category_count as (\
+            select node.path::text as path, count(filed.thought_id) as thought_count \
+            from node join filed on filed.categories <@ node.path \
+            group by node.path\
+            ) \
+            select {projection} from category_count order by path"
+        )
+    }
+}
+
+pub struct CategoryRepository<'client> {
+    client: &'client Client,
+}
+
+impl<'client> CategoryRepository<'client> {
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    pub async fn get_category_counts(&self) -> StdResult<Vec<CategoryCountEntity>> {
+        Provider::new(self.client, Box::new(CategoryCountSqlDefinition::default()))
+            .fetch(WhereCondition::default())
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn category_count_sql_definition() -> StdResult<()> {
+        let definition = CategoryCountSqlDefinition::default();
+
+        assert_eq!(
+            "with filed as (select thought.thought_id, thought.categories from thought.thought as thought where thought.deleted_at is null and (true)), node as (select distinct subpath(category, 0, depth) as path from filed, unnest(filed.categories) as category, generate_series(1, nlevel(category)) as depth), category_count as (select node.path::text as path, count(filed.thought_id) as thought_count from node join filed on filed.categories <@ node.path group by node.path) select path as path, thought_count as thought_count from category_count order by path".to_string(),
+            definition.expand("true")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use agrum::core::{HydrationError, SqlEntity};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use tokio_postgres::{types::FromSql, Row};
+
+/// Outcome of the hydration of a single row of a listing.
+pub type Hydrated<E> = Result<E, HydrationError>;
+
+/// Hydrate the rows of a listing one by one, so a row that cannot be read does not fail the
+/// others and the caller decides what to do with it.
+pub fn hydrate_rows<E: SqlEntity>(rows: Vec<Row>) -> Vec<Hydrated<E>> {
+    rows.into_iter().map(E::hydrate).collect()
+}
+
+/// Read a column, an error is returned instead of panicking if it is NULL or of another type.
+pub fn get_field<'a, T: FromSql<'a>>(row: &'a Row, field: &str) -> Result<T, HydrationError> {
+    row.try_get(field).map_err(|e| {
+        HydrationError::InvalidData(format!(
+            "Could not read data for field '{field}'. Error = '{e}'."
+        ))
+    })
+}
+
+/// Read a `timestamp without time zone` column, the sessions use the UTC time zone so the
+/// database stores the dates in UTC.
+pub fn get_timestamp(row: &Row, field: &str) -> Result<DateTime<Utc>, HydrationError> {
+    row.try_get::<_, NaiveDateTime>(field)
+        .map(|date| Utc.from_utc_datetime(&date))
+        .map_err(|e| {
+            HydrationError::InvalidData(format!(
+                "Could not read timestamp data for field '{field}'. Error = '{e}'."
+            ))
+        })
+}
+
+/// Read a `jsonb` column as is, its content is checked when the entity is converted to the
+/// model.
+pub fn get_json(row: &Row, field: &str) -> Result<Value, HydrationError> {
+    row.try_get::<_, Value>(field).map_err(|e| {
+        HydrationError::InvalidData(format!(
+            "Could not read JSON data for field '{field}'. Error = '{e}'."
+        ))
+    })
+}
@@ -0,0 +1,184 @@
+use agrum::{
+    core::{
+        HydrationError, Projection, Provider, SourceAliases, SqlDefinition, SqlEntity, Structure,
+        Structured, WhereCondition,
+    },
+    params,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use tokio_postgres::{Client, Row};
+use uuid::Uuid;
+
+use crate::{thoughts::model::LinkKind, StdResult};
+
+use super::get_timestamp;
+
+/// Typed link between two thoughts.
+#[derive(Debug)]
+pub struct ThoughtLinkEntity {
+    pub link_id: Uuid,
+    pub from_thought_id: Uuid,
+    pub to_thought_id: Uuid,
+    pub kind: LinkKind,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Structured for ThoughtLinkEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[
+            ("link_id", "text"),
+            ("from_thought_id", "text"),
+            ("to_thought_id", "text"),
+            ("kind", "text"),
+            ("created_at", "timestamp"),
+        ])
+    }
+}
+
+impl SqlEntity for ThoughtLinkEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        let created_at = get_timestamp(&row, "created_at")?;
+        let kind: String = row.get("kind");
+        let kind = LinkKind::parse(&kind).ok_or_else(|| {
+            HydrationError::InvalidData(format!("Unknown link kind '{kind}' for field 'kind'."))
+        })?;
+
+        Ok(Self {
+            link_id: row.get("link_id"),
+            from_thought_id: row.get("from_thought_id"),
+            to_thought_id: row.get("to_thought_id"),
+            kind,
+            created_at,
+        })
+    }
+}
+
+/// Links between live thoughts, oldest first. Links to or from a deleted thought are hidden
+/// until it is restored.
+#[derive(Debug, Default)]
+pub struct ThoughtLinkSqlDefinition {
+    projection: Projection<ThoughtLinkEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for ThoughtLinkSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "select {projection} from (\
+            select link.* from thought.thought_link as link \
+            join thought.thought as origin on origin.thought_id = link.from_thought_id \
+            join thought.thought as target on target.thought_id = link.to_thought_id \
+            where origin.deleted_at is null and target.deleted_at is null\
+            ) as link \
+            where {condition} order by created_at, link_id"
+        )
+    }
+}
+
+pub struct LinkRepository<'client> {
+    client: &'client Client,
+}
+
+impl<'client> LinkRepository<'client> {
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    fn provider(&self) -> Provider<'client, ThoughtLinkEntity> {
+        Provider::new(self.client, Box::new(ThoughtLinkSqlDefinition::default()))
+    }
+
+    pub async fn get_link(&self, link_id: &Uuid) -> StdResult<Option<ThoughtLinkEntity>> {
+        let condition = WhereCondition::new("link_id = $?", params![link_id]);
+        let entity = self
+            .provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .pop();
+
+        Ok(entity)
+    }
+
+    /// Fetch the links starting from the given thought.
+    pub async fn get_outgoing_links(&self, thought_id: &Uuid) -> StdResult<Vec<ThoughtLinkEntity>> {
+        let condition = WhereCondition::new("from_thought_id = $?", params![thought_id]);
+
+        self.provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Fetch the links pointing to the given thought.
+    pub async fn get_backlinks(&self, thought_id: &Uuid) -> StdResult<Vec<ThoughtLinkEntity>> {
+        let condition = WhereCondition::new("to_thought_id = $?", params![thought_id]);
+
+        self.provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Insert a new link, the creation date is set by the database.
+    pub async fn insert_link(&self, link: &ThoughtLinkEntity) -> StdResult<ThoughtLinkEntity> {
+        let sql = format!(
+            "insert into thought.thought_link (link_id, from_thought_id, to_thought_id, kind) \
+            values ($1, $2, $3, $4) \
+            returning {}",
+            Projection::<ThoughtLinkEntity>::default().expand(&SourceAliases::default())
+        );
+        let row = self
+            .client
+            .query_one(
+                &sql,
+                &[
+                    &link.link_id,
+                    &link.from_thought_id,
+                    &link.to_thought_id,
+                    &link.kind.as_str(),
+                ],
+            )
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!("Could not insert link '{}'.", link.link_id))
+            })?;
+
+        ThoughtLinkEntity::hydrate(row).map_err(|e| anyhow!(e))
+    }
+
+    /// Remove a link, return false if it did not exist.
+    pub async fn delete_link(&self, link_id: &Uuid) -> StdResult<bool> {
+        let deleted = self
+            .client
+            .execute(
+                "delete from thought.thought_link where link_id = $1",
+                &[link_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(format!("Could not delete link '{link_id}'.")))?;
+
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thought_link_sql_definition() {
+        let definition = ThoughtLinkSqlDefinition::default();
+
+        assert_eq!(
+            "select link_id as link_id, from_thought_id as from_thought_id, to_thought_id as to_thought_id, kind as kind, created_at as created_at from (select link.* from thought.thought_link as link join thought.thought as origin on origin.thought_id = link.from_thought_id join thought.thought as target on target.thought_id = link.to_thought_id where origin.deleted_at is null and target.deleted_at is null) as link where true order by created_at, link_id".to_string(),
+            definition.expand("true")
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use agrum::core::{
+    HydrationError, Projection, SourceAliases, SqlDefinition, SqlEntity, Structure, Structured,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use tokio_postgres::{types::ToSql, Client, Row};
+use uuid::Uuid;
+
+use crate::{
+    thoughts::model::{ListingCursor, ThreadOrder},
+    StdResult,
+};
+
+use super::{get_field, get_timestamp, hydrate_rows, Hydrated, ThoughtEntity};
+
+/// Thread root with the date of its most recent thought and its number of thoughts.
+#[derive(Debug)]
+pub struct ThreadSummaryEntity {
+    pub thread: ThoughtEntity,
+    pub last_activity: DateTime<Utc>,
+    pub thought_count: i64,
+}
+
+impl Structured for ThreadSummaryEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[
+            ("thought_id", "text"),
+            ("parent_thought_id", "text"),
+            ("keywords", "text[]"),
+            ("categories", "ltree[]"),
+            ("source_ids", "uuid[]"),
+            ("created_at", "timestamp"),
+            ("content", "jsonb"),
+            ("content_format", "text"),
+            ("last_activity", "timestamp"),
+            ("thought_count", "bigint"),
+        ])
+    }
+}
+
+impl SqlEntity for ThreadSummaryEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        let last_activity = get_timestamp(&row, "last_activity")?;
+        let thought_count = get_field(&row, "thought_count")?;
+
+        Ok(Self {
+            thread: ThoughtEntity::hydrate(row)?,
+            last_activity,
+            thought_count,
+        })
+    }
+}
+
+/// Thread roots with their activity, most recent first according to the given order. The
+/// thought identifier breaks the ties so the order is total and can be resumed from a cursor.
+#[derive(Debug)]
+pub struct ThreadListSqlDefinition {
+    order: ThreadOrder,
+    projection: Projection<ThreadSummaryEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl ThreadListSqlDefinition {
+    pub fn new(order: ThreadOrder) -> Self {
+        Self {
+            order,
+            projection: Projection::default(),
+            source_aliases: SourceAliases::default(),
+        }
+    }
+
+    /// Column the threads are sorted by.
+    pub fn sort_column(&self) -> &'static str {
+        match self.order {
+            ThreadOrder::CreatedAt => "created_at",
+            ThreadOrder::LastActivity => "last_activity",
+        }
+    }
+}
+
+impl SqlDefinition for ThreadListSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+        let sort_column = self.sort_column();
+
+        format!(
+            "with recursive member as (\
+            select thought.thought_id as root_id, thought.thought_id, thought.created_at \
+            from thought.thought as thought \
+            where thought.parent_thought_id is null and thought.deleted_at is null \
+            union all \
+            select member.root_id, child.thought_id, child.created_at \
+            from thought.thought as child join member on child.parent_thought_id = member.thought_id \
+            where child.deleted_at is null\
+            ), \
+            activity as (\
+            select root_id, max(created_at) as last_activity, count(*) as thought_count \
+            from member group by root_id\
+            ), \
+            summary as (\
+            select thought.*, activity.last_activity, activity.thought_count \
+            from thought.thought as thought join activity on activity.root_id = thought.thought_id\
+            ) \
+            select {projection} from summary where {condition} \
+            order by {sort_column} desc, thought_id desc"
+        )
+    }
+}
+
+/// All the thoughts of the thread whose root identifier is the first parameter, oldest first.
+/// The thought identifier breaks the ties so the order is total and can be resumed from a
+/// cursor.
+#[derive(Debug, Default)]
+pub struct ThreadThoughtsSqlDefinition {
+    projection: Projection<ThoughtEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for ThreadThoughtsSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "with recursive member as (\
+            select thought.* from thought.thought as thought \
+            where thought.thought_id = $1 and thought.parent_thought_id is null \
+            and thought.deleted_at is null \
+            union all \
+            select child.* from thought.thought as child \
+            join member on child.parent_thought_id = member.thought_id \
+            where child.deleted_at is null\
+            ) \
+            select {projection} from member where {condition} order by created_at, thought_id"
+        )
+    }
+}
+
+/// Keyset paginated listings of threads and thoughts.
+pub struct ListingRepository<'client> {
+    client: &'client Client,
+}
+
+impl<'client> ListingRepository<'client> {
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    /// Fetch at most `limit` thread roots sorted by the given order, starting right after the
+    /// cursor if any.
+    pub async fn list_threads(
+        &self,
+        order: ThreadOrder,
+        after: Option<&ListingCursor>,
+        limit: i64,
+    ) -> StdResult<Vec<Hydrated<ThreadSummaryEntity>>> {
+        let definition = ThreadListSqlDefinition::new(order);
+        let position = after.map(|c| c.position.naive_utc());
+        let (sql, parameters): (String, Vec<&(dyn ToSql + Sync)>) = match (&position, after) {
+            (Some(position), Some(cursor)) => (
+                format!(
+                    "{} limit $3",
+                    definition.expand(&format!(
+                        "({}, thought_id) < ($1::timestamp, $2)",
+                        definition.sort_column()
+                    ))
+                ),
+                vec![position, &cursor.thought_id, &limit],
+            ),
+            _ => (
+                format!("{} limit $1", definition.expand("true")),
+                vec![&limit],
+            ),
+        };
+        let rows = self
+            .client
+            .query(&sql, &parameters)
+            .await
+            .map_err(|e| anyhow!(e).context("Could not list threads."))?;
+
+        Ok(hydrate_rows(rows))
+    }
+
+    /// Fetch at most `limit` thoughts of the given thread, oldest first, starting right after
+    /// the cursor if any. Nothing is returned if the identifier is not a thread root.
+    pub async fn list_thread_thoughts(
+        &self,
+        thread_id: &Uuid,
+        after: Option<&ListingCursor>,
+        limit: i64,
+    ) -> StdResult<Vec<Hydrated<ThoughtEntity>>> {
+        let definition = ThreadThoughtsSqlDefinition::default();
+        let position = after.map(|c| c.position.naive_utc());
+        let (sql, parameters): (String, Vec<&(dyn ToSql + Sync)>) = match (&position, after) {
+            (Some(position), Some(cursor)) => (
+                format!(
+                    "{} limit $4",
+                    definition.expand("(created_at, thought_id) > ($2::timestamp, $3)")
+                ),
+                vec![thread_id, position, &cursor.thought_id, &limit],
+            ),
+            _ => (
+                format!("{} limit $2", definition.expand("true")),
+                vec![thread_id, &limit],
+            ),
+        };
+        let rows = self.client.query(&sql, &parameters).await.map_err(|e| {
+            anyhow!(e).context(format!(
+                "Could not list the thoughts of thread '{thread_id}'."
+            ))
+        })?;
+
+        Ok(hydrate_rows(rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_list_sql_definition() {
+        let definition = ThreadListSqlDefinition::new(ThreadOrder::LastActivity);
+
+        assert_eq!(
+            "with recursive member as (select thought.thought_id as root_id, thought.thought_id, thought.created_at from thought.thought as thought where thought.parent_thought_id is null and thought.deleted_at is null union all select member.root_id, child.thought_id, child.created_at from thought.thought as child join member on child.parent_thought_id = member.thought_id where child.deleted_at is null), activity as (select root_id, max(created_at) as last_activity, count(*) as thought_count from member group by root_id), summary as (select thought.*, activity.last_activity, activity.thought_count from thought.thought as thought join activity on activity.root_id = thought.thought_id) select thought_id as thought_id, parent_thought_id as parent_thought_id, keywords as keywords, categories as categories, source_ids as source_ids, created_at as created_at, content as content, content_format as content_format, last_activity as last_activity, thought_count as thought_count from summary where true order by last_activity desc, thought_id desc".to_string(),
+            definition.expand("true")
+        );
+    }
+
+    #[test]
+    fn thread_thoughts_sql_definition() {
+        let definition = ThreadThoughtsSqlDefinition::default();
+
+        assert_eq!(
+            "with recursive member as (select thought.* from thought.thought as thought where thought.thought_id = $1 and thought.parent_thought_id is null and thought.deleted_at is null union all select child.* from thought.thought as child join member on child.parent_thought_id = member.thought_id where child.deleted_at is null) select thought_id as thought_id, parent_thought_id as parent_thought_id, keywords as keywords, categories as categories, source_ids as source_ids, created_at as created_at, content as content, content_format as content_format from member where true order by created_at, thought_id".to_string(),
+            definition.expand("true")
+        );
+    }
+}
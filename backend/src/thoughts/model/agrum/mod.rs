@@ -1,3 +1,23 @@
+mod attachment;
+mod author;
+mod category;
+mod hydration;
+mod link;
+mod listing;
+mod revision;
+mod search;
+mod source;
 mod thought;
+mod trash;
 
+pub use attachment::*;
+pub use author::*;
+pub use category::*;
+pub use hydration::*;
+pub use link::*;
+pub use listing::*;
+pub use revision::*;
+pub use search::*;
+pub use source::*;
 pub use thought::*;
+pub use trash::*;
@@ -0,0 +1,132 @@
+use agrum::{
+    core::{
+        HydrationError, Projection, Provider, SourceAliases, SqlDefinition, SqlEntity, Structure,
+        Structured, WhereCondition,
+    },
+    params,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio_postgres::{Client, Row};
+use uuid::Uuid;
+
+use crate::StdResult;
+
+use super::{get_field, get_json, get_timestamp};
+
+/// Prior version of a thought, recorded by the database when the thought is updated.
+#[derive(Debug)]
+pub struct ThoughtRevisionEntity {
+    pub thought_id: Uuid,
+    pub revision: i32,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub source_ids: Vec<Uuid>,
+    pub content: Value,
+    pub content_format: String,
+    pub written_at: DateTime<Utc>,
+    pub superseded_at: DateTime<Utc>,
+}
+
+impl Structured for ThoughtRevisionEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[
+            ("thought_id", "text"),
+            ("revision", "integer"),
+            ("keywords", "text[]"),
+            ("categories", "ltree[]"),
+            ("source_ids", "uuid[]"),
+            ("content", "jsonb"),
+            ("content_format", "text"),
+            ("written_at", "timestamp"),
+            ("superseded_at", "timestamp"),
+        ])
+    }
+}
+
+impl SqlEntity for ThoughtRevisionEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        let written_at = get_timestamp(&row, "written_at")?;
+        let superseded_at = get_timestamp(&row, "superseded_at")?;
+
+        Ok(Self {
+            thought_id: row.get("thought_id"),
+            revision: row.get("revision"),
+            keywords: row.get("keywords"),
+            categories: row.get("categories"),
+            source_ids: row.get("source_ids"),
+            content: get_json(&row, "content")?,
+            content_format: get_field(&row, "content_format")?,
+            written_at,
+            superseded_at,
+        })
+    }
+}
+
+/// Prior versions of the thoughts, oldest first. A version was written when the previous one
+/// was superseded, the first one when the thought was created.
+#[derive(Debug, Default)]
+pub struct ThoughtRevisionSqlDefinition {
+    projection: Projection<ThoughtRevisionEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for ThoughtRevisionSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "with revision as (\
+            select revision.*, coalesce(\
+            lag(revision.superseded_at) over (partition by revision.thought_id order by revision.revision), \
+            thought.created_at\
+            ) as written_at \
+            from thought.thought_revision as revision \
+            join thought.thought as thought on thought.thought_id = revision.thought_id\
+            ) \
+            select {projection} from revision where {condition} order by revision"
+        )
+    }
+}
+
+pub struct ThoughtRevisionRepository<'client> {
+    client: &'client Client,
+}
+
+impl<'client> ThoughtRevisionRepository<'client> {
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    /// Fetch the prior versions of the given thought, oldest first.
+    pub async fn get_revisions(&self, thought_id: &Uuid) -> StdResult<Vec<ThoughtRevisionEntity>> {
+        let condition = WhereCondition::new("thought_id = $?", params![thought_id]);
+
+        Provider::new(
+            self.client,
+            Box::new(ThoughtRevisionSqlDefinition::default()),
+        )
+        .fetch(condition)
+        .await
+        .map_err(|e| anyhow!(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thought_revision_sql_definition() {
+        let definition = ThoughtRevisionSqlDefinition::default();
+
+        assert_eq!(
+            "with revision as (select revision.*, coalesce(lag(revision.superseded_at) over (partition by revision.thought_id order by revision.revision), thought.created_at) as written_at from thought.thought_revision as revision join thought.thought as thought on thought.thought_id = revision.thought_id) select thought_id as thought_id, revision as revision, keywords as keywords, categories as categories, source_ids as source_ids, content as content, content_format as content_format, written_at as written_at, superseded_at as superseded_at from revision where true order by revision".to_string(),
+            definition.expand("true")
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use agrum::core::{
+    HydrationError, Projection, SourceAliases, SqlDefinition, SqlEntity, Structure, Structured,
+};
+use tokio_postgres::Row;
+
+use super::{get_field, ThoughtEntity};
+
+/// Thought matching a full text search query.
+#[derive(Debug)]
+pub struct ThoughtSearchEntity {
+    pub thought: ThoughtEntity,
+    pub rank: f32,
+    pub snippet: String,
+}
+
+impl Structured for ThoughtSearchEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[
+            ("thought_id", "text"),
+            ("parent_thought_id", "text"),
+            ("keywords", "text[]"),
+            ("categories", "ltree[]"),
+            ("source_ids", "uuid[]"),
+            ("created_at", "timestamp"),
+            ("content", "jsonb"),
+            ("content_format", "text"),
+            ("rank", "real"),
+            ("snippet", "text"),
+        ])
+    }
+}
+
+impl SqlEntity for ThoughtSearchEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        let rank = get_field(&row, "rank")?;
+        let snippet = get_field(&row, "snippet")?;
+
+        Ok(Self {
+            thought: ThoughtEntity::hydrate(row)?,
+            rank,
+            snippet,
+        })
+    }
+}
+
+/// Full text search over the thoughts content and keywords, best matches first. The query
+/// expects the text search configuration as first parameter and the search terms, in web search
+/// syntax, as second parameter. Matching terms are enclosed in the highlight markers in the
+/// snippet, it is plain text to be escaped before the markers are turned into HTML.
+#[derive(Debug, Default)]
+pub struct ThoughtSearchSqlDefinition {
+    projection: Projection<ThoughtSearchEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for ThoughtSearchSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "with search as (\
+            select thought.*, ts_rank(thought.search_vector, query) as rank, \
+            ts_headline(thought.search_language, coalesce(thought.search_text, thought.content #>> '{{}}'), query, \
+            'StartSel=\u{2}, StopSel=\u{3}, MaxFragments=2') as snippet \
+            from thought.thought as thought, websearch_to_tsquery($1::text::regconfig, $2) as query \
+            where thought.search_vector @@ query and thought.deleted_at is null\
+            ) \
+            select {projection} from search where {condition} order by rank desc, created_at desc"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StdResult;
+
+    use super::*;
+
+    #[test]
+    fn thought_search_sql_definition() -> StdResult<()> {
+        let definition = ThoughtSearchSqlDefinition::default();
+
+        assert_eq!(
+            "with search as (select thought.*, ts_rank(thought.search_vector, query) as rank, ts_headline(thought.search_language, coalesce(thought.search_text, thought.content #>> '{}'), query, 'StartSel=\u{2}, StopSel=\u{3}, MaxFragments=2') as snippet from thought.thought as thought, websearch_to_tsquery($1::text::regconfig, $2) as query where thought.search_vector @@ query and thought.deleted_at is null) select thought_id as thought_id, parent_thought_id as parent_thought_id, keywords as keywords, categories as categories, source_ids as source_ids, created_at as created_at, content as content, content_format as content_format, rank as rank, snippet as snippet from search where true order by rank desc, created_at desc".to_string(),
+            definition.expand("true")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use agrum::{
+    core::{
+        HydrationError, Projection, Provider, SourceAliases, SqlDefinition, SqlEntity, Structure,
+        Structured, WhereCondition,
+    },
+    params,
+};
+use anyhow::anyhow;
+use tokio_postgres::{Client, Row, Transaction};
+use uuid::Uuid;
+
+use crate::StdResult;
+
+/// Registered source cited by thoughts.
+#[derive(Debug)]
+pub struct SourceEntity {
+    pub source_id: Uuid,
+    pub name: String,
+    pub authors: Vec<String>,
+    pub description: String,
+    pub author_ids: Vec<Uuid>,
+}
+
+impl Structured for SourceEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[
+            ("source_id", "text"),
+            ("name", "text"),
+            ("authors", "text[]"),
+            ("description", "text"),
+            ("author_ids", "uuid[]"),
+        ])
+    }
+}
+
+impl SqlEntity for SourceEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            source_id: row.get("source_id"),
+            name: row.get("name"),
+            authors: row.get("authors"),
+            description: row.get("description"),
+            author_ids: row.get("author_ids"),
+        })
+    }
+}
+
+/// Registered sources with their authors, sorted by name.
+#[derive(Debug, Default)]
+pub struct SourceSqlDefinition {
+    projection: Projection<SourceEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for SourceSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "select {projection} from (\
+            select source.*, array(\
+            select link.author_id from thought.source_author as link \
+            where link.source_id = source.source_id order by link.position\
+            ) as author_ids \
+            from thought.source as source\
+            ) as source \
+            where {condition} order by name, source_id"
+        )
+    }
+}
+
+/// Replace the references to the source `$2` by references to the source `$1` in a
+/// `source_ids` column, only the first reference to a source is kept.
+const REPLACE_SOURCE_IDS: &str = "source_ids = array(\
+    select reference.source_id \
+    from unnest(array_replace(source_ids, $2, $1)) with ordinality as reference(source_id, position) \
+    group by reference.source_id order by min(reference.position)\
+    )";
+
+pub struct SourceRepository<'client> {
+    client: &'client Client,
+}
+
+impl<'client> SourceRepository<'client> {
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    fn provider(&self) -> Provider<'client, SourceEntity> {
+        Provider::new(self.client, Box::new(SourceSqlDefinition::default()))
+    }
+
+    pub async fn get_source(&self, source_id: &Uuid) -> StdResult<Option<SourceEntity>> {
+        let condition = WhereCondition::new("source_id = $?", params![source_id]);
+        let entity = self
+            .provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .pop();
+
+        Ok(entity)
+    }
+
+    pub async fn get_sources(&self) -> StdResult<Vec<SourceEntity>> {
+        self.provider()
+            .fetch(WhereCondition::default())
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Fetch the sources linked to the given author.
+    pub async fn get_sources_by_author(&self, author_id: &Uuid) -> StdResult<Vec<SourceEntity>> {
+        let condition = WhereCondition::new("$? = any(author_ids)", params![author_id]);
+
+        self.provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Fetch the source holding the given duplicate key.
+    pub async fn get_source_by_key(&self, duplicate_key: &str) -> StdResult<Option<SourceEntity>> {
+        let condition = WhereCondition::new("duplicate_key = $?", params![duplicate_key]);
+        let entity = self
+            .provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .pop();
+
+        Ok(entity)
+    }
+
+    /// Register a new source linked to its authors under the given duplicate key. If the key is
+    /// already taken, nothing is created and None is returned.
+    pub async fn insert_source(
+        &self,
+        source: &SourceEntity,
+        duplicate_key: &str,
+    ) -> StdResult<Option<SourceEntity>> {
+        let sql = format!(
+            "with source as (\
+            insert into thought.source (source_id, name, authors, description, duplicate_key) \
+            values ($1, $2, $3, $4, $6) \
+            on conflict (duplicate_key) do nothing \
+            returning *\
+            ), \
+            link as (\
+            insert into thought.source_author (source_id, author_id, position) \
+            select source.source_id, link.author_id, link.position \
+            from source \
+            cross join unnest($5::uuid[]) with ordinality as link(author_id, position)\
+            ) \
+            select {} from (select source.*, $5::uuid[] as author_ids from source) as source",
+            Projection::<SourceEntity>::default().expand(&SourceAliases::default())
+        );
+        let row = self
+            .client
+            .query_opt(
+                &sql,
+                &[
+                    &source.source_id,
+                    &source.name,
+                    &source.authors,
+                    &source.description,
+                    &source.author_ids,
+                    &duplicate_key,
+                ],
+            )
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!("Could not insert source '{}'.", source.source_id))
+            })?;
+
+        row.map(|row| SourceEntity::hydrate(row).map_err(|e| anyhow!(e)))
+            .transpose()
+    }
+
+    /// Merge the absorbed source into the kept one within the given transaction: the thoughts
+    /// and their revisions cite the kept source instead and the absorbed source is removed. The
+    /// revisions are updated last so the ones recorded by the thought updates are fixed too.
+    /// The kept source takes over the duplicate key of the absorbed one if it holds none. The
+    /// identifiers of the live thoughts whose sources changed are returned.
+    pub async fn merge_sources(
+        transaction: &Transaction<'_>,
+        kept_id: &Uuid,
+        absorbed_id: &Uuid,
+    ) -> StdResult<Vec<Uuid>> {
+        let context = || format!("Could not merge source '{absorbed_id}' into '{kept_id}'.");
+        let rows = transaction
+            .query(
+                &format!(
+                    "with updated as (\
+                    update thought.thought set {REPLACE_SOURCE_IDS} \
+                    where $2 = any(source_ids) \
+                    returning thought_id, deleted_at\
+                    ) \
+                    select thought_id from updated where deleted_at is null"
+                ),
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+        transaction
+            .execute(
+                &format!(
+                    "update thought.thought_revision set {REPLACE_SOURCE_IDS} \
+                    where $2 = any(source_ids)"
+                ),
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+        transaction
+            .execute(
+                "with absorbed as (\
+                delete from thought.source where source_id = $2 returning duplicate_key\
+                ) \
+                update thought.source as source set duplicate_key = absorbed.duplicate_key \
+                from absorbed \
+                where source.source_id = $1 and source.duplicate_key is null",
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+
+        Ok(rows.iter().map(|row| row.get("thought_id")).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_sql_definition() {
+        let definition = SourceSqlDefinition::default();
+
+        assert_eq!(
+            "select source_id as source_id, name as name, authors as authors, description as description, author_ids as author_ids from (select source.*, array(select link.author_id from thought.source_author as link where link.source_id = source.source_id order by link.position) as author_ids from thought.source as source) as source where true order by name, source_id".to_string(),
+            definition.expand("true")
+        );
+    }
+}
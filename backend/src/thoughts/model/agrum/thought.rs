@@ -7,10 +7,27 @@ use agrum::{
 };
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
-use tokio_postgres::Row;
+use serde_json::Value;
+use tokio_postgres::{types::ToSql, Client, Row, Transaction};
 use uuid::Uuid;
 
-use crate::StdResult;
+use crate::{
+    thoughts::model::{ThoughtStoreError, ThreadMerge},
+    StdResult,
+};
+
+use super::{
+    get_field, get_json, get_timestamp, hydrate_rows, Hydrated, ThoughtSearchEntity,
+    ThoughtSearchSqlDefinition,
+};
+
+/// Serialize the transactions changing the shape of the threads, so two of them cannot create a
+/// cycle together.
+const LOCK_THREADS: &str = "select pg_advisory_xact_lock(hashtext('thought.move_thought'))";
+
+/// Append the items of the absorbed root array missing from the kept root one, in order.
+const MERGE_ARRAY: &str = "array(select item from unnest(kept.{column} || absorbed.{column}) \
+    with ordinality as merged(item, position) group by item order by min(position))";
 
 /// Entity read/written from/to database.
 #[derive(Debug)]
@@ -19,9 +36,11 @@ pub struct ThoughtEntity {
     pub parent_thought_id: Option<Uuid>,
     pub keywords: Vec<String>,
     pub categories: Vec<String>,
-    pub sources: Vec<String>,
+    pub source_ids: Vec<Uuid>,
     pub created_at: DateTime<Utc>,
-    pub content: String,
+    /// Text of the thought as a JSON string, it is checked when converted to the model.
+    pub content: Value,
+    pub content_format: String,
 }
 
 impl Structured for ThoughtEntity {
@@ -30,10 +49,11 @@ impl Structured for ThoughtEntity {
             ("thought_id", "text"),
             ("parent_thought_id", "text"),
             ("keywords", "text[]"),
-            ("categories", "text[]"),
-            ("sources", "jsonb[]"),
+            ("categories", "ltree[]"),
+            ("source_ids", "uuid[]"),
             ("created_at", "timestamp"),
             ("content", "jsonb"),
+            ("content_format", "text"),
         ])
     }
 }
@@ -43,21 +63,17 @@ impl SqlEntity for ThoughtEntity {
     where
         Self: Sized,
     {
-        let created_at = DateTime::parse_from_rfc2822(&row.get::<_, String>("created_at"))
-            .map_err(|e| {
-                HydrationError::InvalidData(format!(
-                    "Could not parse DateTime data for field 'created_at'. Error = '{e:?}'."
-                ))
-            })?;
+        let created_at = get_timestamp(&row, "created_at")?;
 
         Ok(Self {
-            thought_id: row.get("thought_id"),
-            parent_thought_id: row.get("parent_thought_id"),
-            keywords: row.get("keywords"),
-            categories: row.get("categories"),
-            sources: row.get("sources"),
-            created_at: created_at.into(),
-            content: row.get("content"),
+            thought_id: get_field(&row, "thought_id")?,
+            parent_thought_id: get_field(&row, "parent_thought_id")?,
+            keywords: get_field(&row, "keywords")?,
+            categories: get_field(&row, "categories")?,
+            source_ids: get_field(&row, "source_ids")?,
+            created_at,
+            content: get_json(&row, "content")?,
+            content_format: get_field(&row, "content_format")?,
         })
     }
 }
@@ -72,23 +88,111 @@ impl SqlDefinition for ThoughtEntitySqlDefinition {
     fn expand(&self, condition: &str) -> String {
         let projection = self.projection.expand(&self.source_aliases);
 
-        format!("select {projection} from thought.thought where {condition}")
+        format!(
+            "select {projection} from thought.thought where deleted_at is null and ({condition})"
+        )
+    }
+}
+
+/// Walk the `parent_thought_id` chain up from the live thought matching the condition to the
+/// root `Thread`. Rows are sorted from the root down to the starting thought. The walk stops as
+/// soon as a thought is met twice so cycles cannot make the query loop forever, the rows closing
+/// a cycle are discarded.
+#[derive(Debug, Default)]
+pub struct ThoughtThreadSqlDefinition {
+    projection: Projection<ThoughtEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for ThoughtThreadSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "with recursive thread as (\
+            select thought.*, 0 as depth, array[thought.thought_id] as path, false as is_cycle \
+            from thought.thought as thought where thought.deleted_at is null and ({condition}) \
+            union all \
+            select parent.*, thread.depth + 1, thread.path || parent.thought_id, parent.thought_id = any(thread.path) \
+            from thought.thought as parent join thread on parent.thought_id = thread.parent_thought_id \
+            where not thread.is_cycle\
+            ) \
+            select {projection} from thread where not is_cycle order by depth desc"
+        )
+    }
+}
+
+/// Walk the `parent_thought_id` links down from the live thought matching the condition. The
+/// walk goes at most `max_depth` levels below the starting thought, or to the leaves when no
+/// depth is given. A thought already met on the way down is not walked again so cycles cannot
+/// make the query loop forever. Rows are sorted by depth, the starting thought first.
+#[derive(Debug, Default)]
+pub struct ThoughtSubtreeSqlDefinition {
+    projection: Projection<ThoughtEntity>,
+    source_aliases: SourceAliases,
+    max_depth: Option<u32>,
+}
+
+impl ThoughtSubtreeSqlDefinition {
+    pub fn new(max_depth: Option<u32>) -> Self {
+        Self {
+            max_depth,
+            ..Default::default()
+        }
+    }
+}
+
+impl SqlDefinition for ThoughtSubtreeSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+        // the depth is a number, it is written in the query so the parameters are only the ones
+        // of the condition
+        let depth_limit = self
+            .max_depth
+            .map(|max_depth| format!(" and subtree.depth < {max_depth}"))
+            .unwrap_or_default();
+
+        format!(
+            "with recursive subtree as (\
+            select thought.*, 0 as depth, array[thought.thought_id] as path \
+            from thought.thought as thought where thought.deleted_at is null and ({condition}) \
+            union all \
+            select child.*, subtree.depth + 1, subtree.path || child.thought_id \
+            from thought.thought as child join subtree on child.parent_thought_id = subtree.thought_id \
+            where child.deleted_at is null and child.thought_id <> all(subtree.path)\
+            {depth_limit}\
+            ) \
+            select {projection} from subtree order by depth, created_at, thought_id"
+        )
     }
 }
 
 pub struct ThoughtEntityRepository<'client> {
-    provider: Provider<'client, ThoughtEntity>,
+    client: &'client Client,
 }
 
 impl<'client> ThoughtEntityRepository<'client> {
-    pub fn new(provider: Provider<'client, ThoughtEntity>) -> Self {
-        Self { provider }
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    /// Create a provider for the given SQL definition.
+    fn provider<D>(&self, definition: D) -> Provider<'client, ThoughtEntity>
+    where
+        D: SqlDefinition + Send + Sync + 'static,
+    {
+        Provider::new(self.client, Box::new(definition))
+    }
+
+    /// Projection used in the `RETURNING` clause of write queries.
+    fn returning_projection(&self) -> String {
+        Projection::<ThoughtEntity>::default().expand(&SourceAliases::default())
     }
 
     pub async fn get_thought(&self, thought_id: &Uuid) -> StdResult<Option<ThoughtEntity>> {
         let condition = WhereCondition::new("thought_id = $?", params![thought_id]);
         let entity = self
-            .provider
+            .provider(ThoughtEntitySqlDefinition::default())
             .fetch(condition)
             .await
             .map_err(|e| anyhow!(e))?
@@ -96,6 +200,419 @@ impl<'client> ThoughtEntityRepository<'client> {
 
         Ok(entity)
     }
+
+    /// Fetch the live thoughts matching the condition, which uses numbered parameters. Each row
+    /// is hydrated on its own so a corrupt row does not fail the whole listing.
+    async fn fetch_rows(
+        &self,
+        condition: &str,
+        parameters: &[&(dyn ToSql + Sync)],
+    ) -> StdResult<Vec<Hydrated<ThoughtEntity>>> {
+        let sql = ThoughtEntitySqlDefinition::default().expand(condition);
+        let rows = self
+            .client
+            .query(&sql, parameters)
+            .await
+            .map_err(|e| anyhow!(e).context("Could not fetch the thoughts."))?;
+
+        Ok(hydrate_rows(rows))
+    }
+
+    /// Fetch the thoughts filed under the given category path or one of its descendants.
+    pub async fn get_thoughts_by_category(
+        &self,
+        path: &str,
+    ) -> StdResult<Vec<Hydrated<ThoughtEntity>>> {
+        self.fetch_rows("categories <@ $1::text::ltree", &[&path])
+            .await
+    }
+
+    /// Fetch the thoughts having a category matching the given `lquery` pattern.
+    pub async fn get_thoughts_by_category_pattern(
+        &self,
+        pattern: &str,
+    ) -> StdResult<Vec<Hydrated<ThoughtEntity>>> {
+        self.fetch_rows("categories ~ $1::text::lquery", &[&pattern])
+            .await
+    }
+
+    /// Fetch the thoughts citing the given source.
+    pub async fn get_thoughts_by_source(
+        &self,
+        source_id: &Uuid,
+    ) -> StdResult<Vec<Hydrated<ThoughtEntity>>> {
+        self.fetch_rows("$1 = any(source_ids)", &[source_id]).await
+    }
+
+    /// Fetch the thoughts citing one of the sources of the given author.
+    pub async fn get_thoughts_by_author(
+        &self,
+        author_id: &Uuid,
+    ) -> StdResult<Vec<Hydrated<ThoughtEntity>>> {
+        self.fetch_rows(
+            "source_ids && array(select source_id from thought.source_author where author_id = $1)",
+            &[author_id],
+        )
+        .await
+    }
+
+    /// Fetch the thread from its root down to the given thought. If the root cannot be reached
+    /// because the chain of parents is cyclic, an error is returned.
+    pub async fn get_thread(&self, thought_id: &Uuid) -> StdResult<Option<Vec<ThoughtEntity>>> {
+        let condition = WhereCondition::new("thought_id = $?", params![thought_id]);
+        let thread = self
+            .provider(ThoughtThreadSqlDefinition::default())
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))?;
+
+        match thread.first() {
+            None => Ok(None),
+            Some(root) if root.parent_thought_id.is_some() => {
+                Err(ThoughtStoreError::Cycle(format!(
+                    "Cycle detected while walking up the thread of thought '{thought_id}', \
+                    thought '{}' is reached twice.",
+                    root.thought_id
+                ))
+                .into())
+            }
+            Some(_) => Ok(Some(thread)),
+        }
+    }
+
+    /// Fetch the given thought and its descendants down to `max_depth` levels below it, the
+    /// given thought first. None is returned if the thought does not exist.
+    pub async fn get_subtree(
+        &self,
+        thought_id: &Uuid,
+        max_depth: Option<u32>,
+    ) -> StdResult<Option<Vec<ThoughtEntity>>> {
+        let condition = WhereCondition::new("thought.thought_id = $?", params![thought_id]);
+        let subtree = self
+            .provider(ThoughtSubtreeSqlDefinition::new(max_depth))
+            .fetch(condition)
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!(
+                    "Could not fetch the subtree of thought '{thought_id}'."
+                ))
+            })?;
+
+        Ok((!subtree.is_empty()).then_some(subtree))
+    }
+
+    /// Full text search using the given text search configuration, best matches first.
+    pub async fn search(
+        &self,
+        search_language: &str,
+        query: &str,
+        limit: i64,
+    ) -> StdResult<Vec<Hydrated<ThoughtSearchEntity>>> {
+        let sql = format!(
+            "{} limit $3",
+            ThoughtSearchSqlDefinition::default().expand("true")
+        );
+        let rows = self
+            .client
+            .query(&sql, &[&search_language, &query, &limit])
+            .await
+            .map_err(|e| anyhow!(e).context(format!("Could not search thoughts for '{query}'.")))?;
+
+        Ok(hydrate_rows(rows))
+    }
+
+    /// Insert a new thought in the database. The creation date is set by the database, the
+    /// search language is the text search configuration used to index the thought and the
+    /// search text is the text extracted from its content.
+    pub async fn insert_thought(
+        &self,
+        thought: &ThoughtEntity,
+        search_language: &str,
+        search_text: &str,
+    ) -> StdResult<ThoughtEntity> {
+        let sql = format!(
+            "insert into thought.thought (thought_id, parent_thought_id, keywords, categories, source_ids, content, search_language, content_format, search_text) \
+            values ($1, $2, $3, $4::text[]::ltree[], $5, $6::jsonb, $7::text::regconfig, $8, $9) \
+            returning {}",
+            self.returning_projection()
+        );
+        let row = self
+            .client
+            .query_one(
+                &sql,
+                &[
+                    &thought.thought_id,
+                    &thought.parent_thought_id,
+                    &thought.keywords,
+                    &thought.categories,
+                    &thought.source_ids,
+                    &thought.content,
+                    &search_language,
+                    &thought.content_format,
+                    &search_text,
+                ],
+            )
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!(
+                    "Could not insert thought '{}'.",
+                    thought.thought_id
+                ))
+            })?;
+
+        ThoughtEntity::hydrate(row).map_err(|e| anyhow!(e))
+    }
+
+    /// Update an existing thought. The creation date and the parent are left untouched, the
+    /// parent only has to match so a thought moved in the meantime is not overwritten. If the
+    /// thought does not exist, is deleted or has another parent, None is returned.
+    pub async fn update_thought(
+        &self,
+        thought: &ThoughtEntity,
+        search_language: &str,
+        search_text: &str,
+    ) -> StdResult<Option<ThoughtEntity>> {
+        let sql = format!(
+            "update thought.thought \
+            set keywords = $3, categories = $4::text[]::ltree[], \
+            source_ids = $5, content = $6::jsonb, \
+            search_language = $7::text::regconfig, content_format = $8, search_text = $9 \
+            where thought_id = $1 and deleted_at is null \
+            and parent_thought_id is not distinct from $2 \
+            returning {}",
+            self.returning_projection()
+        );
+        let row = self
+            .client
+            .query_opt(
+                &sql,
+                &[
+                    &thought.thought_id,
+                    &thought.parent_thought_id,
+                    &thought.keywords,
+                    &thought.categories,
+                    &thought.source_ids,
+                    &thought.content,
+                    &search_language,
+                    &thought.content_format,
+                    &search_text,
+                ],
+            )
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!(
+                    "Could not update thought '{}'.",
+                    thought.thought_id
+                ))
+            })?;
+
+        row.map(|r| ThoughtEntity::hydrate(r).map_err(|e| anyhow!(e)))
+            .transpose()
+    }
+
+    /// Move a live thought under a new parent within the given transaction, or make it a thread
+    /// root when no parent is given. Moves are serialized so two concurrent moves cannot create
+    /// a cycle together. An error is returned if the new parent does not exist or is the thought
+    /// itself or one of its descendants. The identifiers of the moved thought and its live
+    /// descendants are returned, the moved thought first. If the thought does not exist, None
+    /// is returned.
+    pub async fn move_thought(
+        transaction: &Transaction<'_>,
+        thought_id: &Uuid,
+        parent_thought_id: Option<&Uuid>,
+    ) -> StdResult<Option<Vec<Uuid>>> {
+        let context = || format!("Could not move thought '{thought_id}'.");
+        transaction
+            .execute(LOCK_THREADS, &[])
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+        let locked = transaction
+            .query_opt(
+                "select thought_id from thought.thought \
+                where thought_id = $1 and deleted_at is null for update",
+                &[thought_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+
+        if locked.is_none() {
+            return Ok(None);
+        }
+        if let Some(parent_thought_id) = parent_thought_id {
+            let ancestors: Vec<Uuid> = transaction
+                .query(
+                    "with recursive ancestor as (\
+                    select thought_id, parent_thought_id, array[thought_id] as path \
+                    from thought.thought where thought_id = $1 and deleted_at is null \
+                    union all \
+                    select parent.thought_id, parent.parent_thought_id, ancestor.path || parent.thought_id \
+                    from thought.thought as parent join ancestor on parent.thought_id = ancestor.parent_thought_id \
+                    where parent.thought_id <> all(ancestor.path)\
+                    ) \
+                    select thought_id from ancestor",
+                    &[parent_thought_id],
+                )
+                .await
+                .map_err(|e| anyhow!(e).context(context()))?
+                .iter()
+                .map(|row| row.get("thought_id"))
+                .collect();
+
+            if ancestors.is_empty() {
+                return Err(anyhow!(ThoughtStoreError::NotFound(format!(
+                    "Parent thought '{parent_thought_id}' of thought '{thought_id}' does not exist."
+                )))
+                .context(context()));
+            }
+            if ancestors.contains(thought_id) {
+                return Err(anyhow!(ThoughtStoreError::Conflict(format!(
+                    "Thought '{parent_thought_id}' is thought '{thought_id}' or one of its \
+                    descendants, moving under it would create a cycle."
+                )))
+                .context(context()));
+            }
+        }
+        transaction
+            .execute(
+                "update thought.thought set parent_thought_id = $2 where thought_id = $1",
+                &[thought_id, &parent_thought_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+        let subtree = transaction
+            .query(
+                &ThoughtSubtreeSqlDefinition::default().expand("thought.thought_id = $1"),
+                &[thought_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+
+        Ok(Some(
+            subtree.iter().map(|row| row.get("thought_id")).collect(),
+        ))
+    }
+
+    /// Fold the absorbed thread root into the kept one within the given transaction: the kept
+    /// root gets the keywords, categories and sources of the absorbed one, the children of the
+    /// absorbed root, trashed ones included, are moved under the kept root, the links and
+    /// attachments are redirected and the absorbed root is deleted, its revisions with it. If one
+    /// of the thoughts is not a live thread root, None is returned.
+    pub async fn combine_threads(
+        transaction: &Transaction<'_>,
+        kept_id: &Uuid,
+        absorbed_id: &Uuid,
+    ) -> StdResult<Option<ThreadMerge>> {
+        let context = || format!("Could not combine thread '{absorbed_id}' into '{kept_id}'.");
+        transaction
+            .execute(LOCK_THREADS, &[])
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+        let locked = transaction
+            .query(
+                "select thought_id from thought.thought \
+                where thought_id in ($1, $2) and parent_thought_id is null and deleted_at is null \
+                for update",
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+
+        if kept_id == absorbed_id || locked.len() != 2 {
+            return Ok(None);
+        }
+        let descendants: Vec<Uuid> = transaction
+            .query(
+                &ThoughtSubtreeSqlDefinition::default().expand("thought.thought_id = $1"),
+                &[absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?
+            .iter()
+            .skip(1)
+            .map(|row| row.get("thought_id"))
+            .collect();
+        let merged_columns = ["keywords", "categories", "source_ids"]
+            .iter()
+            .map(|column| format!("{column} = {}", MERGE_ARRAY.replace("{column}", column)))
+            .collect::<Vec<String>>()
+            .join(", ");
+        transaction
+            .execute(
+                &format!(
+                    "update thought.thought as kept set {merged_columns} \
+                    from thought.thought as absorbed \
+                    where kept.thought_id = $1 and absorbed.thought_id = $2"
+                ),
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+        transaction
+            .execute(
+                "update thought.thought set parent_thought_id = $1 where parent_thought_id = $2",
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+        let removed_link_ids: Vec<Uuid> = transaction
+            .query(
+                "delete from thought.thought_link as link \
+                where (link.from_thought_id = $2 and (link.to_thought_id = $1 or exists (\
+                select 1 from thought.thought_link as kept where kept.from_thought_id = $1 \
+                and kept.to_thought_id = link.to_thought_id and kept.kind = link.kind))) \
+                or (link.to_thought_id = $2 and (link.from_thought_id = $1 or exists (\
+                select 1 from thought.thought_link as kept where kept.to_thought_id = $1 \
+                and kept.from_thought_id = link.from_thought_id and kept.kind = link.kind))) \
+                returning link_id",
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?
+            .iter()
+            .map(|row| row.get("link_id"))
+            .collect();
+        let redirected_link_ids: Vec<Uuid> = transaction
+            .query(
+                "update thought.thought_link set \
+                from_thought_id = case when from_thought_id = $2 then $1 else from_thought_id end, \
+                to_thought_id = case when to_thought_id = $2 then $1 else to_thought_id end \
+                where from_thought_id = $2 or to_thought_id = $2 \
+                returning link_id",
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?
+            .iter()
+            .map(|row| row.get("link_id"))
+            .collect();
+        let moved_attachment_ids: Vec<Uuid> = transaction
+            .query(
+                "update thought.attachment set thought_id = $1 where thought_id = $2 \
+                returning attachment_id",
+                &[kept_id, absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?
+            .iter()
+            .map(|row| row.get("attachment_id"))
+            .collect();
+        // a soft deleted root could be restored as an empty thread, it is gone for good
+        transaction
+            .execute(
+                "delete from thought.thought where thought_id = $1",
+                &[absorbed_id],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context(context()))?;
+
+        Ok(Some(ThreadMerge {
+            updated_thought_ids: [*kept_id].into_iter().chain(descendants).collect(),
+            deleted_thought_id: Some(*absorbed_id),
+            redirected_link_ids,
+            removed_link_ids,
+            moved_attachment_ids,
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -107,10 +624,40 @@ mod tests {
         let definition = ThoughtEntitySqlDefinition::default();
 
         assert_eq!(
-            "select thought_id as thought_id, parent_thought_id as parent_thought_id, keywords as keywords, categories as categories, sources as sources, created_at as created_at, content as content from thought.thought where true".to_string(),
+            "select thought_id as thought_id, parent_thought_id as parent_thought_id, keywords as keywords, categories as categories, source_ids as source_ids, created_at as created_at, content as content, content_format as content_format from thought.thought where deleted_at is null and (true)".to_string(),
             definition.expand("true")
         );
 
         Ok(())
     }
+
+    #[test]
+    fn thought_thread_sql_definition() -> StdResult<()> {
+        let definition = ThoughtThreadSqlDefinition::default();
+
+        assert_eq!(
+            "with recursive thread as (select thought.*, 0 as depth, array[thought.thought_id] as path, false as is_cycle from thought.thought as thought where thought.deleted_at is null and (thought_id = $1) union all select parent.*, thread.depth + 1, thread.path || parent.thought_id, parent.thought_id = any(thread.path) from thought.thought as parent join thread on parent.thought_id = thread.parent_thought_id where not thread.is_cycle) select thought_id as thought_id, parent_thought_id as parent_thought_id, keywords as keywords, categories as categories, source_ids as source_ids, created_at as created_at, content as content, content_format as content_format from thread where not is_cycle order by depth desc".to_string(),
+            definition.expand("thought_id = $1")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn thought_subtree_sql_definition() -> StdResult<()> {
+        let definition = ThoughtSubtreeSqlDefinition::default();
+
+        assert_eq!(
+            "with recursive subtree as (select thought.*, 0 as depth, array[thought.thought_id] as path from thought.thought as thought where thought.deleted_at is null and (thought.thought_id = $1) union all select child.*, subtree.depth + 1, subtree.path || child.thought_id from thought.thought as child join subtree on child.parent_thought_id = subtree.thought_id where child.deleted_at is null and child.thought_id <> all(subtree.path)) select thought_id as thought_id, parent_thought_id as parent_thought_id, keywords as keywords, categories as categories, source_ids as source_ids, created_at as created_at, content as content, content_format as content_format from subtree order by depth, created_at, thought_id".to_string(),
+            definition.expand("thought.thought_id = $1")
+        );
+
+        let expanded = ThoughtSubtreeSqlDefinition::new(Some(3)).expand("thought.thought_id = $1");
+        assert!(expanded.contains(
+            "where child.deleted_at is null and child.thought_id <> all(subtree.path) and subtree.depth < 3) select"
+        ));
+        assert!(!expanded.contains("$2"));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,200 @@
+use agrum::{
+    core::{
+        HydrationError, Projection, Provider, SourceAliases, SqlDefinition, SqlEntity, Structure,
+        Structured, WhereCondition,
+    },
+    params,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use tokio_postgres::{Client, Row};
+use uuid::Uuid;
+
+use crate::StdResult;
+
+use super::{get_timestamp, hydrate_rows, Hydrated, ThoughtEntity};
+
+/// Soft deleted thought with its deletion date.
+#[derive(Debug)]
+pub struct TrashedThoughtEntity {
+    pub thought: ThoughtEntity,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl Structured for TrashedThoughtEntity {
+    fn get_structure() -> Structure {
+        Structure::new(&[
+            ("thought_id", "text"),
+            ("parent_thought_id", "text"),
+            ("keywords", "text[]"),
+            ("categories", "ltree[]"),
+            ("source_ids", "uuid[]"),
+            ("created_at", "timestamp"),
+            ("content", "jsonb"),
+            ("content_format", "text"),
+            ("deleted_at", "timestamp"),
+        ])
+    }
+}
+
+impl SqlEntity for TrashedThoughtEntity {
+    fn hydrate(row: Row) -> Result<Self, HydrationError>
+    where
+        Self: Sized,
+    {
+        let deleted_at = get_timestamp(&row, "deleted_at")?;
+
+        Ok(Self {
+            thought: ThoughtEntity::hydrate(row)?,
+            deleted_at,
+        })
+    }
+}
+
+/// Soft deleted thoughts, most recently deleted first.
+#[derive(Debug, Default)]
+pub struct TrashSqlDefinition {
+    projection: Projection<TrashedThoughtEntity>,
+    source_aliases: SourceAliases,
+}
+
+impl SqlDefinition for TrashSqlDefinition {
+    fn expand(&self, condition: &str) -> String {
+        let projection = self.projection.expand(&self.source_aliases);
+
+        format!(
+            "select {projection} from thought.thought \
+            where deleted_at is not null and ({condition}) \
+            order by deleted_at desc, thought_id"
+        )
+    }
+}
+
+/// Soft deletion, restoration and purge of thoughts. Thoughts deleted together share the same
+/// deletion date, this is how a restoration finds the subtree to bring back.
+pub struct TrashRepository<'client> {
+    client: &'client Client,
+}
+
+impl<'client> TrashRepository<'client> {
+    pub fn new(client: &'client Client) -> Self {
+        Self { client }
+    }
+
+    fn provider(&self) -> Provider<'client, TrashedThoughtEntity> {
+        Provider::new(self.client, Box::new(TrashSqlDefinition::default()))
+    }
+
+    pub async fn get_trashed_thought(
+        &self,
+        thought_id: &Uuid,
+    ) -> StdResult<Option<TrashedThoughtEntity>> {
+        let condition = WhereCondition::new("thought_id = $?", params![thought_id]);
+        let entity = self
+            .provider()
+            .fetch(condition)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .pop();
+
+        Ok(entity)
+    }
+
+    /// List the trash, each row is hydrated on its own so a corrupt row does not fail the
+    /// whole listing.
+    pub async fn list_trash(&self) -> StdResult<Vec<Hydrated<TrashedThoughtEntity>>> {
+        let sql = TrashSqlDefinition::default().expand("true");
+        let rows = self
+            .client
+            .query(&sql, &[])
+            .await
+            .map_err(|e| anyhow!(e).context("Could not list the trash."))?;
+
+        Ok(hydrate_rows(rows))
+    }
+
+    /// Soft delete a live thought. When `recursive` is false, the thought is only deleted if it
+    /// has no live children. Otherwise, its live descendants are deleted with it. The
+    /// identifiers of the deleted thoughts are returned.
+    pub async fn delete_thought(&self, thought_id: &Uuid, recursive: bool) -> StdResult<Vec<Uuid>> {
+        let sql = if recursive {
+            "with recursive subtree as (\
+            select thought_id from thought.thought where thought_id = $1 and deleted_at is null \
+            union all \
+            select child.thought_id from thought.thought as child \
+            join subtree on child.parent_thought_id = subtree.thought_id \
+            where child.deleted_at is null\
+            ) \
+            update thought.thought set deleted_at = now() \
+            where thought_id in (select thought_id from subtree) \
+            returning thought_id"
+        } else {
+            "update thought.thought set deleted_at = now() \
+            where thought_id = $1 and deleted_at is null \
+            and not exists (\
+            select 1 from thought.thought as child \
+            where child.parent_thought_id = $1 and child.deleted_at is null\
+            ) \
+            returning thought_id"
+        };
+        let rows =
+            self.client.query(sql, &[thought_id]).await.map_err(|e| {
+                anyhow!(e).context(format!("Could not delete thought '{thought_id}'."))
+            })?;
+
+        Ok(rows.iter().map(|row| row.get("thought_id")).collect())
+    }
+
+    /// Restore a soft deleted thought with the descendants deleted at the same time. The
+    /// identifiers of the restored thoughts are returned.
+    pub async fn restore_thought(&self, thought_id: &Uuid) -> StdResult<Vec<Uuid>> {
+        let sql = "with recursive subtree as (\
+            select thought_id, deleted_at from thought.thought \
+            where thought_id = $1 and deleted_at is not null \
+            union all \
+            select child.thought_id, child.deleted_at from thought.thought as child \
+            join subtree on child.parent_thought_id = subtree.thought_id \
+            where child.deleted_at = subtree.deleted_at\
+            ) \
+            update thought.thought set deleted_at = null \
+            where thought_id in (select thought_id from subtree) \
+            returning thought_id";
+        let rows = self.client.query(sql, &[thought_id]).await.map_err(|e| {
+            anyhow!(e).context(format!("Could not restore thought '{thought_id}'."))
+        })?;
+
+        Ok(rows.iter().map(|row| row.get("thought_id")).collect())
+    }
+
+    /// Permanently remove the thoughts deleted before the given date. Descendants are never
+    /// deleted after their ancestors, so whole subtrees are removed at once. The identifiers of
+    /// the purged thoughts are returned.
+    pub async fn purge(&self, before: &DateTime<Utc>) -> StdResult<Vec<Uuid>> {
+        let before = before.naive_utc();
+        let rows = self
+            .client
+            .query(
+                "delete from thought.thought where deleted_at < $1::timestamp returning thought_id",
+                &[&before],
+            )
+            .await
+            .map_err(|e| anyhow!(e).context("Could not purge the trash."))?;
+
+        Ok(rows.iter().map(|row| row.get("thought_id")).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_sql_definition() {
+        let definition = TrashSqlDefinition::default();
+
+        assert_eq!(
+            "select thought_id as thought_id, parent_thought_id as parent_thought_id, keywords as keywords, categories as categories, source_ids as source_ids, created_at as created_at, content as content, content_format as content_format, deleted_at as deleted_at from thought.thought where deleted_at is not null and (true) order by deleted_at desc, thought_id".to_string(),
+            definition.expand("true")
+        );
+    }
+}
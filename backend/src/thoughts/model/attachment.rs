@@ -0,0 +1,119 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::agrum::AttachmentEntity;
+
+/// File attached to a thought. Its content is stored once per hash in the blob store, several
+/// attachments may share the same content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub attachment_id: Uuid,
+    pub thought_id: Uuid,
+    pub file_name: String,
+    pub mime_type: String,
+    /// Size of the content in bytes.
+    pub size: i64,
+    /// SHA-256 hash of the content, hexadecimal encoded.
+    pub hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Keep the last component of an uploaded file name, without control characters. None is
+/// returned if nothing is left.
+pub fn sanitize_file_name(file_name: &str) -> Option<String> {
+    let file_name: String = Path::new(&file_name.replace('\\', "/"))
+        .file_name()?
+        .to_string_lossy()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    let file_name = file_name.trim();
+
+    (!file_name.is_empty()).then(|| file_name.to_string())
+}
+
+/// Return true if the given text looks like a `type/subtype` MIME type, parameters allowed.
+pub fn is_valid_mime_type(mime_type: &str) -> bool {
+    let essence = mime_type.split(';').next().unwrap_or_default();
+    let is_token = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+    };
+
+    if mime_type.chars().any(|c| c.is_control()) {
+        return false;
+    }
+
+    match essence.trim().split_once('/') {
+        Some((kind, subtype)) => is_token(kind) && is_token(subtype),
+        None => false,
+    }
+}
+
+impl From<AttachmentEntity> for Attachment {
+    fn from(value: AttachmentEntity) -> Self {
+        Self {
+            attachment_id: value.attachment_id,
+            thought_id: value.thought_id,
+            file_name: value.file_name,
+            mime_type: value.mime_type,
+            size: value.size,
+            hash: value.hash,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<Attachment> for AttachmentEntity {
+    fn from(value: Attachment) -> Self {
+        Self {
+            attachment_id: value.attachment_id,
+            thought_id: value.thought_id,
+            file_name: value.file_name,
+            mime_type: value.mime_type,
+            size: value.size,
+            hash: value.hash,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_are_sanitized() {
+        assert_eq!(
+            Some("notes.pdf".to_string()),
+            sanitize_file_name("../../etc/notes.pdf")
+        );
+        assert_eq!(
+            Some("memo.ogg".to_string()),
+            sanitize_file_name("C:\\Users\\me\\memo.ogg")
+        );
+        assert_eq!(
+            Some("photo.jpg".to_string()),
+            sanitize_file_name("pho\u{0}to.jpg")
+        );
+        assert_eq!(None, sanitize_file_name(".."));
+        assert_eq!(None, sanitize_file_name("  "));
+    }
+
+    #[test]
+    fn mime_types_are_validated() {
+        assert!(is_valid_mime_type("image/png"));
+        assert!(is_valid_mime_type("audio/ogg; codecs=opus"));
+        assert!(is_valid_mime_type(
+            "application/vnd.oasis.opendocument.text"
+        ));
+        assert!(!is_valid_mime_type("image"));
+        assert!(!is_valid_mime_type("image/"));
+        assert!(!is_valid_mime_type("text/html\r\nx-header: 1"));
+        assert!(!is_valid_mime_type("text/plain; charset=utf-8\r\n"));
+    }
+}
@@ -0,0 +1,68 @@
+use uuid::Uuid;
+
+use super::{agrum::AuthorEntity, normalize_name};
+
+/// Author indexed across the sources. An author is known by several names, they are matched
+/// ignoring case, punctuation and spacing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Author {
+    pub author_id: Uuid,
+    pub name: String,
+    /// Names the author is known by, its name included, sorted.
+    pub aliases: Vec<String>,
+}
+
+impl Author {
+    /// Key an alias is looked up with, empty if the name is only made of ASCII punctuation and
+    /// spacing.
+    pub fn alias_key(alias: &str) -> String {
+        normalize_name(alias)
+    }
+
+    /// Return true if the author is known by the given name.
+    pub fn is_known_as(&self, alias: &str) -> bool {
+        let key = Self::alias_key(alias);
+
+        self.aliases.iter().any(|a| Self::alias_key(a) == key)
+    }
+}
+
+impl From<AuthorEntity> for Author {
+    fn from(value: AuthorEntity) -> Self {
+        Self {
+            author_id: value.author_id,
+            name: value.name,
+            aliases: value.aliases,
+        }
+    }
+}
+
+impl From<Author> for AuthorEntity {
+    fn from(value: Author) -> Self {
+        Self {
+            author_id: value.author_id,
+            name: value.name,
+            aliases: value.aliases,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_are_matched_loosely() {
+        let author = Author {
+            author_id: Uuid::new_v4(),
+            name: "Marcus Aurelius".to_string(),
+            aliases: vec!["Marc Aurèle".to_string(), "Marcus Aurelius".to_string()],
+        };
+
+        assert!(author.is_known_as("marcus   aurelius"));
+        assert!(author.is_known_as("MARC-aurèle."));
+        assert!(!author.is_known_as("MARC AURÈLE"));
+        assert!(!author.is_known_as("Marcus"));
+        assert_eq!("", Author::alias_key(" - "));
+    }
+}
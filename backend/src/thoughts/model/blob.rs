@@ -0,0 +1,280 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::anyhow;
+use log::debug;
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs::{self, File},
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+};
+use uuid::Uuid;
+
+use crate::StdResult;
+
+/// Directory of the blob store where uploads are written before being named after their hash.
+const TEMPORARY_DIRECTORY: &str = "tmp";
+
+/// Size of the chunks read while storing a blob.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Content stored in the blob store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredBlob {
+    /// SHA-256 hash of the content, hexadecimal encoded.
+    pub hash: String,
+    /// Size of the content in bytes.
+    pub size: i64,
+}
+
+/// Content addressed files on the local filesystem. Each blob is named after the SHA-256 hash
+/// of its content and lives in a sub directory named after the first two characters of the
+/// hash, identical contents are stored once.
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Return true if the given text is a hexadecimal encoded SHA-256 hash.
+    pub fn is_valid_hash(hash: &str) -> bool {
+        hash.len() == 64
+            && hash
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    }
+
+    /// Location of the blob with the given hash, it may not exist.
+    pub fn get_path(&self, hash: &str) -> StdResult<PathBuf> {
+        if !Self::is_valid_hash(hash) {
+            return Err(anyhow!("Invalid blob hash '{hash}'."));
+        }
+
+        Ok(self.root.join(&hash[..2]).join(hash))
+    }
+
+    /// Store the content read from the given reader chunk by chunk. It is written to a
+    /// temporary file first, hashed on the way, then moved to its final location.
+    pub async fn store<R: AsyncRead + Unpin>(&self, reader: &mut R) -> StdResult<StoredBlob> {
+        let temporary_directory = self.root.join(TEMPORARY_DIRECTORY);
+        fs::create_dir_all(&temporary_directory)
+            .await
+            .map_err(|e| {
+                anyhow!(e).context(format!(
+                    "Could not create blob directory '{}'.",
+                    temporary_directory.display()
+                ))
+            })?;
+        let temporary_path = temporary_directory.join(Uuid::new_v4().to_string());
+        let stored = self.write(reader, &temporary_path).await;
+
+        if stored.is_err() {
+            // the error of the write is more relevant than this one
+            let _ = fs::remove_file(&temporary_path).await;
+        }
+
+        stored
+    }
+
+    /// Store the content of the given file by moving it to its final location, it is only
+    /// read to be hashed. If the file cannot be moved, because it is on another filesystem, its
+    /// content is copied and the file is left in place.
+    pub async fn store_file(&self, path: &Path) -> StdResult<StoredBlob> {
+        let mut file = File::open(path).await.map_err(|e| {
+            anyhow!(e).context(format!("Could not open file '{}'.", path.display()))
+        })?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let mut size: u64 = 0;
+
+        loop {
+            let read = file.read(&mut buffer).await?;
+
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            size += read as u64;
+        }
+        drop(file);
+
+        let hash = format!("{:x}", hasher.finalize());
+        let blob_path = self.get_path(&hash)?;
+
+        if let Some(directory) = blob_path.parent() {
+            fs::create_dir_all(directory).await?;
+        }
+        if let Err(e) = fs::rename(path, &blob_path).await {
+            debug!(
+                "Could not move '{}' to the blob store, copying it ({e}).",
+                path.display()
+            );
+            let mut file = File::open(path).await?;
+
+            return self.store(&mut file).await;
+        }
+
+        Ok(StoredBlob {
+            hash,
+            size: size.try_into()?,
+        })
+    }
+
+    async fn write<R: AsyncRead + Unpin>(
+        &self,
+        reader: &mut R,
+        temporary_path: &Path,
+    ) -> StdResult<StoredBlob> {
+        let mut file = File::create(temporary_path).await?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let mut size: u64 = 0;
+
+        loop {
+            let read = reader.read(&mut buffer).await?;
+
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            file.write_all(&buffer[..read]).await?;
+            size += read as u64;
+        }
+        file.sync_all().await?;
+        drop(file);
+
+        let hash = format!("{:x}", hasher.finalize());
+        let path = self.get_path(&hash)?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).await?;
+        }
+        // An existing blob with the same content is replaced too, this refreshes its
+        // modification date so the garbage collector leaves it alone until it is referenced.
+        fs::rename(temporary_path, &path).await.map_err(|e| {
+            anyhow!(e).context(format!("Could not move blob to '{}'.", path.display()))
+        })?;
+
+        Ok(StoredBlob {
+            hash,
+            size: size.try_into()?,
+        })
+    }
+
+    /// Remove the blobs whose hash is not referenced and the abandoned temporary files. Files
+    /// modified during the grace period are kept, they may belong to an upload in progress. The
+    /// hashes of the removed blobs are returned, sorted.
+    pub async fn collect_garbage(
+        &self,
+        referenced: &HashSet<String>,
+        grace_period: Duration,
+    ) -> StdResult<Vec<String>> {
+        let mut removed: Vec<String> = Vec::new();
+
+        if !fs::try_exists(&self.root).await? {
+            return Ok(removed);
+        }
+        let mut directories = fs::read_dir(&self.root).await?;
+
+        while let Some(directory) = directories.next_entry().await? {
+            let name = directory.file_name().to_string_lossy().to_string();
+
+            if !directory.file_type().await?.is_dir() {
+                continue;
+            }
+            let is_temporary = name == TEMPORARY_DIRECTORY;
+            let mut files = fs::read_dir(directory.path()).await?;
+
+            while let Some(file) = files.next_entry().await? {
+                let hash = file.file_name().to_string_lossy().to_string();
+                let is_orphan = Self::is_valid_hash(&hash)
+                    && hash.starts_with(&name)
+                    && !referenced.contains(&hash);
+
+                if !(is_temporary || is_orphan)
+                    || !Self::is_older(&file.path(), grace_period).await?
+                {
+                    continue;
+                }
+                fs::remove_file(file.path()).await.map_err(|e| {
+                    anyhow!(e).context(format!(
+                        "Could not remove blob '{}'.",
+                        file.path().display()
+                    ))
+                })?;
+
+                if is_orphan {
+                    removed.push(hash);
+                }
+            }
+        }
+        removed.sort();
+
+        Ok(removed)
+    }
+
+    /// Return true if the file was last modified before the given period.
+    async fn is_older(path: &Path, period: Duration) -> StdResult<bool> {
+        let modified = fs::metadata(path).await?.modified()?;
+
+        Ok(SystemTime::now()
+            .duration_since(modified)
+            .map(|age| age >= period)
+            .unwrap_or(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn blobs_are_deduplicated_and_collected() -> StdResult<()> {
+        let root = std::env::temp_dir().join(format!("omstasher-blobs-{}", Uuid::new_v4()));
+        let store = BlobStore::new(root.clone());
+
+        let first = store.store(&mut "Memento mori".as_bytes()).await?;
+        let second = store.store(&mut "Memento mori".as_bytes()).await?;
+        let other = store.store(&mut "Amor fati".as_bytes()).await?;
+
+        assert_eq!(first, second);
+        assert_eq!(12, first.size);
+        assert_eq!(
+            "Memento mori",
+            fs::read_to_string(store.get_path(&first.hash)?).await?
+        );
+        store.get_path("../../etc/passwd").unwrap_err();
+
+        let referenced: HashSet<String> = [first.hash.clone()].into();
+        assert!(store
+            .collect_garbage(&referenced, Duration::from_secs(3600))
+            .await?
+            .is_empty());
+        assert_eq!(
+            vec![other.hash.clone()],
+            store.collect_garbage(&referenced, Duration::ZERO).await?
+        );
+        assert!(fs::try_exists(store.get_path(&first.hash)?).await?);
+        assert!(!fs::try_exists(store.get_path(&other.hash)?).await?);
+
+        let upload = root.join("upload");
+        fs::write(&upload, "Amor fati").await?;
+        assert_eq!(other, store.store_file(&upload).await?);
+        assert!(!fs::try_exists(&upload).await?);
+        assert_eq!(
+            "Amor fati",
+            fs::read_to_string(store.get_path(&other.hash)?).await?
+        );
+
+        fs::remove_dir_all(root).await?;
+
+        Ok(())
+    }
+}
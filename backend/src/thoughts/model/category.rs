@@ -0,0 +1,177 @@
+use super::agrum::CategoryCountEntity;
+
+/// Number of thoughts filed under a category path or one of its sub categories.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryCount {
+    pub path: String,
+    pub thought_count: u64,
+}
+
+impl From<CategoryCountEntity> for CategoryCount {
+    fn from(value: CategoryCountEntity) -> Self {
+        Self {
+            path: value.path,
+            thought_count: value.thought_count.max(0) as u64,
+        }
+    }
+}
+
+/// Node of the category tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryNode {
+    /// Last label of the path.
+    pub name: String,
+    /// Full path of the category, labels are separated by dots.
+    pub path: String,
+    /// Number of thoughts filed under this category or one of its sub categories.
+    pub thought_count: u64,
+    pub children: Vec<CategoryNode>,
+}
+
+impl CategoryNode {
+    /// Build the category tree from the counts. Counts must be sorted by path so parents come
+    /// before their children.
+    pub fn build_tree(counts: Vec<CategoryCount>) -> Vec<CategoryNode> {
+        let mut roots: Vec<CategoryNode> = Vec::new();
+
+        for count in counts {
+            let labels: Vec<&str> = count.path.split('.').collect();
+            let mut siblings = &mut roots;
+
+            for (depth, label) in labels.iter().enumerate() {
+                let position = match siblings.iter().position(|node| node.name == *label) {
+                    Some(position) => position,
+                    None => {
+                        siblings.push(CategoryNode {
+                            name: label.to_string(),
+                            path: labels[..=depth].join("."),
+                            thought_count: 0,
+                            children: Vec::new(),
+                        });
+                        siblings.len() - 1
+                    }
+                };
+
+                if depth == labels.len() - 1 {
+                    siblings[position].thought_count = count.thought_count;
+                }
+                siblings = &mut siblings[position].children;
+            }
+        }
+
+        roots
+    }
+}
+
+/// Check the given path is a valid category path: dot separated labels made of ASCII
+/// alphanumeric characters and underscores, the only label characters `ltree` accepts before
+/// PostgreSQL 16.
+pub fn is_valid_category_path(path: &str) -> bool {
+    !path.is_empty()
+        && path.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Return true if the category path is the given ancestor or one of its descendants.
+pub fn is_category_descendant(path: &str, ancestor: &str) -> bool {
+    path == ancestor || (path.starts_with(ancestor) && path[ancestor.len()..].starts_with('.'))
+}
+
+/// Check the given pattern only uses characters of the PostgreSQL `lquery` syntax.
+pub fn is_valid_category_pattern(pattern: &str) -> bool {
+    !pattern.is_empty()
+        && pattern
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.*|!{},@%".contains(c))
+}
+
+/// Match a category path against a subset of the `lquery` syntax: dot separated items being
+/// either `*` (any number of labels) or alternatives separated by `|`, each alternative matching
+/// a label exactly or, when ending with `*`, as a prefix. Quantifiers, negations and modifiers
+/// are not supported and never match.
+pub fn matches_category_pattern(path: &str, pattern: &str) -> bool {
+    fn matches_label(label: &str, item: &str) -> bool {
+        item.split('|')
+            .any(|alternative| match alternative.strip_suffix('*') {
+                Some(prefix) => label.starts_with(prefix),
+                None => label == alternative,
+            })
+    }
+
+    fn matches(labels: &[&str], items: &[&str]) -> bool {
+        match items.split_first() {
+            None => labels.is_empty(),
+            Some((&"*", rest)) => (0..=labels.len()).any(|skip| matches(&labels[skip..], rest)),
+            Some((item, rest)) => match labels.split_first() {
+                Some((label, labels)) => matches_label(label, item) && matches(labels, rest),
+                None => false,
+            },
+        }
+    }
+
+    let labels: Vec<&str> = path.split('.').collect();
+    let items: Vec<&str> = pattern.split('.').collect();
+
+    matches(&labels, &items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(path: &str, thought_count: u64) -> CategoryCount {
+        CategoryCount {
+            path: path.to_string(),
+            thought_count,
+        }
+    }
+
+    #[test]
+    fn category_tree() {
+        let tree = CategoryNode::build_tree(vec![
+            count("philosophy", 3),
+            count("philosophy.ethics", 2),
+            count("philosophy.stoicism", 1),
+            count("science", 1),
+        ]);
+
+        assert_eq!(2, tree.len());
+        assert_eq!("philosophy", tree[0].path);
+        assert_eq!(3, tree[0].thought_count);
+        assert_eq!(2, tree[0].children.len());
+        assert_eq!("philosophy.stoicism", tree[0].children[1].path);
+        assert_eq!("stoicism", tree[0].children[1].name);
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn category_paths() {
+        assert!(is_valid_category_path("philosophy.stoic_school.late_stoa"));
+        assert!(!is_valid_category_path("philosophy.stoic_school.late-stoa"));
+        assert!(!is_valid_category_path(""));
+        assert!(!is_valid_category_path("philosophy..ethics"));
+        assert!(!is_valid_category_path("philosophy.*"));
+
+        assert!(is_category_descendant("philosophy.ethics", "philosophy"));
+        assert!(is_category_descendant("philosophy", "philosophy"));
+        assert!(!is_category_descendant("philosophy_of_mind", "philosophy"));
+    }
+
+    #[test]
+    fn category_patterns() {
+        assert!(matches_category_pattern(
+            "philosophy.ethics",
+            "philosophy.*"
+        ));
+        assert!(matches_category_pattern("philosophy", "philosophy.*"));
+        assert!(matches_category_pattern("a.philosophy.b", "*.philosophy.*"));
+        assert!(matches_category_pattern(
+            "philosophy.ethics",
+            "*.ethics|logic"
+        ));
+        assert!(matches_category_pattern("philosophy.ethics", "phil*.eth*"));
+        assert!(!matches_category_pattern("philosophy.ethics", "philosophy"));
+        assert!(!matches_category_pattern("science.ethics", "philosophy.*"));
+    }
+}
@@ -0,0 +1,156 @@
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+
+/// Format of the text of a thought.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentFormat {
+    #[default]
+    Plain,
+    Markdown,
+}
+
+impl ContentFormat {
+    /// Name of the format as stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Plain => "plain",
+            Self::Markdown => "markdown",
+        }
+    }
+
+    /// Parse the name of a format, None is returned if it is unknown.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Self::Plain),
+            "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    /// Render the text as HTML safe to embed in a page. Plain text is escaped, markdown is
+    /// rendered then sanitized: scripts, event handlers and unknown tags are stripped.
+    pub fn render_html(&self, text: &str) -> String {
+        match self {
+            Self::Plain => text
+                .split("\n\n")
+                .filter(|paragraph| !paragraph.trim().is_empty())
+                .map(|paragraph| {
+                    format!(
+                        "<p>{}</p>\n",
+                        escape_html(paragraph.trim()).replace('\n', "<br>\n")
+                    )
+                })
+                .collect(),
+            Self::Markdown => {
+                let parser =
+                    Parser::new_ext(text, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH);
+                let mut unsafe_html = String::new();
+                html::push_html(&mut unsafe_html, parser);
+
+                ammonia::clean(&unsafe_html)
+            }
+        }
+    }
+
+    /// Extract the words of the text without markup, this is what the search indexes.
+    pub fn plain_text(&self, text: &str) -> String {
+        match self {
+            Self::Plain => text.to_string(),
+            Self::Markdown => {
+                let mut plain_text = String::with_capacity(text.len());
+
+                for event in Parser::new_ext(text, Options::ENABLE_TABLES) {
+                    match event {
+                        Event::Text(text) | Event::Code(text) => plain_text.push_str(&text),
+                        Event::SoftBreak => plain_text.push(' '),
+                        Event::HardBreak
+                        | Event::End(
+                            Tag::Paragraph
+                            | Tag::Heading(..)
+                            | Tag::Item
+                            | Tag::CodeBlock(_)
+                            | Tag::TableCell,
+                        ) => plain_text.push('\n'),
+                        _ => (),
+                    }
+                }
+
+                plain_text.trim_end().to_string()
+            }
+        }
+    }
+}
+
+/// Markers enclosing the matching terms of a search snippet before it is rendered, they are
+/// control characters so they cannot be mistaken for the text or for markup.
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_STOP: char = '\u{3}';
+
+/// Render a search snippet as HTML. The text is escaped and the terms enclosed in the highlight
+/// markers are enclosed in `<mark>` tags, the snippet never carries the markup of the thought.
+pub fn render_snippet(snippet: &str) -> String {
+    escape_html(snippet)
+        .replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_STOP, "</mark>")
+}
+
+/// Escape the characters having a meaning in HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_is_escaped() {
+        assert_eq!(
+            "<p>1 &lt; 2 &amp; 3<br>\n4</p>\n<p>&lt;b&gt;</p>\n",
+            ContentFormat::Plain.render_html("1 < 2 & 3\n4\n\n<b>")
+        );
+        assert_eq!("**a**", ContentFormat::Plain.plain_text("**a**"));
+    }
+
+    #[test]
+    fn markdown_is_sanitized() {
+        let html = ContentFormat::Markdown.render_html(
+            "# Title\n\n*virtue* <script>alert(1)</script> [link](javascript:alert(1))",
+        );
+
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("<em>virtue</em>"));
+        assert!(!html.contains("script"));
+        assert!(!html.contains("javascript"));
+    }
+
+    #[test]
+    fn snippet_is_escaped() {
+        assert_eq!(
+            "&lt;img src=x onerror=alert(1)&gt; <mark>stoic</mark> &amp; wise",
+            render_snippet("<img src=x onerror=alert(1)> \u{2}stoic\u{3} & wise")
+        );
+    }
+
+    #[test]
+    fn markdown_plain_text() {
+        assert_eq!(
+            "Title\nSome virtue and code.\none\ntwo",
+            ContentFormat::Markdown
+                .plain_text("# Title\n\nSome *virtue*\nand `code`.\n\n- one\n- two\n")
+        );
+        assert_eq!(None, ContentFormat::parse("html"));
+    }
+}
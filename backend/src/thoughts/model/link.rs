@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::agrum::ThoughtLinkEntity;
+
+/// How a thought relates to the thought it links to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    SeeAlso,
+    Contradicts,
+    Supersedes,
+}
+
+impl LinkKind {
+    /// Name of the kind as stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SeeAlso => "see_also",
+            Self::Contradicts => "contradicts",
+            Self::Supersedes => "supersedes",
+        }
+    }
+
+    /// Parse the name of a kind, None is returned if it is unknown.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "see_also" => Some(Self::SeeAlso),
+            "contradicts" => Some(Self::Contradicts),
+            "supersedes" => Some(Self::Supersedes),
+            _ => None,
+        }
+    }
+}
+
+/// Typed link from a thought to another one, whatever their threads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThoughtLink {
+    pub link_id: Uuid,
+    pub from_thought_id: Uuid,
+    pub to_thought_id: Uuid,
+    pub kind: LinkKind,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ThoughtLinkEntity> for ThoughtLink {
+    fn from(value: ThoughtLinkEntity) -> Self {
+        Self {
+            link_id: value.link_id,
+            from_thought_id: value.from_thought_id,
+            to_thought_id: value.to_thought_id,
+            kind: value.kind,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<ThoughtLink> for ThoughtLinkEntity {
+    fn from(value: ThoughtLink) -> Self {
+        Self {
+            link_id: value.link_id,
+            from_thought_id: value.from_thought_id,
+            to_thought_id: value.to_thought_id,
+            kind: value.kind,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_kind_names() {
+        for kind in [
+            LinkKind::SeeAlso,
+            LinkKind::Contradicts,
+            LinkKind::Supersedes,
+        ] {
+            assert_eq!(Some(kind), LinkKind::parse(kind.as_str()));
+        }
+        assert_eq!(None, LinkKind::parse("SeeAlso"));
+    }
+}
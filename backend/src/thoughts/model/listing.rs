@@ -0,0 +1,185 @@
+use chrono::{DateTime, TimeZone, Utc};
+use uuid::Uuid;
+
+use super::{agrum::ThreadSummaryEntity, ThoughtConversionError, ThoughtEnvelope};
+
+/// Sort order of the thread listing, most recent first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThreadOrder {
+    /// Creation date of the thread root.
+    #[default]
+    CreatedAt,
+    /// Creation date of the most recent thought of the thread.
+    LastActivity,
+}
+
+impl ThreadOrder {
+    /// Parse the name of an order, `created_at` or `last_activity`. None is returned if it is
+    /// unknown.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "created_at" => Some(Self::CreatedAt),
+            "last_activity" => Some(Self::LastActivity),
+            _ => None,
+        }
+    }
+
+    /// Whether the listing can be resumed from a cursor. The last activity of a thread changes
+    /// as thoughts are added, a thread could then move past the cursor and be skipped, so only
+    /// the creation order is paged.
+    pub fn is_pageable(self) -> bool {
+        matches!(self, Self::CreatedAt)
+    }
+}
+
+/// Position in a listing: the sort key and the identifier of the last listed thought. The
+/// identifier breaks ties between thoughts sharing the same date so the listing can resume
+/// right after it, whatever is inserted in the meantime. Cursors are ordered by position then
+/// identifier, like the listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ListingCursor {
+    pub position: DateTime<Utc>,
+    pub thought_id: Uuid,
+}
+
+impl ListingCursor {
+    pub fn new(position: DateTime<Utc>, thought_id: Uuid) -> Self {
+        Self {
+            position,
+            thought_id,
+        }
+    }
+
+    /// Encode the cursor as an opaque string: the position in microseconds and the thought
+    /// identifier as hexadecimal bytes.
+    pub fn encode(&self) -> String {
+        self.position
+            .timestamp_micros()
+            .to_be_bytes()
+            .iter()
+            .chain(self.thought_id.as_bytes().iter())
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// Decode a cursor produced by `encode`, None is returned if the string is not a valid
+    /// cursor.
+    pub fn decode(cursor: &str) -> Option<Self> {
+        if cursor.len() != 48 || !cursor.is_ascii() {
+            return None;
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        let micros = i64::from_be_bytes(bytes[..8].try_into().ok()?);
+        let position = Utc.timestamp_micros(micros).single()?;
+        let thought_id = Uuid::from_slice(&bytes[8..]).ok()?;
+
+        Some(Self::new(position, thought_id))
+    }
+}
+
+/// A thread root with its activity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadSummary {
+    pub thread: ThoughtEnvelope,
+    /// Creation date of the most recent thought of the thread, root included.
+    pub last_activity: DateTime<Utc>,
+    /// Number of thoughts in the thread, root included.
+    pub thought_count: u64,
+}
+
+impl ThreadSummary {
+    /// Cursor pointing at this thread in a listing sorted with the given order.
+    pub fn cursor(&self, order: ThreadOrder) -> ListingCursor {
+        let position = match order {
+            ThreadOrder::CreatedAt => self.thread.created_at,
+            ThreadOrder::LastActivity => self.last_activity,
+        };
+
+        ListingCursor::new(position, self.thread.thought_id)
+    }
+}
+
+impl TryFrom<ThreadSummaryEntity> for ThreadSummary {
+    type Error = ThoughtConversionError;
+
+    fn try_from(value: ThreadSummaryEntity) -> Result<Self, Self::Error> {
+        Ok(Self {
+            thread: value.thread.try_into()?,
+            last_activity: value.last_activity,
+            thought_count: value.thought_count.max(0) as u64,
+        })
+    }
+}
+
+/// A page of a listing. When there are more items, `next_cursor` is the opaque cursor to pass
+/// to get the next page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Build a page from `limit + 1` fetched items: the extra item only tells there is a next
+    /// page, it is dropped and the cursor points at the last item kept.
+    pub fn from_items<F>(mut items: Vec<T>, limit: usize, cursor: F) -> Self
+    where
+        F: Fn(&T) -> ListingCursor,
+    {
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|item| cursor(item).encode())
+        } else {
+            None
+        };
+
+        Self { items, next_cursor }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = ListingCursor::new(
+            Utc.timestamp_micros(1_700_000_000_123_456).unwrap(),
+            Uuid::new_v4(),
+        );
+        let encoded = cursor.encode();
+
+        assert_eq!(48, encoded.len());
+        assert_eq!(Some(cursor), ListingCursor::decode(&encoded));
+        assert_eq!(None, ListingCursor::decode("not a cursor"));
+        assert_eq!(None, ListingCursor::decode(&encoded[..46]));
+    }
+
+    #[test]
+    fn page_from_items() {
+        let cursor = |i: &i64| ListingCursor::new(Utc.timestamp_micros(*i).unwrap(), Uuid::nil());
+        let page = Page::from_items(vec![1, 2, 3], 2, cursor);
+
+        assert_eq!(vec![1, 2], page.items);
+        assert_eq!(Some(cursor(&2).encode()), page.next_cursor);
+        assert_eq!(None, Page::from_items(vec![1, 2], 2, cursor).next_cursor);
+    }
+
+    #[test]
+    fn thread_order_names() {
+        assert_eq!(
+            Some(ThreadOrder::CreatedAt),
+            ThreadOrder::parse("created_at")
+        );
+        assert_eq!(
+            Some(ThreadOrder::LastActivity),
+            ThreadOrder::parse("last_activity")
+        );
+        assert_eq!(None, ThreadOrder::parse("oldest"));
+        assert!(ThreadOrder::CreatedAt.is_pageable());
+        assert!(!ThreadOrder::LastActivity.is_pageable());
+    }
+}
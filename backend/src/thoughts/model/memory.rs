@@ -0,0 +1,1847 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::{
+    is_category_descendant, matches_category_pattern, merge_items, render_snippet,
+    replace_reference, Attachment, Author, CategoryCount, ListingCursor, ThoughtContent,
+    ThoughtEnvelope as Thought, ThoughtLink, ThoughtRevision, ThoughtSearchResult, ThoughtSource,
+    ThoughtStore, ThoughtStoreError, ThoughtStoreResult, ThreadMerge, ThreadOrder, ThreadSummary,
+    TrashedThought, HIGHLIGHT_START, HIGHLIGHT_STOP,
+};
+
+/// Volatile ThoughtStore keeping the thoughts in memory. It behaves like the database backed
+/// store (parent validation, thread walking and ordering) and is meant for development and tests.
+/// All the thoughts are lost when the store is dropped. Soft deleted thoughts are moved to a
+/// separate trash. Locks are always taken in this order: thoughts, trash, revisions, sources,
+/// authors, links, attachments. The dates the store records come from the system clock unless a
+/// date is set with `set_clock`.
+#[derive(Debug, Default)]
+pub struct MemoryThoughtStore {
+    clock: Mutex<Option<DateTime<Utc>>>,
+    thoughts: RwLock<HashMap<Uuid, Thought>>,
+    trash: RwLock<HashMap<Uuid, TrashedThought>>,
+    revisions: RwLock<HashMap<Uuid, Vec<ThoughtRevision>>>,
+    sources: RwLock<HashMap<Uuid, ThoughtSource>>,
+    authors: RwLock<HashMap<Uuid, Author>>,
+    links: RwLock<HashMap<Uuid, ThoughtLink>>,
+    attachments: RwLock<HashMap<Uuid, Attachment>>,
+}
+
+impl MemoryThoughtStore {
+    /// Make the store record the given date instead of the current one, None gives the system
+    /// clock back. This lets tests order thoughts without waiting.
+    pub fn set_clock(&self, now: Option<DateTime<Utc>>) {
+        *self.clock.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    /// Date recorded by the store, truncated to the microsecond the database keeps.
+    fn now(&self) -> DateTime<Utc> {
+        self.clock
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .unwrap_or_else(Utc::now)
+            .trunc_subsecs(6)
+    }
+
+    /// Check the parent of the given thought exists in the store, like the foreign key of the
+    /// database would.
+    fn check_parent(
+        thoughts: &HashMap<Uuid, Thought>,
+        thought: &Thought,
+    ) -> ThoughtStoreResult<()> {
+        match thought.parent_thought_id() {
+            Some(parent_id) if !thoughts.contains_key(&parent_id) => {
+                Err(ThoughtStoreError::NotFound(format!(
+                    "Parent thought '{parent_id}' of thought '{}' does not exist.",
+                    thought.thought_id
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Render the text as an HTML snippet where the words containing one of the terms are
+    /// enclosed in `<mark>` tags.
+    fn highlight(text: &str, terms: &[String]) -> String {
+        let snippet = text
+            .split_whitespace()
+            .map(|word| {
+                let lowercase_word = word.to_lowercase();
+
+                if terms
+                    .iter()
+                    .any(|term| lowercase_word.contains(term.as_str()))
+                {
+                    format!("{HIGHLIGHT_START}{word}{HIGHLIGHT_STOP}")
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        render_snippet(&snippet)
+    }
+
+    /// The thread root and all its descendants, in no particular order.
+    fn thread_members<'a>(
+        thoughts: &'a HashMap<Uuid, Thought>,
+        root: &'a Thought,
+    ) -> Vec<&'a Thought> {
+        let mut members = vec![root];
+        let mut index = 0;
+
+        while index < members.len() {
+            let parent_id = members[index].thought_id;
+            members.extend(
+                thoughts
+                    .values()
+                    .filter(|t| t.parent_thought_id() == Some(parent_id)),
+            );
+            index += 1;
+        }
+
+        members
+    }
+
+    /// Record the replaced version of a thought, like the database trigger does.
+    fn record_revision(
+        revisions: &mut HashMap<Uuid, Vec<ThoughtRevision>>,
+        replaced: &Thought,
+        superseded_at: DateTime<Utc>,
+    ) {
+        let history = revisions.entry(replaced.thought_id).or_default();
+        let mut revision = ThoughtRevision::current(replaced, history);
+        revision.superseded_at = Some(superseded_at);
+        history.push(revision);
+    }
+
+    /// Links between live thoughts satisfying the predicate, oldest first.
+    async fn filter_links<F>(&self, predicate: F) -> Vec<ThoughtLink>
+    where
+        F: Fn(&ThoughtLink) -> bool,
+    {
+        let thoughts = self.thoughts.read().await;
+        let mut links: Vec<ThoughtLink> = self
+            .links
+            .read()
+            .await
+            .values()
+            .filter(|link| {
+                thoughts.contains_key(&link.from_thought_id)
+                    && thoughts.contains_key(&link.to_thought_id)
+                    && predicate(link)
+            })
+            .cloned()
+            .collect();
+        links.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then(a.link_id.cmp(&b.link_id))
+        });
+
+        links
+    }
+
+    /// Thoughts having at least one category satisfying the predicate, oldest first.
+    async fn filter_by_category<F>(&self, predicate: F) -> Vec<Thought>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut thoughts: Vec<Thought> = self
+            .thoughts
+            .read()
+            .await
+            .values()
+            .filter(|thought| thought.categories.iter().any(|c| predicate(c)))
+            .cloned()
+            .collect();
+        thoughts.sort_by_key(|t| t.created_at);
+
+        thoughts
+    }
+}
+
+#[async_trait]
+impl ThoughtStore for MemoryThoughtStore {
+    async fn get_thought(&self, thought_id: &Uuid) -> ThoughtStoreResult<Option<Thought>> {
+        Ok(self.thoughts.read().await.get(thought_id).cloned())
+    }
+
+    async fn get_thread(&self, thought_id: &Uuid) -> ThoughtStoreResult<Option<Vec<Thought>>> {
+        let thoughts = self.thoughts.read().await;
+        let mut thread: Vec<Thought> = Vec::new();
+        let mut visited: HashSet<Uuid> = HashSet::new();
+        let mut current = thoughts.get(thought_id);
+
+        while let Some(thought) = current {
+            if !visited.insert(thought.thought_id) {
+                return Err(ThoughtStoreError::Cycle(format!(
+                    "Cycle detected while walking up the thread of thought '{thought_id}', \
+                    thought '{}' is reached twice.",
+                    thought.thought_id
+                )));
+            }
+            thread.push(thought.clone());
+            current = thought
+                .parent_thought_id()
+                .and_then(|parent_id| thoughts.get(&parent_id));
+        }
+
+        if thread.is_empty() {
+            return Ok(None);
+        }
+        thread.reverse();
+
+        Ok(Some(thread))
+    }
+
+    async fn get_subtree(
+        &self,
+        thought_id: &Uuid,
+        max_depth: Option<u32>,
+    ) -> ThoughtStoreResult<Option<Vec<Thought>>> {
+        let thoughts = self.thoughts.read().await;
+        let root = match thoughts.get(thought_id) {
+            Some(root) => root.clone(),
+            None => return Ok(None),
+        };
+        let mut visited: HashSet<Uuid> = HashSet::from([root.thought_id]);
+        let mut level: Vec<Thought> = vec![root];
+        let mut subtree: Vec<Thought> = Vec::new();
+        let mut depth = 0;
+
+        while !level.is_empty() {
+            let parent_ids: HashSet<Uuid> = level.iter().map(|t| t.thought_id).collect();
+            subtree.append(&mut level);
+
+            if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                break;
+            }
+            level = thoughts
+                .values()
+                .filter(|t| {
+                    t.parent_thought_id()
+                        .is_some_and(|parent_id| parent_ids.contains(&parent_id))
+                        && visited.insert(t.thought_id)
+                })
+                .cloned()
+                .collect();
+            level.sort_by(|a, b| {
+                a.created_at
+                    .cmp(&b.created_at)
+                    .then(a.thought_id.cmp(&b.thought_id))
+            });
+            depth += 1;
+        }
+
+        Ok(Some(subtree))
+    }
+
+    /// Naive search: thoughts whose text or keywords contain all the terms of the query, case
+    /// insensitive. The rank is the number of occurrences of the terms.
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<ThoughtSearchResult>> {
+        let terms: Vec<String> = query.split_whitespace().map(|t| t.to_lowercase()).collect();
+
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let thoughts = self.thoughts.read().await;
+        let mut results: Vec<ThoughtSearchResult> = thoughts
+            .values()
+            .filter_map(|thought| {
+                let text = thought.plain_text();
+                let haystack = format!("{} {}", text, thought.keywords.join(" ")).to_lowercase();
+
+                if !terms.iter().all(|term| haystack.contains(term.as_str())) {
+                    return None;
+                }
+                let rank: usize = terms
+                    .iter()
+                    .map(|term| haystack.matches(term.as_str()).count())
+                    .sum();
+
+                Some(ThoughtSearchResult {
+                    thought: thought.clone(),
+                    rank: rank as f32,
+                    snippet: Self::highlight(&text, &terms),
+                })
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            b.rank
+                .total_cmp(&a.rank)
+                .then(b.thought.created_at.cmp(&a.thought.created_at))
+        });
+        results.truncate(limit);
+
+        Ok(results)
+    }
+
+    async fn get_category_counts(&self) -> ThoughtStoreResult<Vec<CategoryCount>> {
+        let thoughts = self.thoughts.read().await;
+        let mut paths: BTreeSet<String> = BTreeSet::new();
+
+        for category in thoughts.values().flat_map(|t| t.categories.iter()) {
+            let labels: Vec<&str> = category.split('.').collect();
+
+            for depth in 1..=labels.len() {
+                paths.insert(labels[..depth].join("."));
+            }
+        }
+
+        Ok(paths
+            .into_iter()
+            .map(|path| {
+                let thought_count = thoughts
+                    .values()
+                    .filter(|t| {
+                        t.categories
+                            .iter()
+                            .any(|c| is_category_descendant(c, &path))
+                    })
+                    .count() as u64;
+
+                CategoryCount {
+                    path,
+                    thought_count,
+                }
+            })
+            .collect())
+    }
+
+    async fn get_thoughts_by_category(&self, path: &str) -> ThoughtStoreResult<Vec<Thought>> {
+        Ok(self
+            .filter_by_category(|category| is_category_descendant(category, path))
+            .await)
+    }
+
+    async fn get_thoughts_by_category_pattern(
+        &self,
+        pattern: &str,
+    ) -> ThoughtStoreResult<Vec<Thought>> {
+        Ok(self
+            .filter_by_category(|category| matches_category_pattern(category, pattern))
+            .await)
+    }
+
+    async fn list_threads(
+        &self,
+        order: ThreadOrder,
+        after: Option<&ListingCursor>,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<ThreadSummary>> {
+        let thoughts = self.thoughts.read().await;
+        let mut threads: Vec<ThreadSummary> = thoughts
+            .values()
+            .filter(|thought| thought.parent_thought_id().is_none())
+            .map(|thread| {
+                let members = Self::thread_members(&thoughts, thread);
+
+                ThreadSummary {
+                    thread: thread.clone(),
+                    last_activity: members
+                        .iter()
+                        .map(|t| t.created_at)
+                        .max()
+                        .unwrap_or(thread.created_at),
+                    thought_count: members.len() as u64,
+                }
+            })
+            .filter(|summary| match after {
+                Some(cursor) => summary.cursor(order) < *cursor,
+                None => true,
+            })
+            .collect();
+        threads.sort_by_key(|summary| std::cmp::Reverse(summary.cursor(order)));
+        threads.truncate(limit);
+
+        Ok(threads)
+    }
+
+    async fn list_thread_thoughts(
+        &self,
+        thread_id: &Uuid,
+        after: Option<&ListingCursor>,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<Thought>> {
+        let thoughts = self.thoughts.read().await;
+        let root = match thoughts.get(thread_id) {
+            Some(root) if root.parent_thought_id().is_none() => root,
+            _ => return Ok(Vec::new()),
+        };
+        let mut members: Vec<Thought> = Self::thread_members(&thoughts, root)
+            .into_iter()
+            .filter(|thought| match after {
+                Some(cursor) => {
+                    ListingCursor::new(thought.created_at, thought.thought_id) > *cursor
+                }
+                None => true,
+            })
+            .cloned()
+            .collect();
+        members.sort_by_key(|t| (t.created_at, t.thought_id));
+        members.truncate(limit);
+
+        Ok(members)
+    }
+
+    async fn get_trashed_thought(
+        &self,
+        thought_id: &Uuid,
+    ) -> ThoughtStoreResult<Option<TrashedThought>> {
+        Ok(self.trash.read().await.get(thought_id).cloned())
+    }
+
+    async fn list_trash(&self) -> ThoughtStoreResult<Vec<TrashedThought>> {
+        let mut trash: Vec<TrashedThought> = self.trash.read().await.values().cloned().collect();
+        trash.sort_by(|a, b| {
+            b.deleted_at
+                .cmp(&a.deleted_at)
+                .then(a.thought.thought_id.cmp(&b.thought.thought_id))
+        });
+
+        Ok(trash)
+    }
+
+    async fn delete_thought(
+        &self,
+        thought_id: &Uuid,
+        recursive: bool,
+    ) -> ThoughtStoreResult<Vec<Uuid>> {
+        let mut thoughts = self.thoughts.write().await;
+        let mut trash = self.trash.write().await;
+        let subtree: Vec<Uuid> = match thoughts.get(thought_id) {
+            Some(thought) => Self::thread_members(&thoughts, thought)
+                .into_iter()
+                .map(|t| t.thought_id)
+                .collect(),
+            None => return Ok(Vec::new()),
+        };
+
+        if !recursive && subtree.len() > 1 {
+            return Ok(Vec::new());
+        }
+        let deleted_at = self.now();
+
+        for thought_id in &subtree {
+            if let Some(thought) = thoughts.remove(thought_id) {
+                trash.insert(
+                    *thought_id,
+                    TrashedThought {
+                        thought,
+                        deleted_at,
+                    },
+                );
+            }
+        }
+
+        Ok(subtree)
+    }
+
+    async fn restore_thought(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<Uuid>> {
+        let mut thoughts = self.thoughts.write().await;
+        let mut trash = self.trash.write().await;
+        let deleted_at = match trash.get(thought_id) {
+            Some(trashed) => trashed.deleted_at,
+            None => return Ok(Vec::new()),
+        };
+        let mut subtree = vec![*thought_id];
+        let mut index = 0;
+
+        while index < subtree.len() {
+            let parent_id = subtree[index];
+            subtree.extend(
+                trash
+                    .values()
+                    .filter(|t| {
+                        t.deleted_at == deleted_at
+                            && t.thought.parent_thought_id() == Some(parent_id)
+                    })
+                    .map(|t| t.thought.thought_id),
+            );
+            index += 1;
+        }
+
+        for thought_id in &subtree {
+            if let Some(trashed) = trash.remove(thought_id) {
+                thoughts.insert(*thought_id, trashed.thought);
+            }
+        }
+
+        Ok(subtree)
+    }
+
+    async fn purge_trash(&self, before: &DateTime<Utc>) -> ThoughtStoreResult<Vec<Uuid>> {
+        let mut trash = self.trash.write().await;
+        let purged: Vec<Uuid> = trash
+            .values()
+            .filter(|t| t.deleted_at < *before)
+            .map(|t| t.thought.thought_id)
+            .collect();
+
+        let mut revisions = self.revisions.write().await;
+
+        for thought_id in &purged {
+            trash.remove(thought_id);
+            revisions.remove(thought_id);
+        }
+        // like the foreign keys of the database, purged thoughts take their links with them
+        self.links.write().await.retain(|_, link| {
+            !purged.contains(&link.from_thought_id) && !purged.contains(&link.to_thought_id)
+        });
+        self.attachments
+            .write()
+            .await
+            .retain(|_, attachment| !purged.contains(&attachment.thought_id));
+
+        Ok(purged)
+    }
+
+    async fn get_revisions(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtRevision>> {
+        Ok(self
+            .revisions
+            .read()
+            .await
+            .get(thought_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn get_source(&self, source_id: &Uuid) -> ThoughtStoreResult<Option<ThoughtSource>> {
+        Ok(self.sources.read().await.get(source_id).cloned())
+    }
+
+    async fn get_sources(&self) -> ThoughtStoreResult<Vec<ThoughtSource>> {
+        let mut sources: Vec<ThoughtSource> = self.sources.read().await.values().cloned().collect();
+        sources.sort_by(|a, b| a.name.cmp(&b.name).then(a.source_id.cmp(&b.source_id)));
+
+        Ok(sources)
+    }
+
+    async fn insert_source(&self, source: ThoughtSource) -> ThoughtStoreResult<ThoughtSource> {
+        let mut sources = self.sources.write().await;
+        let duplicate_key = source.duplicate_key();
+
+        if let Some(registered) = sources
+            .values()
+            .find(|s| s.duplicate_key() == duplicate_key)
+        {
+            return Ok(registered.clone());
+        }
+        if sources.contains_key(&source.source_id) {
+            return Err(ThoughtStoreError::Conflict(format!(
+                "Could not insert source '{}', it already exists.",
+                source.source_id
+            )));
+        }
+        sources.insert(source.source_id, source.clone());
+
+        Ok(source)
+    }
+
+    async fn get_thoughts_by_source(&self, source_id: &Uuid) -> ThoughtStoreResult<Vec<Thought>> {
+        let mut thoughts: Vec<Thought> = self
+            .thoughts
+            .read()
+            .await
+            .values()
+            .filter(|thought| thought.source_ids.contains(source_id))
+            .cloned()
+            .collect();
+        thoughts.sort_by_key(|t| t.created_at);
+
+        Ok(thoughts)
+    }
+
+    async fn merge_sources(
+        &self,
+        kept_id: &Uuid,
+        absorbed_id: &Uuid,
+    ) -> ThoughtStoreResult<Vec<Uuid>> {
+        let mut thoughts = self.thoughts.write().await;
+        let mut trash = self.trash.write().await;
+        let mut revisions = self.revisions.write().await;
+        let mut sources = self.sources.write().await;
+        let mut updated: Vec<Uuid> = Vec::new();
+
+        for thought in thoughts.values_mut() {
+            let existing = thought.clone();
+
+            if replace_reference(&mut thought.source_ids, kept_id, absorbed_id) {
+                Self::record_revision(&mut revisions, &existing, self.now());
+                updated.push(thought.thought_id);
+            }
+        }
+        for trashed in trash.values_mut() {
+            let existing = trashed.thought.clone();
+
+            if replace_reference(&mut trashed.thought.source_ids, kept_id, absorbed_id) {
+                Self::record_revision(&mut revisions, &existing, self.now());
+            }
+        }
+        for revision in revisions.values_mut().flatten() {
+            replace_reference(&mut revision.source_ids, kept_id, absorbed_id);
+        }
+        sources.remove(absorbed_id);
+
+        Ok(updated)
+    }
+
+    async fn get_author(&self, author_id: &Uuid) -> ThoughtStoreResult<Option<Author>> {
+        Ok(self.authors.read().await.get(author_id).cloned())
+    }
+
+    async fn get_authors(&self) -> ThoughtStoreResult<Vec<Author>> {
+        let mut authors: Vec<Author> = self.authors.read().await.values().cloned().collect();
+        authors.sort_by(|a, b| a.name.cmp(&b.name).then(a.author_id.cmp(&b.author_id)));
+
+        Ok(authors)
+    }
+
+    async fn find_author(&self, alias: &str) -> ThoughtStoreResult<Option<Author>> {
+        Ok(self
+            .authors
+            .read()
+            .await
+            .values()
+            .find(|author| author.is_known_as(alias))
+            .cloned())
+    }
+
+    async fn insert_author(&self, mut author: Author) -> ThoughtStoreResult<Author> {
+        let mut authors = self.authors.write().await;
+
+        if let Some(known) = authors.values().find(|a| a.is_known_as(&author.name)) {
+            return Ok(known.clone());
+        }
+        if authors.contains_key(&author.author_id) {
+            return Err(ThoughtStoreError::Conflict(format!(
+                "Could not insert author '{}', it already exists.",
+                author.author_id
+            )));
+        }
+        author.aliases = vec![author.name.clone()];
+        authors.insert(author.author_id, author.clone());
+
+        Ok(author)
+    }
+
+    async fn add_author_alias(&self, author_id: &Uuid, alias: &str) -> ThoughtStoreResult<()> {
+        let mut sources = self.sources.write().await;
+        let mut authors = self.authors.write().await;
+
+        if !authors.contains_key(author_id) {
+            return Err(ThoughtStoreError::NotFound(format!(
+                "Could not add alias '{alias}' to author '{author_id}', it does not exist."
+            )));
+        }
+        let absorbed_id = authors
+            .values()
+            .find(|author| author.is_known_as(alias))
+            .map(|author| author.author_id);
+        let mut aliases: Vec<String> = match absorbed_id {
+            Some(absorbed_id) if absorbed_id == *author_id => return Ok(()),
+            Some(absorbed_id) => {
+                for source in sources.values_mut() {
+                    replace_reference(&mut source.author_ids, author_id, &absorbed_id);
+                }
+                authors
+                    .remove(&absorbed_id)
+                    .map(|absorbed| absorbed.aliases)
+                    .unwrap_or_default()
+            }
+            None => vec![alias.to_string()],
+        };
+
+        if let Some(author) = authors.get_mut(author_id) {
+            author.aliases.append(&mut aliases);
+            author.aliases.sort();
+        }
+
+        Ok(())
+    }
+
+    async fn get_sources_by_author(
+        &self,
+        author_id: &Uuid,
+    ) -> ThoughtStoreResult<Vec<ThoughtSource>> {
+        let mut sources: Vec<ThoughtSource> = self
+            .sources
+            .read()
+            .await
+            .values()
+            .filter(|source| source.author_ids.contains(author_id))
+            .cloned()
+            .collect();
+        sources.sort_by(|a, b| a.name.cmp(&b.name).then(a.source_id.cmp(&b.source_id)));
+
+        Ok(sources)
+    }
+
+    async fn get_thoughts_by_author(&self, author_id: &Uuid) -> ThoughtStoreResult<Vec<Thought>> {
+        let thoughts = self.thoughts.read().await;
+        let source_ids: HashSet<Uuid> = self
+            .sources
+            .read()
+            .await
+            .values()
+            .filter(|source| source.author_ids.contains(author_id))
+            .map(|source| source.source_id)
+            .collect();
+        let mut thoughts: Vec<Thought> = thoughts
+            .values()
+            .filter(|thought| thought.source_ids.iter().any(|s| source_ids.contains(s)))
+            .cloned()
+            .collect();
+        thoughts.sort_by_key(|t| t.created_at);
+
+        Ok(thoughts)
+    }
+
+    async fn get_link(&self, link_id: &Uuid) -> ThoughtStoreResult<Option<ThoughtLink>> {
+        Ok(self
+            .filter_links(|link| link.link_id == *link_id)
+            .await
+            .pop())
+    }
+
+    async fn get_outgoing_links(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtLink>> {
+        Ok(self
+            .filter_links(|link| link.from_thought_id == *thought_id)
+            .await)
+    }
+
+    async fn get_backlinks(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtLink>> {
+        Ok(self
+            .filter_links(|link| link.to_thought_id == *thought_id)
+            .await)
+    }
+
+    async fn insert_link(&self, mut link: ThoughtLink) -> ThoughtStoreResult<ThoughtLink> {
+        let thoughts = self.thoughts.read().await;
+        let trash = self.trash.read().await;
+        let mut links = self.links.write().await;
+
+        // mirror the constraints of the database
+        for thought_id in [&link.from_thought_id, &link.to_thought_id] {
+            if !thoughts.contains_key(thought_id) && !trash.contains_key(thought_id) {
+                return Err(ThoughtStoreError::NotFound(format!(
+                    "Could not insert link '{}', thought '{thought_id}' does not exist.",
+                    link.link_id
+                )));
+            }
+        }
+        if link.from_thought_id == link.to_thought_id {
+            return Err(ThoughtStoreError::Conflict(format!(
+                "Could not insert link '{}', a thought cannot link to itself.",
+                link.link_id
+            )));
+        }
+        if links.contains_key(&link.link_id)
+            || links.values().any(|l| {
+                (l.from_thought_id, l.to_thought_id, l.kind)
+                    == (link.from_thought_id, link.to_thought_id, link.kind)
+            })
+        {
+            return Err(ThoughtStoreError::Conflict(format!(
+                "Could not insert link '{}', it already exists.",
+                link.link_id
+            )));
+        }
+        link.created_at = self.now();
+        links.insert(link.link_id, link.clone());
+
+        Ok(link)
+    }
+
+    async fn delete_link(&self, link_id: &Uuid) -> ThoughtStoreResult<bool> {
+        Ok(self.links.write().await.remove(link_id).is_some())
+    }
+
+    async fn get_attachment(&self, attachment_id: &Uuid) -> ThoughtStoreResult<Option<Attachment>> {
+        Ok(self.attachments.read().await.get(attachment_id).cloned())
+    }
+
+    async fn get_attachments(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<Attachment>> {
+        let mut attachments: Vec<Attachment> = self
+            .attachments
+            .read()
+            .await
+            .values()
+            .filter(|attachment| attachment.thought_id == *thought_id)
+            .cloned()
+            .collect();
+        attachments.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then(a.attachment_id.cmp(&b.attachment_id))
+        });
+
+        Ok(attachments)
+    }
+
+    async fn insert_attachment(
+        &self,
+        mut attachment: Attachment,
+    ) -> ThoughtStoreResult<Attachment> {
+        let thoughts = self.thoughts.read().await;
+        let trash = self.trash.read().await;
+        let mut attachments = self.attachments.write().await;
+
+        // mirror the constraints of the database
+        if !thoughts.contains_key(&attachment.thought_id)
+            && !trash.contains_key(&attachment.thought_id)
+        {
+            return Err(ThoughtStoreError::NotFound(format!(
+                "Could not insert attachment '{}', thought '{}' does not exist.",
+                attachment.attachment_id, attachment.thought_id
+            )));
+        }
+        if attachments.contains_key(&attachment.attachment_id) {
+            return Err(ThoughtStoreError::Conflict(format!(
+                "Could not insert attachment '{}', it already exists.",
+                attachment.attachment_id
+            )));
+        }
+        attachment.created_at = self.now();
+        attachments.insert(attachment.attachment_id, attachment.clone());
+
+        Ok(attachment)
+    }
+
+    async fn delete_attachment(&self, attachment_id: &Uuid) -> ThoughtStoreResult<bool> {
+        Ok(self
+            .attachments
+            .write()
+            .await
+            .remove(attachment_id)
+            .is_some())
+    }
+
+    async fn get_attachment_hashes(&self) -> ThoughtStoreResult<Vec<String>> {
+        let hashes: BTreeSet<String> = self
+            .attachments
+            .read()
+            .await
+            .values()
+            .map(|attachment| attachment.hash.clone())
+            .collect();
+
+        Ok(hashes.into_iter().collect())
+    }
+
+    async fn insert_thought(&self, mut thought: Thought) -> ThoughtStoreResult<Thought> {
+        let mut thoughts = self.thoughts.write().await;
+
+        if thoughts.contains_key(&thought.thought_id)
+            || self.trash.read().await.contains_key(&thought.thought_id)
+        {
+            return Err(ThoughtStoreError::Conflict(format!(
+                "Could not insert thought '{}', it already exists.",
+                thought.thought_id
+            )));
+        }
+        Self::check_parent(&thoughts, &thought)?;
+        thought.created_at = self.now();
+        thoughts.insert(thought.thought_id, thought.clone());
+
+        Ok(thought)
+    }
+
+    async fn update_thought(&self, mut thought: Thought) -> ThoughtStoreResult<Option<Thought>> {
+        let mut thoughts = self.thoughts.write().await;
+
+        let existing = match thoughts.get(&thought.thought_id) {
+            Some(existing) => existing.clone(),
+            None => return Ok(None),
+        };
+        // like the database update, the parent is kept, moving a thought is not an update
+        if existing.parent_thought_id() != thought.parent_thought_id() {
+            return Ok(None);
+        }
+        thought.created_at = existing.created_at;
+
+        // like the database trigger, record the replaced version if an editable part changed
+        if (
+            existing.text(),
+            existing.content_format,
+            &existing.keywords,
+            &existing.categories,
+            &existing.source_ids,
+        ) != (
+            thought.text(),
+            thought.content_format,
+            &thought.keywords,
+            &thought.categories,
+            &thought.source_ids,
+        ) {
+            Self::record_revision(&mut *self.revisions.write().await, &existing, self.now());
+        }
+        thoughts.insert(thought.thought_id, thought.clone());
+
+        Ok(Some(thought))
+    }
+
+    async fn move_thought(
+        &self,
+        thought_id: &Uuid,
+        parent_thought_id: Option<&Uuid>,
+    ) -> ThoughtStoreResult<Option<Vec<Uuid>>> {
+        let mut thoughts = self.thoughts.write().await;
+        let moved = match thoughts.get(thought_id) {
+            Some(moved) => moved,
+            None => return Ok(None),
+        };
+        let subtree: Vec<Uuid> = Self::thread_members(&thoughts, moved)
+            .iter()
+            .map(|t| t.thought_id)
+            .collect();
+
+        if let Some(parent_thought_id) = parent_thought_id {
+            if !thoughts.contains_key(parent_thought_id) {
+                return Err(ThoughtStoreError::NotFound(format!(
+                    "Parent thought '{parent_thought_id}' of thought '{thought_id}' does not exist."
+                )));
+            }
+            if subtree.contains(parent_thought_id) {
+                return Err(ThoughtStoreError::Conflict(format!(
+                    "Thought '{parent_thought_id}' is thought '{thought_id}' or one of its \
+                    descendants, moving under it would create a cycle."
+                )));
+            }
+        }
+        let moved = thoughts.get_mut(thought_id).ok_or_else(|| {
+            ThoughtStoreError::NotFound(format!(
+                "Thought '{thought_id}' vanished while being moved."
+            ))
+        })?;
+        let text = moved.text().to_string();
+        moved.content = match parent_thought_id {
+            Some(parent_thought_id) => ThoughtContent::Node {
+                parent_thought_id: *parent_thought_id,
+                thought: text,
+            },
+            None => ThoughtContent::Thread { title: text },
+        };
+
+        Ok(Some(subtree))
+    }
+
+    async fn combine_threads(
+        &self,
+        kept_id: &Uuid,
+        absorbed_id: &Uuid,
+    ) -> ThoughtStoreResult<Option<ThreadMerge>> {
+        let mut thoughts = self.thoughts.write().await;
+        let mut trash = self.trash.write().await;
+        let mut revisions = self.revisions.write().await;
+        let mut links = self.links.write().await;
+        let mut attachments = self.attachments.write().await;
+        let (kept, absorbed) = match (thoughts.get(kept_id), thoughts.get(absorbed_id)) {
+            (Some(kept), Some(absorbed))
+                if kept_id != absorbed_id
+                    && kept.parent_thought_id().is_none()
+                    && absorbed.parent_thought_id().is_none() =>
+            {
+                (kept.clone(), absorbed.clone())
+            }
+            _ => return Ok(None),
+        };
+        let descendants: Vec<Uuid> = Self::thread_members(&thoughts, &absorbed)
+            .iter()
+            .skip(1)
+            .map(|t| t.thought_id)
+            .collect();
+
+        let mut combined = kept.clone();
+        merge_items(&mut combined.keywords, &absorbed.keywords);
+        merge_items(&mut combined.categories, &absorbed.categories);
+        merge_items(&mut combined.source_ids, &absorbed.source_ids);
+        if combined != kept {
+            Self::record_revision(&mut revisions, &kept, self.now());
+        }
+        thoughts.insert(*kept_id, combined);
+
+        // children are moved whether they are live or in the trash
+        let children = thoughts
+            .values_mut()
+            .chain(trash.values_mut().map(|t| &mut t.thought))
+            .filter(|t| t.parent_thought_id() == Some(*absorbed_id));
+        for child in children {
+            let thought = child.text().to_string();
+            child.content = ThoughtContent::Node {
+                parent_thought_id: *kept_id,
+                thought,
+            };
+        }
+
+        let mut merge = ThreadMerge {
+            updated_thought_ids: [*kept_id].into_iter().chain(descendants).collect(),
+            deleted_thought_id: Some(*absorbed_id),
+            ..ThreadMerge::default()
+        };
+        let redirect = |thought_id: Uuid| match thought_id == *absorbed_id {
+            true => *kept_id,
+            false => thought_id,
+        };
+        let mut link_ids: Vec<Uuid> = links
+            .values()
+            .filter(|l| l.from_thought_id == *absorbed_id || l.to_thought_id == *absorbed_id)
+            .map(|l| l.link_id)
+            .collect();
+        link_ids.sort();
+        for link_id in link_ids {
+            let mut link = links.remove(&link_id).ok_or_else(|| {
+                ThoughtStoreError::NotFound(format!(
+                    "Link '{link_id}' vanished while thread '{absorbed_id}' was merged."
+                ))
+            })?;
+            link.from_thought_id = redirect(link.from_thought_id);
+            link.to_thought_id = redirect(link.to_thought_id);
+            let duplicate = links.values().any(|l| {
+                (l.from_thought_id, l.to_thought_id, l.kind)
+                    == (link.from_thought_id, link.to_thought_id, link.kind)
+            });
+
+            if link.from_thought_id == link.to_thought_id || duplicate {
+                merge.removed_link_ids.push(link_id);
+            } else {
+                links.insert(link_id, link);
+                merge.redirected_link_ids.push(link_id);
+            }
+        }
+
+        for attachment in attachments
+            .values_mut()
+            .filter(|a| a.thought_id == *absorbed_id)
+        {
+            attachment.thought_id = *kept_id;
+            merge.moved_attachment_ids.push(attachment.attachment_id);
+        }
+        merge.moved_attachment_ids.sort();
+
+        // like the database, the absorbed root is deleted for good so it cannot be restored
+        thoughts.remove(absorbed_id);
+        revisions.remove(absorbed_id);
+
+        Ok(Some(merge))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::{
+        thoughts::model::{ContentFormat, LinkKind},
+        StdResult,
+    };
+
+    fn thread(thought_id: Uuid, title: &str) -> Thought {
+        Thought {
+            thought_id,
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            source_ids: Vec::new(),
+            created_at: DateTime::UNIX_EPOCH,
+            content: ThoughtContent::Thread {
+                title: title.to_string(),
+            },
+            content_format: ContentFormat::Plain,
+        }
+    }
+
+    /// Date the given number of seconds after the epoch, to record thoughts in a known order.
+    fn at(seconds: i64) -> Option<DateTime<Utc>> {
+        Some(DateTime::UNIX_EPOCH + chrono::Duration::seconds(seconds))
+    }
+
+    fn node(thought_id: Uuid, parent_thought_id: Uuid, thought: &str) -> Thought {
+        Thought {
+            content: ThoughtContent::Node {
+                parent_thought_id,
+                thought: thought.to_string(),
+            },
+            ..thread(thought_id, "")
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_and_get() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let thought_id = Uuid::new_v4();
+        let inserted = store.insert_thought(thread(thought_id, "title")).await?;
+
+        assert_ne!(DateTime::UNIX_EPOCH, inserted.created_at);
+        assert_eq!(Some(inserted), store.get_thought(&thought_id).await?);
+        assert_eq!(None, store.get_thought(&Uuid::new_v4()).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn insert_checks_parent_and_duplicates() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let thought_id = Uuid::new_v4();
+
+        assert!(matches!(
+            store
+                .insert_thought(node(thought_id, Uuid::new_v4(), "orphan"))
+                .await,
+            Err(ThoughtStoreError::NotFound(_))
+        ));
+        store.insert_thought(thread(thought_id, "title")).await?;
+        assert!(matches!(
+            store.insert_thought(thread(thought_id, "title")).await,
+            Err(ThoughtStoreError::Conflict(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_keeps_creation_date() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let thought_id = Uuid::new_v4();
+
+        assert_eq!(None, store.update_thought(thread(thought_id, "t")).await?);
+
+        let inserted = store.insert_thought(thread(thought_id, "title")).await?;
+        let updated = store
+            .update_thought(thread(thought_id, "new title"))
+            .await?
+            .unwrap();
+
+        assert_eq!(inserted.created_at, updated.created_at);
+        assert_eq!(
+            ThoughtContent::Thread {
+                title: "new title".to_string()
+            },
+            updated.content
+        );
+        assert_eq!(
+            None,
+            store
+                .update_thought(node(thought_id, Uuid::new_v4(), "moved"))
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn thread_is_ordered_from_root() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (root_id, first_id, second_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        store.insert_thought(thread(root_id, "root")).await?;
+        store
+            .insert_thought(node(first_id, root_id, "first"))
+            .await?;
+        store
+            .insert_thought(node(second_id, first_id, "second"))
+            .await?;
+
+        let ids: Vec<Uuid> = store
+            .get_thread(&second_id)
+            .await?
+            .unwrap()
+            .into_iter()
+            .map(|t| t.thought_id)
+            .collect();
+
+        assert_eq!(vec![root_id, first_id, second_id], ids);
+        assert_eq!(None, store.get_thread(&Uuid::new_v4()).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn subtree_is_walked_down_to_max_depth() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (root_id, first_id, second_id, leaf_id) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        store.insert_thought(thread(root_id, "root")).await?;
+        store
+            .insert_thought(node(first_id, root_id, "first"))
+            .await?;
+        store
+            .insert_thought(node(second_id, root_id, "second"))
+            .await?;
+        store
+            .insert_thought(node(leaf_id, first_id, "leaf"))
+            .await?;
+
+        let ids = |subtree: Vec<Thought>| -> Vec<Uuid> {
+            subtree.into_iter().map(|t| t.thought_id).collect()
+        };
+        let subtree = ids(store.get_subtree(&root_id, None).await?.unwrap());
+        assert_eq!(4, subtree.len());
+        assert_eq!(root_id, subtree[0]);
+        assert_eq!(leaf_id, subtree[3]);
+        assert_eq!(
+            3,
+            store.get_subtree(&root_id, Some(1)).await?.unwrap().len()
+        );
+        assert_eq!(
+            vec![first_id, leaf_id],
+            ids(store.get_subtree(&first_id, None).await?.unwrap())
+        );
+        assert_eq!(None, store.get_subtree(&Uuid::new_v4(), None).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn move_rejects_cycles() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (root_id, first_id, leaf_id, other_id) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        store.insert_thought(thread(root_id, "root")).await?;
+        store
+            .insert_thought(node(first_id, root_id, "first"))
+            .await?;
+        store
+            .insert_thought(node(leaf_id, first_id, "leaf"))
+            .await?;
+        store.insert_thought(thread(other_id, "other")).await?;
+
+        assert!(matches!(
+            store.move_thought(&first_id, Some(&leaf_id)).await,
+            Err(ThoughtStoreError::Conflict(_))
+        ));
+        assert!(matches!(
+            store.move_thought(&first_id, Some(&first_id)).await,
+            Err(ThoughtStoreError::Conflict(_))
+        ));
+        assert!(matches!(
+            store.move_thought(&first_id, Some(&Uuid::new_v4())).await,
+            Err(ThoughtStoreError::NotFound(_))
+        ));
+        assert_eq!(
+            Some(vec![first_id, leaf_id]),
+            store.move_thought(&first_id, Some(&other_id)).await?
+        );
+        assert_eq!(
+            Some(other_id),
+            store
+                .get_thought(&first_id)
+                .await?
+                .unwrap()
+                .parent_thought_id()
+        );
+        assert_eq!(
+            other_id,
+            store.get_thread(&leaf_id).await?.unwrap()[0].thought_id
+        );
+
+        assert_eq!(
+            Some(vec![leaf_id]),
+            store.move_thought(&leaf_id, None).await?
+        );
+        assert_eq!(
+            ThoughtContent::Thread {
+                title: "leaf".to_string()
+            },
+            store.get_thought(&leaf_id).await?.unwrap().content
+        );
+        assert_eq!(None, store.move_thought(&Uuid::new_v4(), None).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn combined_threads_share_one_root() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (kept_id, absorbed_id, child_id, other_id) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        store
+            .insert_thought(Thought {
+                keywords: vec!["ethics".to_string()],
+                ..thread(kept_id, "kept")
+            })
+            .await?;
+        store
+            .insert_thought(Thought {
+                keywords: vec!["virtue".to_string(), "ethics".to_string()],
+                ..thread(absorbed_id, "absorbed")
+            })
+            .await?;
+        store
+            .insert_thought(node(child_id, absorbed_id, "child"))
+            .await?;
+        store.insert_thought(thread(other_id, "other")).await?;
+        let link = |from_thought_id: Uuid, to_thought_id: Uuid| ThoughtLink {
+            link_id: Uuid::new_v4(),
+            from_thought_id,
+            to_thought_id,
+            kind: LinkKind::SeeAlso,
+            created_at: DateTime::UNIX_EPOCH,
+        };
+        let to_kept = store.insert_link(link(absorbed_id, kept_id)).await?;
+        let duplicate = store.insert_link(link(absorbed_id, other_id)).await?;
+        store.insert_link(link(kept_id, other_id)).await?;
+        let from_other = store.insert_link(link(other_id, absorbed_id)).await?;
+        let attachment_id = Uuid::new_v4();
+        store
+            .insert_attachment(Attachment {
+                attachment_id,
+                thought_id: absorbed_id,
+                file_name: "memo.ogg".to_string(),
+                mime_type: "audio/ogg".to_string(),
+                size: 12,
+                hash: "0".repeat(64),
+                created_at: DateTime::UNIX_EPOCH,
+            })
+            .await?;
+
+        assert_eq!(None, store.combine_threads(&kept_id, &child_id).await?);
+        let merge = store
+            .combine_threads(&kept_id, &absorbed_id)
+            .await?
+            .unwrap();
+        assert_eq!(vec![kept_id, child_id], merge.updated_thought_ids);
+        assert_eq!(Some(absorbed_id), merge.deleted_thought_id);
+        assert_eq!(vec![from_other.link_id], merge.redirected_link_ids);
+        let mut removed = vec![to_kept.link_id, duplicate.link_id];
+        removed.sort();
+        assert_eq!(removed, merge.removed_link_ids);
+
+        let kept = store.get_thought(&kept_id).await?.unwrap();
+        assert_eq!(vec!["ethics", "virtue"], kept.keywords);
+        assert_eq!(1, store.get_revisions(&kept_id).await?.len());
+        assert_eq!(
+            kept_id,
+            store.get_thread(&child_id).await?.unwrap()[0].thought_id
+        );
+        assert_eq!(None, store.get_thought(&absorbed_id).await?);
+        assert_eq!(None, store.get_trashed_thought(&absorbed_id).await?);
+        assert!(store.restore_thought(&absorbed_id).await?.is_empty());
+        assert_eq!(
+            kept_id,
+            store
+                .get_attachment(&attachment_id)
+                .await?
+                .unwrap()
+                .thought_id
+        );
+        assert_eq!(vec![attachment_id], merge.moved_attachment_ids);
+        assert_eq!(
+            kept_id,
+            store.get_outgoing_links(&other_id).await?[0].to_thought_id
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn thread_cycle_is_reported() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (first_id, second_id) = (Uuid::new_v4(), Uuid::new_v4());
+        store.insert_thought(thread(first_id, "first")).await?;
+        store
+            .insert_thought(node(second_id, first_id, "second"))
+            .await?;
+        // the updates cannot change a parent, corrupt the store like a database could be
+        store
+            .thoughts
+            .write()
+            .await
+            .insert(first_id, node(first_id, second_id, "first"));
+
+        assert!(matches!(
+            store.get_thread(&second_id).await,
+            Err(ThoughtStoreError::Cycle(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_ranks_and_highlights() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (first_id, second_id) = (Uuid::new_v4(), Uuid::new_v4());
+        store
+            .insert_thought(thread(first_id, "Stoic philosophy"))
+            .await?;
+        store
+            .insert_thought(node(second_id, first_id, "Philosophy is a philosophy"))
+            .await?;
+
+        let results = store.search("PHILOSOPHY", 10).await?;
+
+        assert_eq!(2, results.len());
+        assert_eq!(second_id, results[0].thought.thought_id);
+        assert_eq!(
+            "<mark>Philosophy</mark> is a <mark>philosophy</mark>",
+            results[0].snippet
+        );
+        assert_eq!(1, store.search("stoic philosophy", 10).await?.len());
+        assert_eq!(1, store.search("philosophy", 1).await?.len());
+        assert!(store.search("  ", 10).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_ignores_markdown_markup() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let thought_id = Uuid::new_v4();
+        store
+            .insert_thought(Thought {
+                content_format: ContentFormat::Markdown,
+                ..thread(thought_id, "**Stoic** [ethics](https://example.org/markup)")
+            })
+            .await?;
+
+        assert!(store.search("markup", 10).await?.is_empty());
+        assert_eq!(
+            "<mark>Stoic</mark> ethics",
+            store.search("stoic", 10).await?[0].snippet
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn categories_are_counted_and_browsed() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (first_id, second_id) = (Uuid::new_v4(), Uuid::new_v4());
+        store
+            .insert_thought(Thought {
+                categories: vec!["philosophy.ethics".to_string()],
+                ..thread(first_id, "first")
+            })
+            .await?;
+        store
+            .insert_thought(Thought {
+                categories: vec!["philosophy".to_string(), "science".to_string()],
+                ..thread(second_id, "second")
+            })
+            .await?;
+
+        let counts: Vec<(String, u64)> = store
+            .get_category_counts()
+            .await?
+            .into_iter()
+            .map(|c| (c.path, c.thought_count))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("philosophy".to_string(), 2),
+                ("philosophy.ethics".to_string(), 1),
+                ("science".to_string(), 1)
+            ],
+            counts
+        );
+        assert_eq!(2, store.get_thoughts_by_category("philosophy").await?.len());
+        assert_eq!(
+            vec![first_id],
+            store
+                .get_thoughts_by_category("philosophy.ethics")
+                .await?
+                .into_iter()
+                .map(|t| t.thought_id)
+                .collect::<Vec<Uuid>>()
+        );
+        assert_eq!(
+            1,
+            store
+                .get_thoughts_by_category_pattern("*.ethics")
+                .await?
+                .len()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_threads_with_cursor() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (first_id, second_id, node_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        store.set_clock(at(1));
+        store.insert_thought(thread(first_id, "first")).await?;
+        store.set_clock(at(2));
+        store.insert_thought(thread(second_id, "second")).await?;
+        store.set_clock(at(3));
+        store
+            .insert_thought(node(node_id, first_id, "node"))
+            .await?;
+
+        let by_creation = store.list_threads(ThreadOrder::CreatedAt, None, 1).await?;
+        assert_eq!(second_id, by_creation[0].thread.thought_id);
+
+        let cursor = by_creation[0].cursor(ThreadOrder::CreatedAt);
+        let next = store
+            .list_threads(ThreadOrder::CreatedAt, Some(&cursor), 10)
+            .await?;
+        assert_eq!(1, next.len());
+        assert_eq!(first_id, next[0].thread.thought_id);
+        assert_eq!(2, next[0].thought_count);
+
+        let by_activity = store
+            .list_threads(ThreadOrder::LastActivity, None, 10)
+            .await?;
+        assert_eq!(first_id, by_activity[0].thread.thought_id);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_thread_thoughts_with_cursor() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (root_id, first_id, second_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        store.set_clock(at(1));
+        store.insert_thought(thread(root_id, "root")).await?;
+        store.set_clock(at(2));
+        store
+            .insert_thought(node(first_id, root_id, "first"))
+            .await?;
+        let first_page = store.list_thread_thoughts(&root_id, None, 2).await?;
+        store.set_clock(at(3));
+        store
+            .insert_thought(node(second_id, root_id, "second"))
+            .await?;
+
+        let last = first_page.last().unwrap();
+        let cursor = ListingCursor::new(last.created_at, last.thought_id);
+        let second_page = store
+            .list_thread_thoughts(&root_id, Some(&cursor), 2)
+            .await?;
+
+        assert_eq!(
+            vec![root_id, first_id],
+            first_page
+                .iter()
+                .map(|t| t.thought_id)
+                .collect::<Vec<Uuid>>()
+        );
+        assert_eq!(
+            vec![second_id],
+            second_page
+                .iter()
+                .map(|t| t.thought_id)
+                .collect::<Vec<Uuid>>()
+        );
+        assert!(store
+            .list_thread_thoughts(&first_id, None, 2)
+            .await?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_restore_and_purge() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (root_id, node_id) = (Uuid::new_v4(), Uuid::new_v4());
+        store.insert_thought(thread(root_id, "root")).await?;
+        store.insert_thought(node(node_id, root_id, "node")).await?;
+
+        assert!(store.delete_thought(&root_id, false).await?.is_empty());
+        assert_eq!(2, store.delete_thought(&root_id, true).await?.len());
+        assert_eq!(None, store.get_thought(&node_id).await?);
+        assert_eq!(None, store.get_thread(&node_id).await?);
+        assert_eq!(2, store.list_trash().await?.len());
+        store
+            .insert_thought(thread(root_id, "root"))
+            .await
+            .unwrap_err();
+
+        let mut restored = store.restore_thought(&root_id).await?;
+        restored.sort();
+        let mut expected = vec![root_id, node_id];
+        expected.sort();
+
+        assert_eq!(expected, restored);
+        assert_eq!(2, store.get_thread(&node_id).await?.unwrap().len());
+
+        store.delete_thought(&node_id, false).await?;
+        assert!(store.purge_trash(&DateTime::UNIX_EPOCH).await?.is_empty());
+        assert_eq!(vec![node_id], store.purge_trash(&Utc::now()).await?);
+        assert_eq!(None, store.get_trashed_thought(&node_id).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_records_revisions() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let thought_id = Uuid::new_v4();
+        let inserted = store.insert_thought(thread(thought_id, "first")).await?;
+        store.update_thought(thread(thought_id, "first")).await?;
+        store.update_thought(thread(thought_id, "second")).await?;
+        store.update_thought(thread(thought_id, "third")).await?;
+
+        let revisions = store.get_revisions(&thought_id).await?;
+
+        assert_eq!(2, revisions.len());
+        assert_eq!(
+            (1, "first"),
+            (revisions[0].revision, revisions[0].text.as_str())
+        );
+        assert_eq!(inserted.created_at, revisions[0].written_at);
+        assert_eq!(revisions[0].superseded_at, Some(revisions[1].written_at));
+        assert_eq!(
+            (2, "second"),
+            (revisions[1].revision, revisions[1].text.as_str())
+        );
+
+        let mut markdown = thread(thought_id, "third");
+        markdown.content_format = ContentFormat::Markdown;
+        store.update_thought(markdown).await?;
+        let revisions = store.get_revisions(&thought_id).await?;
+
+        assert_eq!(3, revisions.len());
+        assert_eq!(ContentFormat::Plain, revisions[2].content_format);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_sources_updates_citing_thoughts() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let source = |name: &str| ThoughtSource {
+            source_id: Uuid::new_v4(),
+            name: name.to_string(),
+            authors: Vec::new(),
+            description: String::new(),
+            author_ids: Vec::new(),
+        };
+        let kept = store.insert_source(source("Meditations")).await?;
+        let absorbed = store.insert_source(source("Meditation")).await?;
+        assert_eq!(kept, store.insert_source(source("meditations.")).await?);
+        let (first_id, second_id) = (Uuid::new_v4(), Uuid::new_v4());
+        store
+            .insert_thought(Thought {
+                source_ids: vec![absorbed.source_id, kept.source_id],
+                ..thread(first_id, "first")
+            })
+            .await?;
+        store.insert_thought(thread(second_id, "second")).await?;
+
+        assert_eq!(2, store.get_sources().await?.len());
+        assert_eq!(
+            vec![first_id],
+            store
+                .merge_sources(&kept.source_id, &absorbed.source_id)
+                .await?
+        );
+        assert_eq!(
+            vec![kept.source_id],
+            store.get_thought(&first_id).await?.unwrap().source_ids
+        );
+        assert_eq!(
+            vec![kept.source_id],
+            store.get_revisions(&first_id).await?[0].source_ids
+        );
+        assert_eq!(None, store.get_source(&absorbed.source_id).await?);
+        assert_eq!(
+            1,
+            store.get_thoughts_by_source(&kept.source_id).await?.len()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn author_aliases_merge_authors() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let author = |name: &str| Author {
+            author_id: Uuid::new_v4(),
+            name: name.to_string(),
+            aliases: Vec::new(),
+        };
+        let marcus = store.insert_author(author("Marcus Aurelius")).await?;
+        let marc = store.insert_author(author("Marc Aurèle")).await?;
+        assert_eq!(
+            marcus.author_id,
+            store
+                .insert_author(author("marcus aurelius"))
+                .await?
+                .author_id
+        );
+        assert_eq!(2, store.get_authors().await?.len());
+        let source = store
+            .insert_source(ThoughtSource {
+                source_id: Uuid::new_v4(),
+                name: "Pensées pour moi-même".to_string(),
+                authors: vec!["Marc Aurèle".to_string()],
+                description: String::new(),
+                author_ids: vec![marc.author_id],
+            })
+            .await?;
+        let thought_id = Uuid::new_v4();
+        store
+            .insert_thought(Thought {
+                source_ids: vec![source.source_id],
+                ..thread(thought_id, "title")
+            })
+            .await?;
+
+        store
+            .add_author_alias(&marcus.author_id, "marc aurele")
+            .await?;
+        assert_eq!(2, store.get_authors().await?.len());
+        store
+            .add_author_alias(&marcus.author_id, "Marc Aurèle")
+            .await?;
+
+        assert_eq!(None, store.get_author(&marc.author_id).await?);
+        assert_eq!(
+            Some(marcus.author_id),
+            store.find_author("MARC aurèle").await?.map(|a| a.author_id)
+        );
+        assert_eq!(
+            3,
+            store
+                .get_author(&marcus.author_id)
+                .await?
+                .unwrap()
+                .aliases
+                .len()
+        );
+        assert_eq!(
+            vec![marcus.author_id],
+            store
+                .get_source(&source.source_id)
+                .await?
+                .unwrap()
+                .author_ids
+        );
+        assert_eq!(
+            vec![thought_id],
+            store
+                .get_thoughts_by_author(&marcus.author_id)
+                .await?
+                .into_iter()
+                .map(|t| t.thought_id)
+                .collect::<Vec<Uuid>>()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn links_follow_their_thoughts() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let (first_id, second_id) = (Uuid::new_v4(), Uuid::new_v4());
+        store.insert_thought(thread(first_id, "first")).await?;
+        store.insert_thought(thread(second_id, "second")).await?;
+        let link = ThoughtLink {
+            link_id: Uuid::new_v4(),
+            from_thought_id: first_id,
+            to_thought_id: second_id,
+            kind: LinkKind::Contradicts,
+            created_at: DateTime::UNIX_EPOCH,
+        };
+        let inserted = store.insert_link(link.clone()).await?;
+
+        store
+            .insert_link(ThoughtLink {
+                link_id: Uuid::new_v4(),
+                ..link.clone()
+            })
+            .await
+            .unwrap_err();
+        store
+            .insert_link(ThoughtLink {
+                link_id: Uuid::new_v4(),
+                to_thought_id: first_id,
+                ..link.clone()
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(
+            vec![inserted.clone()],
+            store.get_outgoing_links(&first_id).await?
+        );
+        assert_eq!(
+            vec![inserted.clone()],
+            store.get_backlinks(&second_id).await?
+        );
+        assert!(store.get_backlinks(&first_id).await?.is_empty());
+
+        store.delete_thought(&second_id, false).await?;
+        assert!(store.get_outgoing_links(&first_id).await?.is_empty());
+        assert_eq!(None, store.get_link(&link.link_id).await?);
+
+        store.restore_thought(&second_id).await?;
+        assert_eq!(Some(inserted), store.get_link(&link.link_id).await?);
+
+        store.set_clock(at(1));
+        store.delete_thought(&second_id, false).await?;
+        store.purge_trash(&at(2).unwrap()).await?;
+        assert!(!store.delete_link(&link.link_id).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn attachments_follow_their_thoughts() -> StdResult<()> {
+        let store = MemoryThoughtStore::default();
+        let thought_id = Uuid::new_v4();
+        let attachment = Attachment {
+            attachment_id: Uuid::new_v4(),
+            thought_id,
+            file_name: "memo.ogg".to_string(),
+            mime_type: "audio/ogg".to_string(),
+            size: 12,
+            hash: "0".repeat(64),
+            created_at: DateTime::UNIX_EPOCH,
+        };
+
+        store
+            .insert_attachment(attachment.clone())
+            .await
+            .unwrap_err();
+        store.insert_thought(thread(thought_id, "notes")).await?;
+        let inserted = store.insert_attachment(attachment.clone()).await?;
+        store
+            .insert_attachment(Attachment {
+                attachment_id: Uuid::new_v4(),
+                ..attachment.clone()
+            })
+            .await?;
+
+        assert_eq!(2, store.get_attachments(&thought_id).await?.len());
+        assert_eq!(vec!["0".repeat(64)], store.get_attachment_hashes().await?);
+        assert!(store.delete_attachment(&attachment.attachment_id).await?);
+        assert!(!store.delete_attachment(&attachment.attachment_id).await?);
+        assert_eq!(None, store.get_attachment(&inserted.attachment_id).await?);
+
+        store.set_clock(at(1));
+        store.delete_thought(&thought_id, false).await?;
+        assert_eq!(1, store.get_attachment_hashes().await?.len());
+        store.purge_trash(&at(2).unwrap()).await?;
+        assert!(store.get_attachment_hashes().await?.is_empty());
+
+        Ok(())
+    }
+}
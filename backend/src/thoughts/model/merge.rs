@@ -0,0 +1,64 @@
+use uuid::Uuid;
+
+/// How a thread is merged into another one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThreadMergeMode {
+    /// The absorbed root becomes a node under a thought of the other thread, its subtree moves
+    /// with it.
+    #[default]
+    Graft,
+    /// The absorbed root is folded into the other root: its keywords, categories and sources
+    /// are added to the kept root, its children are moved under it and the links and attachments
+    /// pointing to it are redirected to it. The absorbed root is then deleted for good, it
+    /// cannot be restored from the trash.
+    Combine,
+}
+
+impl ThreadMergeMode {
+    /// Parse the name of a mode, `graft` or `combine`. None is returned if it is unknown.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "graft" => Some(Self::Graft),
+            "combine" => Some(Self::Combine),
+            _ => None,
+        }
+    }
+}
+
+/// Changes made by a thread merge.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThreadMerge {
+    /// Live thoughts whose content or thread changed.
+    pub updated_thought_ids: Vec<Uuid>,
+    /// Root deleted for good when the roots are combined, it cannot be restored.
+    pub deleted_thought_id: Option<Uuid>,
+    /// Links now pointing to or from the kept root.
+    pub redirected_link_ids: Vec<Uuid>,
+    /// Links removed because they would link the kept root to itself or duplicate one of its
+    /// links.
+    pub removed_link_ids: Vec<Uuid>,
+    /// Attachments of the absorbed root now attached to the kept root.
+    pub moved_attachment_ids: Vec<Uuid>,
+}
+
+/// Append the items of `other` missing from `items`, keeping the order of both.
+pub fn merge_items<T: Clone + PartialEq>(items: &mut Vec<T>, other: &[T]) {
+    for item in other {
+        if !items.contains(item) {
+            items.push(item.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_are_merged_in_order() {
+        let mut keywords = vec!["stoicism", "ethics"];
+        merge_items(&mut keywords, &["virtue", "ethics", "virtue"]);
+
+        assert_eq!(vec!["stoicism", "ethics", "virtue"], keywords);
+    }
+}
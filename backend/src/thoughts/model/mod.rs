@@ -1,6 +1,32 @@
 pub mod agrum;
+mod attachment;
+mod author;
+mod blob;
+mod category;
+mod format;
+mod link;
+mod listing;
+mod memory;
+mod merge;
+mod revision;
+mod source;
 mod store;
 mod thought;
+mod trash;
+mod tree;
 
+pub use attachment::*;
+pub use author::*;
+pub use blob::*;
+pub use category::*;
+pub use format::*;
+pub use link::*;
+pub use listing::*;
+pub use memory::*;
+pub use merge::*;
+pub use revision::*;
+pub use source::*;
 pub use store::*;
 pub use thought::*;
+pub use trash::*;
+pub use tree::*;
@@ -0,0 +1,228 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{
+    agrum::ThoughtRevisionEntity, get_text_content, ContentFormat, ThoughtConversionError,
+    ThoughtEnvelope,
+};
+
+/// Version of the editable parts of a thought. Revisions are numbered from 1, the most recent
+/// one being the current state of the thought.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThoughtRevision {
+    pub thought_id: Uuid,
+    pub revision: u32,
+    /// Content of a node or title of a thread.
+    pub text: String,
+    /// Format the text was written in.
+    pub content_format: ContentFormat,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub source_ids: Vec<Uuid>,
+    /// When this version was written.
+    pub written_at: DateTime<Utc>,
+    /// When this version was replaced by the next one, None for the current version.
+    pub superseded_at: Option<DateTime<Utc>>,
+}
+
+impl ThoughtRevision {
+    /// Revision describing the current state of the thought, following the given prior
+    /// revisions.
+    pub fn current(thought: &ThoughtEnvelope, previous: &[ThoughtRevision]) -> Self {
+        let last = previous.last();
+
+        Self {
+            thought_id: thought.thought_id,
+            revision: last.map(|r| r.revision + 1).unwrap_or(1),
+            text: thought.text().to_string(),
+            content_format: thought.content_format,
+            keywords: thought.keywords.clone(),
+            categories: thought.categories.clone(),
+            source_ids: thought.source_ids.clone(),
+            written_at: last
+                .and_then(|r| r.superseded_at)
+                .unwrap_or(thought.created_at),
+            superseded_at: None,
+        }
+    }
+}
+
+impl TryFrom<ThoughtRevisionEntity> for ThoughtRevision {
+    type Error = ThoughtConversionError;
+
+    fn try_from(value: ThoughtRevisionEntity) -> Result<Self, Self::Error> {
+        let content_format = ContentFormat::parse(&value.content_format).ok_or_else(|| {
+            ThoughtConversionError::new(
+                value.thought_id,
+                "content_format",
+                format!("unknown content format '{}'", value.content_format),
+            )
+        })?;
+
+        Ok(Self {
+            thought_id: value.thought_id,
+            revision: value.revision.max(0) as u32,
+            text: get_text_content(value.thought_id, "content", value.content)?,
+            content_format,
+            keywords: value.keywords,
+            categories: value.categories,
+            source_ids: value.source_ids,
+            written_at: value.written_at,
+            superseded_at: Some(value.superseded_at),
+        })
+    }
+}
+
+/// How a piece of text changed between two revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffChange {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+/// Consecutive words sharing the same change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChunk {
+    pub change: DiffChange,
+    pub text: String,
+}
+
+/// Differences between two revisions of a thought.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RevisionDiff {
+    pub thought_id: Uuid,
+    pub from_revision: u32,
+    pub to_revision: u32,
+    /// Word by word difference of the texts.
+    pub text: Vec<TextChunk>,
+    pub added_keywords: Vec<String>,
+    pub removed_keywords: Vec<String>,
+    pub added_categories: Vec<String>,
+    pub removed_categories: Vec<String>,
+    pub added_source_ids: Vec<Uuid>,
+    pub removed_source_ids: Vec<Uuid>,
+}
+
+impl RevisionDiff {
+    pub fn new(from: &ThoughtRevision, to: &ThoughtRevision) -> Self {
+        Self {
+            thought_id: to.thought_id,
+            from_revision: from.revision,
+            to_revision: to.revision,
+            text: diff_words(&from.text, &to.text),
+            added_keywords: missing_from(&to.keywords, &from.keywords),
+            removed_keywords: missing_from(&from.keywords, &to.keywords),
+            added_categories: missing_from(&to.categories, &from.categories),
+            removed_categories: missing_from(&from.categories, &to.categories),
+            added_source_ids: missing_from(&to.source_ids, &from.source_ids),
+            removed_source_ids: missing_from(&from.source_ids, &to.source_ids),
+        }
+    }
+}
+
+/// Items of `items` not present in `other`, in their original order.
+fn missing_from<T: Clone + PartialEq>(items: &[T], other: &[T]) -> Vec<T> {
+    items
+        .iter()
+        .filter(|item| !other.contains(item))
+        .cloned()
+        .collect()
+}
+
+/// Word by word difference of two texts, based on their longest common subsequence.
+fn diff_words(from: &str, to: &str) -> Vec<TextChunk> {
+    let from: Vec<&str> = from.split_whitespace().collect();
+    let to: Vec<&str> = to.split_whitespace().collect();
+
+    // common[i][j] is the length of the longest common subsequence of from[i..] and to[j..]
+    let mut common = vec![vec![0_usize; to.len() + 1]; from.len() + 1];
+    for i in (0..from.len()).rev() {
+        for j in (0..to.len()).rev() {
+            common[i][j] = if from[i] == to[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut chunks: Vec<TextChunk> = Vec::new();
+    let mut push = |change: DiffChange, word: &str| match chunks.last_mut() {
+        Some(chunk) if chunk.change == change => {
+            chunk.text.push(' ');
+            chunk.text.push_str(word);
+        }
+        _ => chunks.push(TextChunk {
+            change,
+            text: word.to_string(),
+        }),
+    };
+    let (mut i, mut j) = (0, 0);
+
+    while i < from.len() || j < to.len() {
+        if i < from.len() && j < to.len() && from[i] == to[j] {
+            push(DiffChange::Unchanged, from[i]);
+            i += 1;
+            j += 1;
+        } else if j < to.len() && (i == from.len() || common[i][j + 1] > common[i + 1][j]) {
+            push(DiffChange::Added, to[j]);
+            j += 1;
+        } else {
+            push(DiffChange::Removed, from[i]);
+            i += 1;
+        }
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(change: DiffChange, text: &str) -> TextChunk {
+        TextChunk {
+            change,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn word_diff() {
+        assert_eq!(
+            vec![
+                chunk(DiffChange::Unchanged, "the"),
+                chunk(DiffChange::Added, "very"),
+                chunk(DiffChange::Unchanged, "quick fox"),
+                chunk(DiffChange::Removed, "jumps over"),
+                chunk(DiffChange::Added, "sleeps"),
+            ],
+            diff_words("the quick fox jumps over", "the very quick fox sleeps")
+        );
+        assert!(diff_words("", "").is_empty());
+    }
+
+    #[test]
+    fn revision_diff() {
+        let revision = |revision: u32, text: &str, keywords: &[&str]| ThoughtRevision {
+            thought_id: Uuid::nil(),
+            revision,
+            text: text.to_string(),
+            content_format: ContentFormat::Plain,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            categories: Vec::new(),
+            source_ids: Vec::new(),
+            written_at: DateTime::UNIX_EPOCH,
+            superseded_at: None,
+        };
+        let diff = RevisionDiff::new(
+            &revision(1, "stoic", &["ethics", "logic"]),
+            &revision(2, "stoic", &["logic", "physics"]),
+        );
+
+        assert_eq!(vec!["physics".to_string()], diff.added_keywords);
+        assert_eq!(vec!["ethics".to_string()], diff.removed_keywords);
+        assert_eq!(vec![chunk(DiffChange::Unchanged, "stoic")], diff.text);
+    }
+}
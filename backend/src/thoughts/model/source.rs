@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use uuid::Uuid;
+
+use super::agrum::SourceEntity;
+
+/// Work cited by thoughts. Sources are registered once and referenced by their identifier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThoughtSource {
+    pub source_id: Uuid,
+    pub name: String,
+    /// Authors as cited by the source.
+    pub authors: Vec<String>,
+    pub description: String,
+    /// Identifiers of the indexed authors of the source, in the order they are cited.
+    pub author_ids: Vec<Uuid>,
+}
+
+impl ThoughtSource {
+    /// Key shared by the sources considered as duplicates: the name and the authors, ignoring
+    /// case, punctuation, spacing and the order of the authors.
+    pub fn duplicate_key(&self) -> String {
+        let mut authors: Vec<String> = self.authors.iter().map(|a| normalize_name(a)).collect();
+        authors.sort();
+
+        format!("{}|{}", normalize_name(&self.name), authors.join("|"))
+    }
+
+    /// Group the sources sharing the same duplicate key, only groups of at least two sources
+    /// are returned. Groups are sorted by key, sources by name then identifier.
+    pub fn group_duplicates(sources: Vec<ThoughtSource>) -> Vec<Vec<ThoughtSource>> {
+        let mut groups: BTreeMap<String, Vec<ThoughtSource>> = BTreeMap::new();
+
+        for source in sources {
+            groups
+                .entry(source.duplicate_key())
+                .or_default()
+                .push(source);
+        }
+
+        groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort_by(|a, b| a.name.cmp(&b.name).then(a.source_id.cmp(&b.source_id)));
+                group
+            })
+            .collect()
+    }
+}
+
+impl From<SourceEntity> for ThoughtSource {
+    fn from(value: SourceEntity) -> Self {
+        Self {
+            source_id: value.source_id,
+            name: value.name,
+            authors: value.authors,
+            description: value.description,
+            author_ids: value.author_ids,
+        }
+    }
+}
+
+impl From<ThoughtSource> for SourceEntity {
+    fn from(value: ThoughtSource) -> Self {
+        Self {
+            source_id: value.source_id,
+            name: value.name,
+            authors: value.authors,
+            description: value.description,
+            author_ids: value.author_ids,
+        }
+    }
+}
+
+/// Replace the references to an absorbed source or author by references to the kept one, only
+/// the first reference to each is kept. Return true if the references changed.
+pub fn replace_reference(references: &mut Vec<Uuid>, kept_id: &Uuid, absorbed_id: &Uuid) -> bool {
+    if !references.contains(absorbed_id) {
+        return false;
+    }
+    let mut replaced: Vec<Uuid> = Vec::with_capacity(references.len());
+
+    for reference in references.iter() {
+        let reference = if reference == absorbed_id {
+            kept_id
+        } else {
+            reference
+        };
+
+        if !replaced.contains(reference) {
+            replaced.push(*reference);
+        }
+    }
+    *references = replaced;
+
+    true
+}
+
+/// Lowercase words separated by a single space. Words are split on the ASCII characters other
+/// than letters and digits and only the ASCII letters are lowercased, so the database function
+/// `thought.name_key` computes the same keys whatever its locale.
+pub fn normalize_name(text: &str) -> String {
+    text.split(|c: char| c.is_ascii() && !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(name: &str, authors: &[&str]) -> ThoughtSource {
+        ThoughtSource {
+            source_id: Uuid::new_v4(),
+            name: name.to_string(),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            description: String::new(),
+            author_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn names_are_normalized() {
+        assert_eq!("marc aurèle", normalize_name("  Marc-Aurèle."));
+        assert_eq!(
+            "pensÉes pour moi même",
+            normalize_name("PENSÉES pour moi-même")
+        );
+        assert_eq!("", normalize_name(" - "));
+    }
+
+    #[test]
+    fn duplicates_are_grouped() {
+        let groups = ThoughtSource::group_duplicates(vec![
+            source("Meditations", &["Marcus Aurelius"]),
+            source("Letters from a Stoic", &["Seneca"]),
+            source("meditations.", &["marcus  aurelius"]),
+            source("Meditations", &["Epictetus"]),
+        ]);
+
+        assert_eq!(1, groups.len());
+        assert_eq!(
+            vec!["Meditations", "meditations."],
+            groups[0]
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    fn references_are_replaced() {
+        let (first, second, third) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut source_ids = vec![first, second, third];
+
+        assert!(!replace_reference(&mut source_ids, &first, &Uuid::new_v4()));
+        assert!(replace_reference(&mut source_ids, &first, &third));
+        assert_eq!(vec![first, second], source_ids);
+        assert!(replace_reference(&mut source_ids, &third, &first));
+        assert_eq!(vec![third, second], source_ids);
+    }
+}
@@ -1,48 +1,1055 @@
-use std::{borrow::Borrow, sync::Arc};
-
-use agrum::core::Provider;
+use agrum::core::HydrationError;
+use anyhow::anyhow;
 use async_trait::async_trait;
-use tokio_postgres::Client;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Object, Pool};
+use log::warn;
+use thiserror::Error;
+use tokio_postgres::error::SqlState;
 use uuid::Uuid;
 
-use crate::StdResult;
+use crate::StdError;
 
 use super::{
-    agrum::{ThoughtEntityRepository, ThoughtEntitySqlDefinition},
-    ThoughtEnvelope as Thought,
+    agrum::{
+        AttachmentRepository, AuthorRepository, CategoryRepository, Hydrated, LinkRepository,
+        ListingRepository, SourceRepository, ThoughtEntityRepository, ThoughtRevisionRepository,
+        TrashRepository,
+    },
+    Attachment, Author, CategoryCount, ListingCursor, ThoughtConversionError,
+    ThoughtEnvelope as Thought, ThoughtLink, ThoughtRevision, ThoughtSearchResult, ThoughtSource,
+    ThreadMerge, ThreadOrder, ThreadSummary, TrashedThought,
 };
 
+/// Errors of the ThoughtStore, they tell the callers why an operation failed.
+#[derive(Debug, Error)]
+pub enum ThoughtStoreError {
+    /// The storage could not be reached or failed to run the operation.
+    #[error("Thought store backend error: {0}")]
+    Backend(StdError),
+
+    /// The stored data cannot be turned into model values, it is malformed or inconsistent.
+    #[error("Invalid data in the thought store: {0}")]
+    Hydration(String),
+
+    /// The operation would break a constraint of the store, like a duplicated identifier or a
+    /// move creating a cycle in a thread.
+    #[error("Thought store conflict: {0}")]
+    Conflict(String),
+
+    /// The given data breaks a rule of the store, like a check constraint of the database.
+    #[error("Data rejected by the thought store: {0}")]
+    Rejected(String),
+
+    /// An entity the operation relies on does not exist.
+    #[error("Not found in the thought store: {0}")]
+    NotFound(String),
+
+    /// The parents of a stored thought form a cycle, its thread has no root.
+    #[error("Cycle in a thread of the thought store: {0}")]
+    Cycle(String),
+}
+
+pub type ThoughtStoreResult<T> = Result<T, ThoughtStoreError>;
+
+impl From<StdError> for ThoughtStoreError {
+    /// Sort the errors of the repositories. The ones raised on purpose are kept, the database
+    /// constraint violations are conflicts, rejected data or missing references and the others
+    /// are backend errors.
+    fn from(error: StdError) -> Self {
+        let error = match error.downcast::<ThoughtStoreError>() {
+            Ok(e) => return e,
+            Err(error) => error,
+        };
+
+        if error.downcast_ref::<HydrationError>().is_some() {
+            return Self::Hydration(format!("{error:#}"));
+        }
+        let state = error
+            .downcast_ref::<tokio_postgres::Error>()
+            .and_then(|e| e.code());
+
+        match state {
+            Some(state)
+                if *state == SqlState::UNIQUE_VIOLATION
+                    || *state == SqlState::EXCLUSION_VIOLATION =>
+            {
+                Self::Conflict(error.to_string())
+            }
+            Some(state) if *state == SqlState::CHECK_VIOLATION => Self::Rejected(error.to_string()),
+            Some(state) if *state == SqlState::FOREIGN_KEY_VIOLATION => {
+                Self::NotFound(error.to_string())
+            }
+            _ => Self::Backend(error),
+        }
+    }
+}
+
+impl From<tokio_postgres::Error> for ThoughtStoreError {
+    fn from(error: tokio_postgres::Error) -> Self {
+        anyhow!(error).into()
+    }
+}
+
+impl From<ThoughtConversionError> for ThoughtStoreError {
+    fn from(error: ThoughtConversionError) -> Self {
+        Self::Hydration(error.to_string())
+    }
+}
+
 /// The ThoughtStore is responsible of offering a generic API to persist and retreive thought
 /// entities. It also configures the way the thoughts are being fetch and the kind of thought
 /// entities returned by the different queries. The `SqlEntity` instances shall not being exposed
-/// outside the store.
+/// outside the store. Soft deleted thoughts are only visible through the trash methods.
 #[async_trait]
 pub trait ThoughtStore: Sync + Send {
-    async fn get_thought(&self, thought_id: &Uuid) -> StdResult<Option<Thought>>;
+    async fn get_thought(&self, thought_id: &Uuid) -> ThoughtStoreResult<Option<Thought>>;
+
+    /// Fetch the thread the given thought belongs to, from the root `Thread` down to the given
+    /// thought. If the thought does not exist, None is returned. A `ThoughtStoreError::Cycle` is
+    /// returned if the chain of parents contains a cycle.
+    async fn get_thread(&self, thought_id: &Uuid) -> ThoughtStoreResult<Option<Vec<Thought>>>;
+
+    /// Fetch the given thought and its descendants down to `max_depth` levels below it, or to
+    /// the leaves when no depth is given. The given thought comes first, then its descendants
+    /// level by level. If the thought does not exist, None is returned.
+    async fn get_subtree(
+        &self,
+        thought_id: &Uuid,
+        max_depth: Option<u32>,
+    ) -> ThoughtStoreResult<Option<Vec<Thought>>>;
+
+    /// Full text search over the thoughts content and thread titles, best matches first.
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<ThoughtSearchResult>>;
+
+    /// Every node of the category tree with the number of thoughts filed under it or one of its
+    /// descendants, sorted by path.
+    async fn get_category_counts(&self) -> ThoughtStoreResult<Vec<CategoryCount>>;
+
+    /// Thoughts filed under the given category path or one of its descendants, oldest first.
+    async fn get_thoughts_by_category(&self, path: &str) -> ThoughtStoreResult<Vec<Thought>>;
+
+    /// Thoughts having a category matching the given `lquery` pattern, oldest first.
+    async fn get_thoughts_by_category_pattern(
+        &self,
+        pattern: &str,
+    ) -> ThoughtStoreResult<Vec<Thought>>;
+
+    /// At most `limit` thread roots, most recent first according to the given order, starting
+    /// right after the given cursor if any.
+    async fn list_threads(
+        &self,
+        order: ThreadOrder,
+        after: Option<&ListingCursor>,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<ThreadSummary>>;
+
+    /// At most `limit` thoughts of the thread whose root is given, oldest first, starting right
+    /// after the given cursor if any. Nothing is returned if the thought is not a thread root.
+    async fn list_thread_thoughts(
+        &self,
+        thread_id: &Uuid,
+        after: Option<&ListingCursor>,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<Thought>>;
+
+    /// Fetch a soft deleted thought, None is returned if it is not in the trash.
+    async fn get_trashed_thought(
+        &self,
+        thought_id: &Uuid,
+    ) -> ThoughtStoreResult<Option<TrashedThought>>;
+
+    /// All the soft deleted thoughts, most recently deleted first.
+    async fn list_trash(&self) -> ThoughtStoreResult<Vec<TrashedThought>>;
+
+    /// Soft delete a thought. When `recursive` is false, nothing is deleted if the thought has
+    /// children. Otherwise, all its descendants are deleted with it and share its deletion date.
+    /// The identifiers of the deleted thoughts are returned.
+    async fn delete_thought(
+        &self,
+        thought_id: &Uuid,
+        recursive: bool,
+    ) -> ThoughtStoreResult<Vec<Uuid>>;
+
+    /// Restore a soft deleted thought with the descendants deleted at the same time. The
+    /// identifiers of the restored thoughts are returned.
+    async fn restore_thought(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<Uuid>>;
+
+    /// Permanently remove the thoughts deleted before the given date. The identifiers of the
+    /// removed thoughts are returned.
+    async fn purge_trash(&self, before: &DateTime<Utc>) -> ThoughtStoreResult<Vec<Uuid>>;
+
+    /// Prior versions of the given thought, oldest first. The current version is not included.
+    async fn get_revisions(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtRevision>>;
+
+    async fn get_source(&self, source_id: &Uuid) -> ThoughtStoreResult<Option<ThoughtSource>>;
+
+    /// All the registered sources, sorted by name.
+    async fn get_sources(&self) -> ThoughtStoreResult<Vec<ThoughtSource>>;
+
+    /// Register a new source. If a registered source has the same duplicate key, it is returned
+    /// instead and nothing is created.
+    async fn insert_source(&self, source: ThoughtSource) -> ThoughtStoreResult<ThoughtSource>;
+
+    /// Thoughts citing the given source, oldest first.
+    async fn get_thoughts_by_source(&self, source_id: &Uuid) -> ThoughtStoreResult<Vec<Thought>>;
+
+    /// Make every thought citing the absorbed source cite the kept one instead, their revisions
+    /// included, then remove the absorbed source. This is done atomically. The identifiers of
+    /// the live thoughts whose sources changed are returned.
+    async fn merge_sources(
+        &self,
+        kept_id: &Uuid,
+        absorbed_id: &Uuid,
+    ) -> ThoughtStoreResult<Vec<Uuid>>;
+
+    async fn get_author(&self, author_id: &Uuid) -> ThoughtStoreResult<Option<Author>>;
+
+    /// All the indexed authors, sorted by name.
+    async fn get_authors(&self) -> ThoughtStoreResult<Vec<Author>>;
+
+    /// Fetch the author known by the given name, ignoring case, punctuation and spacing.
+    async fn find_author(&self, alias: &str) -> ThoughtStoreResult<Option<Author>>;
+
+    /// Index a new author, it is known by its name. Its other aliases are ignored. If an author
+    /// is already known by this name, it is returned instead and nothing is created.
+    async fn insert_author(&self, author: Author) -> ThoughtStoreResult<Author>;
+
+    /// Make the given author known by the alias. If another author is known by this alias, it
+    /// is merged into the given one: its sources and aliases are moved over and it is removed.
+    /// This is done atomically.
+    async fn add_author_alias(&self, author_id: &Uuid, alias: &str) -> ThoughtStoreResult<()>;
+
+    /// Sources linked to the given author, sorted by name.
+    async fn get_sources_by_author(
+        &self,
+        author_id: &Uuid,
+    ) -> ThoughtStoreResult<Vec<ThoughtSource>>;
+
+    /// Thoughts citing one of the sources of the given author, oldest first.
+    async fn get_thoughts_by_author(&self, author_id: &Uuid) -> ThoughtStoreResult<Vec<Thought>>;
+
+    /// Fetch a link, None is returned if one of its thoughts is deleted.
+    async fn get_link(&self, link_id: &Uuid) -> ThoughtStoreResult<Option<ThoughtLink>>;
+
+    /// Links from the given thought to live thoughts, oldest first.
+    async fn get_outgoing_links(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtLink>>;
+
+    /// Links from live thoughts to the given thought, oldest first.
+    async fn get_backlinks(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtLink>>;
+
+    /// Persist a new link. The creation date is set by the store, the given one is ignored.
+    /// Links are removed along with their thoughts when those are purged.
+    async fn insert_link(&self, link: ThoughtLink) -> ThoughtStoreResult<ThoughtLink>;
+
+    /// Remove a link, return false if it does not exist.
+    async fn delete_link(&self, link_id: &Uuid) -> ThoughtStoreResult<bool>;
+
+    /// Fetch an attachment whatever the state of its thought.
+    async fn get_attachment(&self, attachment_id: &Uuid) -> ThoughtStoreResult<Option<Attachment>>;
+
+    /// Attachments of the given thought, oldest first.
+    async fn get_attachments(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<Attachment>>;
+
+    /// Persist a new attachment. The creation date is set by the store, the given one is
+    /// ignored. Attachments are removed along with their thought when it is purged.
+    async fn insert_attachment(&self, attachment: Attachment) -> ThoughtStoreResult<Attachment>;
+
+    /// Remove an attachment, return false if it does not exist. Its content is left in the blob
+    /// store.
+    async fn delete_attachment(&self, attachment_id: &Uuid) -> ThoughtStoreResult<bool>;
+
+    /// Hashes of the contents referenced by at least one attachment, the attachments of the
+    /// thoughts in the trash included.
+    async fn get_attachment_hashes(&self) -> ThoughtStoreResult<Vec<String>>;
+
+    /// Persist a new thought. The creation date is set by the store, the given one is ignored.
+    async fn insert_thought(&self, thought: Thought) -> ThoughtStoreResult<Thought>;
+
+    /// Update an existing thought, its creation date is left untouched. If its text, keywords,
+    /// categories or sources change, the replaced version is recorded as a revision. The parent
+    /// is not updated, thoughts are moved with `move_thought`. If the thought does not exist, is
+    /// deleted or does not have the given parent, None is returned.
+    async fn update_thought(&self, thought: Thought) -> ThoughtStoreResult<Option<Thought>>;
+
+    /// Move a thought and its descendants under a new parent, or make it the root of a new
+    /// `Thread` when no parent is given. This is done atomically. An error is returned if the
+    /// new parent does not exist or is the thought itself or one of its descendants. The
+    /// identifiers of the moved thought and its descendants are returned, the moved thought
+    /// first. If the thought does not exist or is deleted, None is returned.
+    async fn move_thought(
+        &self,
+        thought_id: &Uuid,
+        parent_thought_id: Option<&Uuid>,
+    ) -> ThoughtStoreResult<Option<Vec<Uuid>>>;
+
+    /// Fold the absorbed thread root into the kept one: the kept root gets the keywords,
+    /// categories and sources it lacks, the children of the absorbed root are moved under the
+    /// kept root, the links and attachments of the absorbed root are redirected to the kept root
+    /// and the absorbed root is deleted for good, with its revisions, so it cannot be restored
+    /// apart from its former children. This is done atomically. If one of the thoughts is not a
+    /// live thread root, None is returned.
+    async fn combine_threads(
+        &self,
+        kept_id: &Uuid,
+        absorbed_id: &Uuid,
+    ) -> ThoughtStoreResult<Option<ThreadMerge>>;
 }
 
 pub struct AgrumThoughtStore {
-    client: Arc<Client>,
+    pool: Pool,
+    search_language: String,
+    skip_corrupt_rows: bool,
 }
 
 impl AgrumThoughtStore {
-    /// Constructor
-    pub fn new(client: Arc<Client>) -> Self {
-        Self { client }
+    /// Constructor, the search language is the PostgreSQL text search configuration used to
+    /// index and search the thoughts. When `skip_corrupt_rows` is true, the corrupt rows of a
+    /// listing are logged and left out instead of failing the read.
+    pub fn new(pool: Pool, search_language: String, skip_corrupt_rows: bool) -> Self {
+        Self {
+            pool,
+            search_language,
+            skip_corrupt_rows,
+        }
+    }
+
+    /// Convert the rows of a listing to the model, see [`convert_rows`].
+    fn convert_all<E, T>(&self, rows: Vec<Hydrated<E>>) -> ThoughtStoreResult<Vec<T>>
+    where
+        T: TryFrom<E, Error = ThoughtConversionError>,
+    {
+        convert_rows(rows, self.skip_corrupt_rows)
+    }
+
+    /// Check out a database connection from the pool.
+    async fn get_client(&self) -> ThoughtStoreResult<Object> {
+        self.pool.get().await.map_err(|e| {
+            ThoughtStoreError::Backend(
+                anyhow!(e).context("Could not get a database connection from the pool."),
+            )
+        })
+    }
+}
+
+/// Convert the rows of a listing to the model. A row is corrupt when it cannot be hydrated or
+/// converted, the first one fails the conversion unless `skip_corrupt_rows` is true, in which
+/// case the corrupt rows are logged and left out.
+fn convert_rows<E, T>(rows: Vec<Hydrated<E>>, skip_corrupt_rows: bool) -> ThoughtStoreResult<Vec<T>>
+where
+    T: TryFrom<E, Error = ThoughtConversionError>,
+{
+    let converted = rows.into_iter().map(|row| {
+        row.map_err(|e| ThoughtStoreError::Hydration(e.to_string()))
+            .and_then(|entity| T::try_from(entity).map_err(ThoughtStoreError::from))
+    });
+
+    if skip_corrupt_rows {
+        Ok(converted
+            .filter_map(|result| {
+                result
+                    .map_err(|error| warn!("THOUGHT STORE: skipping corrupt row: {error}"))
+                    .ok()
+            })
+            .collect())
+    } else {
+        converted.collect()
     }
 }
 
 #[async_trait]
 impl ThoughtStore for AgrumThoughtStore {
-    async fn get_thought(&self, thought_id: &Uuid) -> StdResult<Option<Thought>> {
-        let thought_repository = ThoughtEntityRepository::new(Provider::new(
-            self.client.borrow(),
-            Box::new(ThoughtEntitySqlDefinition::default()),
-        ));
+    async fn get_thought(&self, thought_id: &Uuid) -> ThoughtStoreResult<Option<Thought>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
 
         thought_repository
             .get_thought(thought_id)
+            .await?
+            .map(Thought::try_from)
+            .transpose()
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn get_thread(&self, thought_id: &Uuid) -> ThoughtStoreResult<Option<Vec<Thought>>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+
+        thought_repository
+            .get_thread(thought_id)
+            .await?
+            .map(|thread| {
+                thread
+                    .into_iter()
+                    .map(Thought::try_from)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn get_subtree(
+        &self,
+        thought_id: &Uuid,
+        max_depth: Option<u32>,
+    ) -> ThoughtStoreResult<Option<Vec<Thought>>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+
+        thought_repository
+            .get_subtree(thought_id, max_depth)
+            .await?
+            .map(|subtree| {
+                subtree
+                    .into_iter()
+                    .map(Thought::try_from)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<ThoughtSearchResult>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+        let limit = i64::try_from(limit).map_err(|e| ThoughtStoreError::Backend(anyhow!(e)))?;
+
+        let results = thought_repository
+            .search(&self.search_language, query, limit)
+            .await?;
+
+        self.convert_all(results)
+    }
+
+    async fn get_category_counts(&self) -> ThoughtStoreResult<Vec<CategoryCount>> {
+        let client = self.get_client().await?;
+        let category_repository = CategoryRepository::new(&client);
+
+        category_repository
+            .get_category_counts()
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|counts| counts.into_iter().map(|c| c.into()).collect())
+    }
+
+    async fn get_thoughts_by_category(&self, path: &str) -> ThoughtStoreResult<Vec<Thought>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+        let mut thoughts: Vec<Thought> =
+            self.convert_all(thought_repository.get_thoughts_by_category(path).await?)?;
+        thoughts.sort_by_key(|t| t.created_at);
+
+        Ok(thoughts)
+    }
+
+    async fn get_thoughts_by_category_pattern(
+        &self,
+        pattern: &str,
+    ) -> ThoughtStoreResult<Vec<Thought>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+        let mut thoughts: Vec<Thought> = self.convert_all(
+            thought_repository
+                .get_thoughts_by_category_pattern(pattern)
+                .await?,
+        )?;
+        thoughts.sort_by_key(|t| t.created_at);
+
+        Ok(thoughts)
+    }
+
+    async fn list_threads(
+        &self,
+        order: ThreadOrder,
+        after: Option<&ListingCursor>,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<ThreadSummary>> {
+        let client = self.get_client().await?;
+        let listing_repository = ListingRepository::new(&client);
+        let limit = i64::try_from(limit).map_err(|e| ThoughtStoreError::Backend(anyhow!(e)))?;
+
+        let threads = listing_repository.list_threads(order, after, limit).await?;
+
+        self.convert_all(threads)
+    }
+
+    async fn list_thread_thoughts(
+        &self,
+        thread_id: &Uuid,
+        after: Option<&ListingCursor>,
+        limit: usize,
+    ) -> ThoughtStoreResult<Vec<Thought>> {
+        let client = self.get_client().await?;
+        let listing_repository = ListingRepository::new(&client);
+        let limit = i64::try_from(limit).map_err(|e| ThoughtStoreError::Backend(anyhow!(e)))?;
+
+        let thoughts = listing_repository
+            .list_thread_thoughts(thread_id, after, limit)
+            .await?;
+
+        self.convert_all(thoughts)
+    }
+
+    async fn get_trashed_thought(
+        &self,
+        thought_id: &Uuid,
+    ) -> ThoughtStoreResult<Option<TrashedThought>> {
+        let client = self.get_client().await?;
+        let trash_repository = TrashRepository::new(&client);
+
+        trash_repository
+            .get_trashed_thought(thought_id)
+            .await?
+            .map(TrashedThought::try_from)
+            .transpose()
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn list_trash(&self) -> ThoughtStoreResult<Vec<TrashedThought>> {
+        let client = self.get_client().await?;
+        let trash_repository = TrashRepository::new(&client);
+
+        let entities = trash_repository.list_trash().await?;
+
+        self.convert_all(entities)
+    }
+
+    async fn delete_thought(
+        &self,
+        thought_id: &Uuid,
+        recursive: bool,
+    ) -> ThoughtStoreResult<Vec<Uuid>> {
+        let client = self.get_client().await?;
+        let trash_repository = TrashRepository::new(&client);
+
+        trash_repository
+            .delete_thought(thought_id, recursive)
+            .await
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn restore_thought(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<Uuid>> {
+        let client = self.get_client().await?;
+        let trash_repository = TrashRepository::new(&client);
+
+        trash_repository
+            .restore_thought(thought_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn purge_trash(&self, before: &DateTime<Utc>) -> ThoughtStoreResult<Vec<Uuid>> {
+        let client = self.get_client().await?;
+        let trash_repository = TrashRepository::new(&client);
+
+        trash_repository
+            .purge(before)
+            .await
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn get_revisions(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtRevision>> {
+        let client = self.get_client().await?;
+        let revision_repository = ThoughtRevisionRepository::new(&client);
+
+        revision_repository
+            .get_revisions(thought_id)
+            .await?
+            .into_iter()
+            .map(ThoughtRevision::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn get_source(&self, source_id: &Uuid) -> ThoughtStoreResult<Option<ThoughtSource>> {
+        let client = self.get_client().await?;
+        let source_repository = SourceRepository::new(&client);
+
+        source_repository
+            .get_source(source_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entity| entity.map(|e| e.into()))
+    }
+
+    async fn get_sources(&self) -> ThoughtStoreResult<Vec<ThoughtSource>> {
+        let client = self.get_client().await?;
+        let source_repository = SourceRepository::new(&client);
+
+        source_repository
+            .get_sources()
             .await
-            .map(|o| o.map(|t| t.into()))
+            .map_err(ThoughtStoreError::from)
+            .map(|entities| entities.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn insert_source(&self, source: ThoughtSource) -> ThoughtStoreResult<ThoughtSource> {
+        let client = self.get_client().await?;
+        let source_repository = SourceRepository::new(&client);
+        let duplicate_key = source.duplicate_key();
+
+        if let Some(entity) = source_repository
+            .insert_source(&source.into(), &duplicate_key)
+            .await?
+        {
+            return Ok(entity.into());
+        }
+
+        source_repository
+            .get_source_by_key(&duplicate_key)
+            .await?
+            .map(|entity| entity.into())
+            .ok_or_else(|| {
+                ThoughtStoreError::Conflict(format!(
+                    "Could not insert source '{duplicate_key}', its duplicate was removed meanwhile."
+                ))
+            })
+    }
+
+    async fn get_thoughts_by_source(&self, source_id: &Uuid) -> ThoughtStoreResult<Vec<Thought>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+        let mut thoughts: Vec<Thought> =
+            self.convert_all(thought_repository.get_thoughts_by_source(source_id).await?)?;
+        thoughts.sort_by_key(|t| t.created_at);
+
+        Ok(thoughts)
+    }
+
+    async fn merge_sources(
+        &self,
+        kept_id: &Uuid,
+        absorbed_id: &Uuid,
+    ) -> ThoughtStoreResult<Vec<Uuid>> {
+        let mut client = self.get_client().await?;
+        let transaction = client.transaction().await?;
+        let updated = SourceRepository::merge_sources(&transaction, kept_id, absorbed_id).await?;
+        transaction.commit().await?;
+
+        Ok(updated)
+    }
+
+    async fn get_author(&self, author_id: &Uuid) -> ThoughtStoreResult<Option<Author>> {
+        let client = self.get_client().await?;
+        let author_repository = AuthorRepository::new(&client);
+
+        author_repository
+            .get_author(author_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entity| entity.map(|e| e.into()))
+    }
+
+    async fn get_authors(&self) -> ThoughtStoreResult<Vec<Author>> {
+        let client = self.get_client().await?;
+        let author_repository = AuthorRepository::new(&client);
+
+        author_repository
+            .get_authors()
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entities| entities.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn find_author(&self, alias: &str) -> ThoughtStoreResult<Option<Author>> {
+        let client = self.get_client().await?;
+        let author_repository = AuthorRepository::new(&client);
+
+        author_repository
+            .find_author(&Author::alias_key(alias))
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entity| entity.map(|e| e.into()))
+    }
+
+    async fn insert_author(&self, author: Author) -> ThoughtStoreResult<Author> {
+        let mut client = self.get_client().await?;
+        let alias_key = Author::alias_key(&author.name);
+        let transaction = client.transaction().await?;
+
+        if let Some(entity) =
+            AuthorRepository::insert_author(&transaction, &author.into(), &alias_key).await?
+        {
+            transaction.commit().await?;
+
+            return Ok(entity.into());
+        }
+        transaction.rollback().await?;
+
+        AuthorRepository::new(&client)
+            .find_author(&alias_key)
+            .await?
+            .map(|entity| entity.into())
+            .ok_or_else(|| {
+                ThoughtStoreError::Conflict(format!(
+                    "Could not insert author '{alias_key}', its name was removed meanwhile."
+                ))
+            })
+    }
+
+    async fn add_author_alias(&self, author_id: &Uuid, alias: &str) -> ThoughtStoreResult<()> {
+        let mut client = self.get_client().await?;
+        let transaction = client.transaction().await?;
+        AuthorRepository::add_author_alias(
+            &transaction,
+            author_id,
+            alias,
+            &Author::alias_key(alias),
+        )
+        .await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_sources_by_author(
+        &self,
+        author_id: &Uuid,
+    ) -> ThoughtStoreResult<Vec<ThoughtSource>> {
+        let client = self.get_client().await?;
+        let source_repository = SourceRepository::new(&client);
+
+        source_repository
+            .get_sources_by_author(author_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entities| entities.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn get_thoughts_by_author(&self, author_id: &Uuid) -> ThoughtStoreResult<Vec<Thought>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+        let mut thoughts: Vec<Thought> =
+            self.convert_all(thought_repository.get_thoughts_by_author(author_id).await?)?;
+        thoughts.sort_by_key(|t| t.created_at);
+
+        Ok(thoughts)
+    }
+
+    async fn get_link(&self, link_id: &Uuid) -> ThoughtStoreResult<Option<ThoughtLink>> {
+        let client = self.get_client().await?;
+        let link_repository = LinkRepository::new(&client);
+
+        link_repository
+            .get_link(link_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entity| entity.map(|e| e.into()))
+    }
+
+    async fn get_outgoing_links(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtLink>> {
+        let client = self.get_client().await?;
+        let link_repository = LinkRepository::new(&client);
+
+        link_repository
+            .get_outgoing_links(thought_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entities| entities.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn get_backlinks(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<ThoughtLink>> {
+        let client = self.get_client().await?;
+        let link_repository = LinkRepository::new(&client);
+
+        link_repository
+            .get_backlinks(thought_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entities| entities.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn insert_link(&self, link: ThoughtLink) -> ThoughtStoreResult<ThoughtLink> {
+        let client = self.get_client().await?;
+        let link_repository = LinkRepository::new(&client);
+
+        link_repository
+            .insert_link(&link.into())
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|e| e.into())
+    }
+
+    async fn delete_link(&self, link_id: &Uuid) -> ThoughtStoreResult<bool> {
+        let client = self.get_client().await?;
+        let link_repository = LinkRepository::new(&client);
+
+        link_repository
+            .delete_link(link_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn get_attachment(&self, attachment_id: &Uuid) -> ThoughtStoreResult<Option<Attachment>> {
+        let client = self.get_client().await?;
+        let attachment_repository = AttachmentRepository::new(&client);
+
+        attachment_repository
+            .get_attachment(attachment_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entity| entity.map(|e| e.into()))
+    }
+
+    async fn get_attachments(&self, thought_id: &Uuid) -> ThoughtStoreResult<Vec<Attachment>> {
+        let client = self.get_client().await?;
+        let attachment_repository = AttachmentRepository::new(&client);
+
+        attachment_repository
+            .get_attachments(thought_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|entities| entities.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn insert_attachment(&self, attachment: Attachment) -> ThoughtStoreResult<Attachment> {
+        let client = self.get_client().await?;
+        let attachment_repository = AttachmentRepository::new(&client);
+
+        attachment_repository
+            .insert_attachment(&attachment.into())
+            .await
+            .map_err(ThoughtStoreError::from)
+            .map(|e| e.into())
+    }
+
+    async fn delete_attachment(&self, attachment_id: &Uuid) -> ThoughtStoreResult<bool> {
+        let client = self.get_client().await?;
+        let attachment_repository = AttachmentRepository::new(&client);
+
+        attachment_repository
+            .delete_attachment(attachment_id)
+            .await
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn get_attachment_hashes(&self) -> ThoughtStoreResult<Vec<String>> {
+        let client = self.get_client().await?;
+        let attachment_repository = AttachmentRepository::new(&client);
+
+        attachment_repository
+            .get_attachment_hashes()
+            .await
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn insert_thought(&self, thought: Thought) -> ThoughtStoreResult<Thought> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+        let search_text = thought.plain_text();
+
+        thought_repository
+            .insert_thought(&thought.into(), &self.search_language, &search_text)
+            .await?
+            .try_into()
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn update_thought(&self, thought: Thought) -> ThoughtStoreResult<Option<Thought>> {
+        let client = self.get_client().await?;
+        let thought_repository = ThoughtEntityRepository::new(&client);
+        let search_text = thought.plain_text();
+
+        thought_repository
+            .update_thought(&thought.into(), &self.search_language, &search_text)
+            .await?
+            .map(Thought::try_from)
+            .transpose()
+            .map_err(ThoughtStoreError::from)
+    }
+
+    async fn move_thought(
+        &self,
+        thought_id: &Uuid,
+        parent_thought_id: Option<&Uuid>,
+    ) -> ThoughtStoreResult<Option<Vec<Uuid>>> {
+        let mut client = self.get_client().await?;
+        let transaction = client.transaction().await?;
+        let moved =
+            ThoughtEntityRepository::move_thought(&transaction, thought_id, parent_thought_id)
+                .await?;
+        transaction.commit().await?;
+
+        Ok(moved)
+    }
+
+    async fn combine_threads(
+        &self,
+        kept_id: &Uuid,
+        absorbed_id: &Uuid,
+    ) -> ThoughtStoreResult<Option<ThreadMerge>> {
+        let mut client = self.get_client().await?;
+        let transaction = client.transaction().await?;
+        let merge =
+            ThoughtEntityRepository::combine_threads(&transaction, kept_id, absorbed_id).await?;
+        transaction.commit().await?;
+
+        Ok(merge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        test_database::TestDatabase,
+        thoughts::model::{agrum::ThoughtEntity, ContentFormat, ThoughtContent},
+    };
+
+    fn entity(thought_id: u128, content_format: &str) -> ThoughtEntity {
+        ThoughtEntity {
+            thought_id: Uuid::from_u128(thought_id),
+            parent_thought_id: None,
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            source_ids: Vec::new(),
+            created_at: DateTime::UNIX_EPOCH,
+            content: json!("Virtue is the only good."),
+            content_format: content_format.to_string(),
+        }
+    }
+
+    fn rows() -> Vec<Hydrated<ThoughtEntity>> {
+        vec![
+            Ok(entity(1, "markdown")),
+            Err(HydrationError::InvalidData(
+                "Could not read data for field 'keywords'.".to_string(),
+            )),
+            Ok(entity(3, "unknown")),
+            Ok(entity(4, "markdown")),
+        ]
+    }
+
+    #[test]
+    fn corrupt_rows_fail_the_listing() {
+        let error = convert_rows::<_, Thought>(rows(), false).unwrap_err();
+
+        assert!(matches!(error, ThoughtStoreError::Hydration(_)));
+    }
+
+    #[test]
+    fn corrupt_rows_are_skipped() {
+        let thoughts: Vec<Thought> = convert_rows(rows(), true).unwrap();
+
+        assert_eq!(
+            vec![Uuid::from_u128(1), Uuid::from_u128(4)],
+            thoughts.iter().map(|t| t.thought_id).collect::<Vec<_>>()
+        );
+    }
+
+    fn thread(title: &str) -> Thought {
+        Thought {
+            thought_id: Uuid::new_v4(),
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            source_ids: Vec::new(),
+            created_at: Utc::now(),
+            content: ThoughtContent::Thread {
+                title: title.to_string(),
+            },
+            content_format: ContentFormat::Plain,
+        }
+    }
+
+    #[tokio::test]
+    async fn search_snippets_escape_the_content() -> crate::StdResult<()> {
+        let database = match TestDatabase::create().await? {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let store = database.get_thought_store().await?;
+        store
+            .insert_thought(thread("stoic <img src=x onerror=alert(1)//"))
+            .await?;
+
+        let results = store.search("stoic", 10).await?;
+
+        assert_eq!(
+            "<mark>stoic</mark> &lt;img src=x onerror=alert",
+            results[0].snippet
+        );
+
+        database.drop().await
+    }
+
+    #[tokio::test]
+    async fn threads_are_paged_by_creation() -> crate::StdResult<()> {
+        let database = match TestDatabase::create().await? {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let store = database.get_thought_store().await?;
+        for title in ["first", "second", "third"] {
+            store.insert_thought(thread(title)).await?;
+        }
+
+        let first_page = store.list_threads(ThreadOrder::CreatedAt, None, 2).await?;
+        let cursor = first_page[1].cursor(ThreadOrder::CreatedAt);
+        let second_page = store
+            .list_threads(ThreadOrder::CreatedAt, Some(&cursor), 2)
+            .await?;
+
+        assert_eq!(1, second_page.len());
+        assert!(second_page[0].cursor(ThreadOrder::CreatedAt) < cursor);
+        assert!(first_page
+            .iter()
+            .all(|summary| summary.thread.thought_id != second_page[0].thread.thought_id));
+
+        database.drop().await
+    }
+
+    #[tokio::test]
+    async fn trash_is_purged_by_deletion_date() -> crate::StdResult<()> {
+        let database = match TestDatabase::create().await? {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let store = database.get_thought_store().await?;
+        let thought = store.insert_thought(thread("deleted")).await?;
+        store.delete_thought(&thought.thought_id, false).await?;
+
+        let hour_ago = Utc::now() - chrono::Duration::hours(1);
+        assert!(store.purge_trash(&hour_ago).await?.is_empty());
+
+        let in_an_hour = Utc::now() + chrono::Duration::hours(1);
+        assert_eq!(
+            vec![thought.thought_id],
+            store.purge_trash(&in_an_hour).await?
+        );
+
+        database.drop().await
+    }
+
+    #[tokio::test]
+    async fn revisions_keep_their_content_format() -> crate::StdResult<()> {
+        let database = match TestDatabase::create().await? {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let store = database.get_thought_store().await?;
+        let thought = store.insert_thought(thread("*stoic*")).await?;
+        store
+            .update_thought(Thought {
+                content_format: ContentFormat::Markdown,
+                ..thought.clone()
+            })
+            .await?;
+
+        let revisions = store.get_revisions(&thought.thought_id).await?;
+
+        assert_eq!(1, revisions.len());
+        assert_eq!(ContentFormat::Plain, revisions[0].content_format);
+
+        database.drop().await
     }
 }
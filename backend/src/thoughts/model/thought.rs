@@ -1,17 +1,14 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
 use uuid::Uuid;
 
-use super::agrum::ThoughtEntity;
+use super::{
+    agrum::{ThoughtEntity, ThoughtSearchEntity},
+    render_snippet, ContentFormat,
+};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ThoughtSource {
-    pub name: String,
-    pub authors: Vec<String>,
-    pub description: String,
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ThoughtContent {
     Node {
         parent_thought_id: Uuid,
@@ -22,42 +19,225 @@ pub enum ThoughtContent {
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ThoughtEnvelope {
     pub thought_id: Uuid,
     pub keywords: Vec<String>,
     pub categories: Vec<String>,
-    pub sources: Vec<ThoughtSource>,
+    /// Identifiers of the registered sources cited by the thought.
+    pub source_ids: Vec<Uuid>,
     pub created_at: DateTime<Utc>,
     pub content: ThoughtContent,
+    /// Format of the text of the content.
+    pub content_format: ContentFormat,
 }
 
-impl From<ThoughtEntity> for ThoughtEnvelope {
-    fn from(value: ThoughtEntity) -> Self {
-        let content = if let Some(parent_thought_id) = value.parent_thought_id {
+impl ThoughtEnvelope {
+    /// Return the parent thought identifier if this thought is a `Node`, None if it is a
+    /// `Thread`.
+    pub fn parent_thought_id(&self) -> Option<Uuid> {
+        match &self.content {
             ThoughtContent::Node {
-                parent_thought_id,
-                thought: value.content,
-            }
-        } else {
-            ThoughtContent::Thread {
-                title: value.content,
+                parent_thought_id, ..
+            } => Some(*parent_thought_id),
+            ThoughtContent::Thread { .. } => None,
+        }
+    }
+
+    /// Return the text of the thought, its content for a `Node` or its title for a `Thread`.
+    pub fn text(&self) -> &str {
+        match &self.content {
+            ThoughtContent::Node { thought, .. } => thought,
+            ThoughtContent::Thread { title } => title,
+        }
+    }
+
+    /// Return the text of the thought rendered as sanitized HTML.
+    pub fn html(&self) -> String {
+        self.content_format.render_html(self.text())
+    }
+
+    /// Return the text of the thought without markup, as indexed by the search.
+    pub fn plain_text(&self) -> String {
+        self.content_format.plain_text(self.text())
+    }
+}
+
+/// Thought matching a search query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThoughtSearchResult {
+    pub thought: ThoughtEnvelope,
+    /// Relevance of the match, the higher the better.
+    pub rank: f32,
+    /// HTML excerpt of the thought, the text is escaped and the matching terms are enclosed in
+    /// `<mark>` tags.
+    pub snippet: String,
+}
+
+impl TryFrom<ThoughtSearchEntity> for ThoughtSearchResult {
+    type Error = ThoughtConversionError;
+
+    fn try_from(value: ThoughtSearchEntity) -> Result<Self, Self::Error> {
+        Ok(Self {
+            thought: value.thought.try_into()?,
+            rank: value.rank,
+            snippet: render_snippet(&value.snippet),
+        })
+    }
+}
+
+/// Error raised when a thought read from the database does not make a valid model.
+#[derive(Debug, Error)]
+#[error("Invalid field '{field}' of thought '{thought_id}': {reason}")]
+pub struct ThoughtConversionError {
+    pub thought_id: Uuid,
+    pub field: &'static str,
+    pub reason: String,
+}
+
+impl ThoughtConversionError {
+    pub fn new(thought_id: Uuid, field: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            thought_id,
+            field,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Extract the text of a thought stored as a JSON string.
+pub(crate) fn get_text_content(
+    thought_id: Uuid,
+    field: &'static str,
+    value: Value,
+) -> Result<String, ThoughtConversionError> {
+    match value {
+        Value::String(text) => Ok(text),
+        other => Err(ThoughtConversionError::new(
+            thought_id,
+            field,
+            format!("expected a JSON string, got '{other}'"),
+        )),
+    }
+}
+
+impl TryFrom<ThoughtEntity> for ThoughtEnvelope {
+    type Error = ThoughtConversionError;
+
+    fn try_from(value: ThoughtEntity) -> Result<Self, Self::Error> {
+        let thought_id = value.thought_id;
+        let text = get_text_content(thought_id, "content", value.content)?;
+        let content_format = ContentFormat::parse(&value.content_format).ok_or_else(|| {
+            ThoughtConversionError::new(
+                thought_id,
+                "content_format",
+                format!("unknown content format '{}'", value.content_format),
+            )
+        })?;
+        let content = match value.parent_thought_id {
+            Some(parent_thought_id) if parent_thought_id == thought_id => {
+                return Err(ThoughtConversionError::new(
+                    thought_id,
+                    "parent_thought_id",
+                    "a thought cannot be its own parent",
+                ));
             }
+            Some(parent_thought_id) => ThoughtContent::Node {
+                parent_thought_id,
+                thought: text,
+            },
+            None => ThoughtContent::Thread { title: text },
         };
 
-        let sources: Vec<ThoughtSource> = value
-            .sources
-            .into_iter()
-            .map(|c| -> ThoughtSource { serde_json::from_str(&c).unwrap() })
-            .collect();
+        Ok(Self {
+            thought_id,
+            keywords: value.keywords,
+            categories: value.categories,
+            source_ids: value.source_ids,
+            created_at: value.created_at,
+            content,
+            content_format,
+        })
+    }
+}
+
+impl From<ThoughtEnvelope> for ThoughtEntity {
+    fn from(value: ThoughtEnvelope) -> Self {
+        let (parent_thought_id, content) = match value.content {
+            ThoughtContent::Node {
+                parent_thought_id,
+                thought,
+            } => (Some(parent_thought_id), thought),
+            ThoughtContent::Thread { title } => (None, title),
+        };
 
         Self {
             thought_id: value.thought_id,
+            parent_thought_id,
             keywords: value.keywords,
             categories: value.categories,
-            sources,
+            source_ids: value.source_ids,
             created_at: value.created_at,
-            content,
+            content: Value::String(content),
+            content_format: value.content_format.as_str().to_string(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn entity(parent_thought_id: Option<Uuid>) -> ThoughtEntity {
+        ThoughtEntity {
+            thought_id: Uuid::from_u128(1),
+            parent_thought_id,
+            keywords: vec!["stoicism".to_string()],
+            categories: Vec::new(),
+            source_ids: Vec::new(),
+            created_at: DateTime::UNIX_EPOCH,
+            content: json!("Virtue is the only good."),
+            content_format: "markdown".to_string(),
+        }
+    }
+
+    #[test]
+    fn convert_entity() {
+        let thread = ThoughtEnvelope::try_from(entity(None)).unwrap();
+        assert_eq!(
+            ThoughtContent::Thread {
+                title: "Virtue is the only good.".to_string()
+            },
+            thread.content
+        );
+        assert_eq!(ContentFormat::Markdown, thread.content_format);
+
+        let node = ThoughtEnvelope::try_from(entity(Some(Uuid::from_u128(2)))).unwrap();
+        assert_eq!(Some(Uuid::from_u128(2)), node.parent_thought_id());
+
+        let entity: ThoughtEntity = node.clone().into();
+        assert_eq!(json!("Virtue is the only good."), entity.content);
+        assert_eq!(node, ThoughtEnvelope::try_from(entity).unwrap());
+    }
+
+    #[test]
+    fn reject_invalid_entity() {
+        let mut invalid = entity(None);
+        invalid.content = json!({ "title": "Virtue" });
+        let error = ThoughtEnvelope::try_from(invalid).unwrap_err();
+        assert_eq!("content", error.field);
+        assert_eq!(Uuid::from_u128(1), error.thought_id);
+
+        let mut invalid = entity(None);
+        invalid.content_format = "html".to_string();
+        assert_eq!(
+            "content_format",
+            ThoughtEnvelope::try_from(invalid).unwrap_err().field
+        );
+
+        let error = ThoughtEnvelope::try_from(entity(Some(Uuid::from_u128(1)))).unwrap_err();
+        assert_eq!("parent_thought_id", error.field);
+    }
+}
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+
+use super::{agrum::TrashedThoughtEntity, ThoughtConversionError, ThoughtEnvelope};
+
+/// Soft deleted thought, it can be restored until the trash is purged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashedThought {
+    pub thought: ThoughtEnvelope,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl TryFrom<TrashedThoughtEntity> for TrashedThought {
+    type Error = ThoughtConversionError;
+
+    fn try_from(value: TrashedThoughtEntity) -> Result<Self, Self::Error> {
+        Ok(Self {
+            thought: value.thought.try_into()?,
+            deleted_at: value.deleted_at,
+        })
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use super::ThoughtEnvelope;
+
+/// Order of the children of a thought in a tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SiblingOrder {
+    #[default]
+    OldestFirst,
+    NewestFirst,
+}
+
+impl SiblingOrder {
+    /// Parse the name of an order, `oldest` or `newest`. None is returned if it is unknown.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "oldest" => Some(Self::OldestFirst),
+            "newest" => Some(Self::NewestFirst),
+            _ => None,
+        }
+    }
+}
+
+/// A thought with its descendants. Several thoughts may share the same parent, so a thread is
+/// a tree whose root is the `Thread` thought.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThoughtTree {
+    pub thought: ThoughtEnvelope,
+    pub children: Vec<ThoughtTree>,
+    /// True when the thought has children left out by the depth limit.
+    pub truncated: bool,
+}
+
+impl ThoughtTree {
+    /// Build the tree rooted at the first of the given thoughts. The thoughts more than
+    /// `max_depth` levels below the root are left out, as are the thoughts not descending from
+    /// the root. Siblings are sorted by creation date then identifier.
+    pub fn build(
+        mut thoughts: Vec<ThoughtEnvelope>,
+        max_depth: Option<u32>,
+        order: SiblingOrder,
+    ) -> Option<Self> {
+        if thoughts.is_empty() {
+            return None;
+        }
+        let root = thoughts.remove(0);
+        let mut children: HashMap<Uuid, Vec<ThoughtEnvelope>> = HashMap::new();
+
+        for thought in thoughts {
+            if let Some(parent_id) = thought.parent_thought_id() {
+                children.entry(parent_id).or_default().push(thought);
+            }
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by(|a, b| {
+                let ordering = a
+                    .created_at
+                    .cmp(&b.created_at)
+                    .then(a.thought_id.cmp(&b.thought_id));
+
+                match order {
+                    SiblingOrder::OldestFirst => ordering,
+                    SiblingOrder::NewestFirst => ordering.reverse(),
+                }
+            });
+        }
+        let mut visited: HashSet<Uuid> = HashSet::new();
+
+        Some(Self::attach(
+            root,
+            0,
+            max_depth,
+            &mut children,
+            &mut visited,
+        ))
+    }
+
+    fn attach(
+        thought: ThoughtEnvelope,
+        depth: u32,
+        max_depth: Option<u32>,
+        children: &mut HashMap<Uuid, Vec<ThoughtEnvelope>>,
+        visited: &mut HashSet<Uuid>,
+    ) -> Self {
+        visited.insert(thought.thought_id);
+        let siblings: Vec<ThoughtEnvelope> = children
+            .remove(&thought.thought_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|child| !visited.contains(&child.thought_id))
+            .collect();
+
+        if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            return Self {
+                thought,
+                truncated: !siblings.is_empty(),
+                children: Vec::new(),
+            };
+        }
+
+        Self {
+            thought,
+            children: siblings
+                .into_iter()
+                .map(|child| Self::attach(child, depth + 1, max_depth, children, visited))
+                .collect(),
+            truncated: false,
+        }
+    }
+
+    /// Number of thoughts in the tree, root included.
+    pub fn thought_count(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(|c| c.thought_count())
+            .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::thoughts::model::{ContentFormat, ThoughtContent};
+
+    fn thought(parent_thought_id: Option<Uuid>, age: i64) -> ThoughtEnvelope {
+        let content = match parent_thought_id {
+            Some(parent_thought_id) => ThoughtContent::Node {
+                parent_thought_id,
+                thought: "node".to_string(),
+            },
+            None => ThoughtContent::Thread {
+                title: "thread".to_string(),
+            },
+        };
+
+        ThoughtEnvelope {
+            thought_id: Uuid::new_v4(),
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            source_ids: Vec::new(),
+            created_at: Utc::now() - Duration::seconds(age),
+            content,
+            content_format: ContentFormat::Plain,
+        }
+    }
+
+    #[test]
+    fn tree_is_built_with_depth_limit_and_order() {
+        let root = thought(None, 10);
+        let older = thought(Some(root.thought_id), 8);
+        let newer = thought(Some(root.thought_id), 5);
+        let grandchild = thought(Some(older.thought_id), 2);
+        let stray = thought(Some(Uuid::new_v4()), 1);
+        let thoughts = vec![
+            root.clone(),
+            newer.clone(),
+            grandchild.clone(),
+            older.clone(),
+            stray,
+        ];
+
+        let tree = ThoughtTree::build(thoughts.clone(), None, SiblingOrder::OldestFirst).unwrap();
+        assert_eq!(4, tree.thought_count());
+        assert_eq!(older, tree.children[0].thought);
+        assert_eq!(grandchild, tree.children[0].children[0].thought);
+        assert!(!tree.children[0].truncated);
+
+        let tree = ThoughtTree::build(thoughts, Some(1), SiblingOrder::NewestFirst).unwrap();
+        assert_eq!(3, tree.thought_count());
+        assert_eq!(newer, tree.children[0].thought);
+        assert!(!tree.children[0].truncated);
+        assert!(tree.children[1].truncated);
+
+        assert_eq!(
+            None,
+            ThoughtTree::build(Vec::new(), None, SiblingOrder::default())
+        );
+    }
+}
@@ -1,53 +1,472 @@
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
+use anyhow::anyhow;
 use async_trait::async_trait;
-use log::trace;
+use chrono::Utc;
+use log::{debug, trace};
 use thiserror::Error;
 use tokio::sync::{mpsc::UnboundedSender, Mutex};
 use uuid::Uuid;
 
-use crate::{EventMessage, StdResult};
+use crate::{EventMessage, StateModification, StdError};
 
 use super::{
-    model::{agrum::ThoughtEntity, ThoughtEnvelope, ThoughtSource, ThoughtStore},
+    model::{
+        is_valid_category_path, is_valid_category_pattern, is_valid_mime_type, sanitize_file_name,
+        Attachment, Author, BlobStore, CategoryNode, ContentFormat, LinkKind, ListingCursor, Page,
+        RevisionDiff, SiblingOrder, ThoughtContent, ThoughtEnvelope, ThoughtLink, ThoughtRevision,
+        ThoughtSearchResult, ThoughtSource, ThoughtStore, ThoughtStoreError, ThoughtTree,
+        ThreadMerge, ThreadMergeMode, ThreadOrder, ThreadSummary, TrashedThought,
+    },
     ThoughtServiceConfig,
 };
 
 const SERVICE_ID: u8 = 1;
 
+/// Subject of the events sent by this service.
+const EVENT_SUBJECT: &str = "thought";
+
+/// Subject of the events about the links between thoughts.
+const LINK_EVENT_SUBJECT: &str = "thought_link";
+
+/// Subject of the events about the files attached to thoughts.
+const ATTACHMENT_EVENT_SUBJECT: &str = "thought_attachment";
+
+/// Attachment files younger than this are kept by the garbage collector, they may belong to an
+/// upload whose attachment is not recorded yet.
+const BLOB_GRACE_PERIOD: Duration = Duration::from_secs(3600);
+
+/// Maximum number of search results returned at once.
+pub const MAX_SEARCH_RESULTS: usize = 100;
+
+/// Maximum number of items in a listing page.
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Maximum number of levels below the root of a tree. Trees are built and serialized
+/// recursively, this keeps a deep thread from exhausting the stack.
+pub const MAX_TREE_DEPTH: u32 = 100;
+
 #[derive(Debug, Error)]
 pub enum ThoughtServiceError {
     #[error("Parent node '{0}' does not exist")]
     ParentNodeDoesNotExist(String),
+
+    #[error("Invalid thought identifier '{0}'")]
+    InvalidIdentifier(String),
+
+    #[error("Search query is empty")]
+    EmptySearchQuery,
+
+    #[error("Invalid category '{0}'")]
+    InvalidCategory(String),
+
+    #[error("Invalid listing cursor '{0}'")]
+    InvalidCursor(String),
+
+    #[error("Threads ordered by last activity cannot be paged with a cursor")]
+    UnpageableOrder,
+
+    #[error("Thought '{0}' has children, they must be deleted with it")]
+    ThoughtHasChildren(String),
+
+    #[error("Thought '{0}' is in the trash, it must be restored first")]
+    ThoughtIsDeleted(String),
+
+    #[error("Thought '{0}' already exists")]
+    ThoughtAlreadyExists(String),
+
+    #[error("Invalid source identifier '{0}'")]
+    InvalidSourceIdentifier(String),
+
+    #[error("Source '{0}' does not exist")]
+    SourceDoesNotExist(String),
+
+    #[error("Source '{0}' cannot be merged into itself")]
+    SourceMergedIntoItself(String),
+
+    #[error("Invalid author identifier '{0}'")]
+    InvalidAuthorIdentifier(String),
+
+    #[error("Invalid author name '{0}'")]
+    InvalidAuthorName(String),
+
+    #[error("Invalid link identifier '{0}'")]
+    InvalidLinkIdentifier(String),
+
+    #[error("Invalid link kind '{0}', expected 'see_also', 'contradicts' or 'supersedes'")]
+    InvalidLinkKind(String),
+
+    #[error("Thought '{0}' cannot be linked to itself")]
+    ThoughtLinkedToItself(String),
+
+    #[error("Thought '{0}' cannot be moved under itself or one of its descendants")]
+    ThoughtMovedUnderItself(String),
+
+    #[error("Thought '{0}' cannot change its parent when updated, it must be moved")]
+    ParentChangedByUpdate(String),
+
+    #[error("Thought '{0}' is not the root of a thread")]
+    NotAThreadRoot(String),
+
+    #[error("Thread '{0}' cannot be merged into itself")]
+    ThreadMergedIntoItself(String),
+
+    #[error("Invalid attachment identifier '{0}'")]
+    InvalidAttachmentIdentifier(String),
+
+    #[error("Invalid attachment file name '{0}'")]
+    InvalidFileName(String),
+
+    #[error("Invalid MIME type '{0}'")]
+    InvalidMimeType(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+
+    #[error(transparent)]
+    Store(#[from] ThoughtStoreError),
+
+    #[error("Attachment content error: {0}")]
+    BlobStore(StdError),
+
+    #[error("Event dispatch error: {0}")]
+    EventDispatch(StdError),
+}
+
+pub type ThoughtServiceResult<T> = Result<T, ThoughtServiceError>;
+
+/// Editable parts of a thought sent by a client, the service checks them before building the
+/// thought. The content is the text of a node or the title of a thread.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThoughtDraft {
+    pub content: String,
+    pub content_format: ContentFormat,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    /// Identifiers of the cited sources, they must be registered.
+    pub source_ids: Vec<String>,
 }
 
 /// Description of the API for BackendHttpService`
 #[async_trait]
 pub trait ThoughtService: Sync + Send {
     /// Retrieve a thought from the referential, if no thought is found, None is returned.
-    async fn get_thought(&self, thought_id: &Uuid) -> StdResult<Option<ThoughtEnvelope>>;
+    async fn get_thought(&self, thought_id: &Uuid)
+        -> ThoughtServiceResult<Option<ThoughtEnvelope>>;
 
     /// Create or update a Thought. It raises an `ThoughtServiceError::ParentNodeDoesNotExist` if
     /// the given `parent_thought_id` does not exist.  If no `parent_thought_id` is given, a new
-    /// `Thread` is created.
+    /// `Thread` is created and the content of the draft is its title. The content is plain text
+    /// or markdown according to the draft format. The cited sources must be registered, otherwise
+    /// a `ThoughtServiceError::SourceDoesNotExist` is raised. An update keeps the parent of the
+    /// thought, a `ThoughtServiceError::ParentChangedByUpdate` is raised if another parent is
+    /// given, thoughts change their parent with `move_thought`.
     async fn post_thought(
         &self,
         thought_id: String,
         parent_thought_id: Option<String>,
-        keywords: Vec<String>,
-        categories: Vec<String>,
-        sources: Vec<ThoughtSource>,
-    ) -> StdResult<ThoughtEnvelope>;
+        draft: ThoughtDraft,
+    ) -> ThoughtServiceResult<ThoughtEnvelope>;
+
+    /// Create a Thought, like `post_thought` does, but never update an existing one. It raises a
+    /// `ThoughtServiceError::ThoughtAlreadyExists` if a thought with the given identifier
+    /// exists, in the trash included.
+    async fn create_thought(
+        &self,
+        thought_id: String,
+        parent_thought_id: Option<String>,
+        draft: ThoughtDraft,
+    ) -> ThoughtServiceResult<ThoughtEnvelope>;
 
     /// Retrieve a Thread from the referential, the given thought_id is one the the Thread's
     /// thought. The Thread is returned from the first thought to the thought pointed by the given
     /// thought_id, if it does not exist, None is returned.
-    async fn get_thread(&self, thought_id: &str) -> StdResult<Option<Vec<ThoughtEnvelope>>>;
+    async fn get_thread(
+        &self,
+        thought_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtEnvelope>>>;
+
+    /// Retrieve the whole tree of the Thread the given thought belongs to, from its root. Only
+    /// the thoughts at most `max_depth` levels below the root are returned, the depth is capped
+    /// to `MAX_TREE_DEPTH` which is also used when no depth is given. Siblings are sorted by
+    /// creation date in the given order. If the thought does not exist, None is returned.
+    async fn get_thread_tree(
+        &self,
+        thought_id: &str,
+        max_depth: Option<u32>,
+        order: SiblingOrder,
+    ) -> ThoughtServiceResult<Option<ThoughtTree>>;
+
+    /// Retrieve the tree below the given thought, like `get_thread_tree` does for the root of a
+    /// Thread. If the thought does not exist, None is returned.
+    async fn get_subtree(
+        &self,
+        thought_id: &str,
+        max_depth: Option<u32>,
+        order: SiblingOrder,
+    ) -> ThoughtServiceResult<Option<ThoughtTree>>;
+
+    /// Full text search over the thoughts content and thread titles, best matches first. At most
+    /// `limit` results are returned, this limit is capped to `MAX_SEARCH_RESULTS`. It raises a
+    /// `ThoughtServiceError::EmptySearchQuery` if the query has no terms.
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> ThoughtServiceResult<Vec<ThoughtSearchResult>>;
+
+    /// Retrieve the category tree with the number of thoughts filed under each category,
+    /// including its sub categories.
+    async fn get_category_tree(&self) -> ThoughtServiceResult<Vec<CategoryNode>>;
+
+    /// Retrieve the thoughts filed under the given category or one of its sub categories, oldest
+    /// first. It raises a `ThoughtServiceError::InvalidCategory` if the path is not valid.
+    async fn get_category_thoughts(&self, path: &str)
+        -> ThoughtServiceResult<Vec<ThoughtEnvelope>>;
+
+    /// Retrieve the thoughts having a category matching the given `lquery` pattern (like
+    /// `*.stoicism.*`), oldest first. It raises a `ThoughtServiceError::InvalidCategory` if the
+    /// pattern is not valid.
+    async fn match_category_thoughts(
+        &self,
+        pattern: &str,
+    ) -> ThoughtServiceResult<Vec<ThoughtEnvelope>>;
+
+    /// List the threads, most recent first according to the given order. The listing starts at
+    /// the beginning or right after the opaque cursor returned with a previous page. The page
+    /// size is capped to `MAX_PAGE_SIZE`. It raises a `ThoughtServiceError::InvalidCursor` if
+    /// the cursor cannot be decoded. Only the creation order is paged: the pages ordered by
+    /// last activity have no next cursor and a `ThoughtServiceError::UnpageableOrder` is
+    /// raised if a cursor is given with that order.
+    async fn list_threads(
+        &self,
+        order: ThreadOrder,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> ThoughtServiceResult<Page<ThreadSummary>>;
+
+    /// List the thoughts of a thread, root included, oldest first. The listing starts at the
+    /// beginning or right after the opaque cursor returned with a previous page. The page size
+    /// is capped to `MAX_PAGE_SIZE`. If the given thought is not a thread root, None is
+    /// returned.
+    async fn list_thread_thoughts(
+        &self,
+        thread_id: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> ThoughtServiceResult<Option<Page<ThoughtEnvelope>>>;
+
+    /// Move a thought to the trash, it is then hidden from the other methods. When `recursive`
+    /// is true, all its descendants are moved with it, otherwise a
+    /// `ThoughtServiceError::ThoughtHasChildren` is raised if it has children. A `Delete` event
+    /// is sent for each deleted thought. If the thought does not exist, None is returned.
+    async fn delete_thought(
+        &self,
+        thought_id: &str,
+        recursive: bool,
+    ) -> ThoughtServiceResult<Option<Vec<Uuid>>>;
+
+    /// List the thoughts in the trash, most recently deleted first.
+    async fn get_trash(&self) -> ThoughtServiceResult<Vec<TrashedThought>>;
+
+    /// Restore a thought from the trash with the descendants deleted at the same time. It raises
+    /// a `ThoughtServiceError::ParentNodeDoesNotExist` if the parent of the thought is not
+    /// restored first. A `Creation` event is sent for each restored thought. If the thought is
+    /// not in the trash, None is returned.
+    async fn restore_thought(&self, thought_id: &str) -> ThoughtServiceResult<Option<Vec<Uuid>>>;
+
+    /// Move a thought and its whole subtree under another thought, possibly in another thread.
+    /// When no parent is given, the thought becomes the root of a new `Thread` titled with its
+    /// text. It raises a `ThoughtServiceError::ParentNodeDoesNotExist` if the new parent does
+    /// not exist and a `ThoughtServiceError::ThoughtMovedUnderItself` if the new parent is the
+    /// thought or one of its descendants. An `Update` event is sent for the moved thought and
+    /// each of its descendants. If the thought does not exist, None is returned.
+    async fn move_thought(
+        &self,
+        thought_id: &str,
+        parent_thought_id: Option<&str>,
+    ) -> ThoughtServiceResult<Option<Vec<Uuid>>>;
+
+    /// Merge a thread into the thread of another thought. With `ThreadMergeMode::Graft`, the
+    /// merged thread root is moved under the given thought. With `ThreadMergeMode::Combine`, it
+    /// is folded into the root of the other thread, see `ThreadMergeMode`. It raises a
+    /// `ThoughtServiceError::NotAThreadRoot` if the merged thought is not a thread root and a
+    /// `ThoughtServiceError::ThreadMergedIntoItself` if both thoughts are in the same thread.
+    /// `Update` events are sent for the thoughts, links and attachments changed, `Delete` events
+    /// for the thought and links removed. If one of the thoughts does not exist, None is returned.
+    async fn merge_threads(
+        &self,
+        thread_id: &str,
+        into_thought_id: &str,
+        mode: ThreadMergeMode,
+    ) -> ThoughtServiceResult<Option<ThreadMerge>>;
+
+    /// Permanently remove the thoughts kept in the trash for longer than the configured
+    /// retention period. A `Delete` event is sent for each removed thought.
+    async fn purge_trash(&self) -> ThoughtServiceResult<Vec<Uuid>>;
+
+    /// List all the versions of a thought, oldest first, the last one being its current state.
+    /// If the thought does not exist, None is returned.
+    async fn get_revisions(
+        &self,
+        thought_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtRevision>>>;
+
+    /// Retrieve one version of a thought, None is returned if the thought or the revision does
+    /// not exist.
+    async fn get_revision(
+        &self,
+        thought_id: &str,
+        revision: u32,
+    ) -> ThoughtServiceResult<Option<ThoughtRevision>>;
+
+    /// Compare two versions of a thought, None is returned if the thought or one of the
+    /// revisions does not exist.
+    async fn diff_revisions(
+        &self,
+        thought_id: &str,
+        from_revision: u32,
+        to_revision: u32,
+    ) -> ThoughtServiceResult<Option<RevisionDiff>>;
+
+    /// Register a source. If a registered source has the same name and authors, ignoring case,
+    /// punctuation and the order of the authors, it is returned instead and nothing is created.
+    async fn register_source(
+        &self,
+        name: String,
+        authors: Vec<String>,
+        description: String,
+    ) -> ThoughtServiceResult<ThoughtSource>;
+
+    /// Retrieve a registered source, if it does not exist, None is returned.
+    async fn get_source(&self, source_id: &str) -> ThoughtServiceResult<Option<ThoughtSource>>;
+
+    /// List all the registered sources, sorted by name.
+    async fn get_sources(&self) -> ThoughtServiceResult<Vec<ThoughtSource>>;
+
+    /// List the groups of registered sources that look like duplicates, they can be merged with
+    /// `merge_sources`.
+    async fn find_duplicate_sources(&self) -> ThoughtServiceResult<Vec<Vec<ThoughtSource>>>;
+
+    /// Merge a source into another one: the thoughts citing it cite the kept source instead and
+    /// it is removed. It raises a `ThoughtServiceError::SourceMergedIntoItself` if both sources
+    /// are the same. An `Update` event is sent for each thought whose sources changed. If one of
+    /// the sources does not exist, None is returned, otherwise the kept source is returned.
+    async fn merge_sources(
+        &self,
+        source_id: &str,
+        into_source_id: &str,
+    ) -> ThoughtServiceResult<Option<ThoughtSource>>;
+
+    /// List the thoughts citing a source, oldest first. If the source does not exist, None is
+    /// returned.
+    async fn get_source_thoughts(
+        &self,
+        source_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtEnvelope>>>;
+
+    /// List all the indexed authors, sorted by name.
+    async fn get_authors(&self) -> ThoughtServiceResult<Vec<Author>>;
+
+    /// Retrieve an indexed author, if it does not exist, None is returned.
+    async fn get_author(&self, author_id: &str) -> ThoughtServiceResult<Option<Author>>;
+
+    /// Make an author known by another name. If another author is known by this name, it is
+    /// merged into the given one. It raises a `ThoughtServiceError::InvalidAuthorName` if the
+    /// alias is only made of punctuation and spacing. If the author does not exist, None is
+    /// returned.
+    async fn add_author_alias(
+        &self,
+        author_id: &str,
+        alias: &str,
+    ) -> ThoughtServiceResult<Option<Author>>;
+
+    /// List the sources of an author, sorted by name. If the author does not exist, None is
+    /// returned.
+    async fn get_author_sources(
+        &self,
+        author_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtSource>>>;
+
+    /// List the thoughts citing one of the sources of an author, oldest first. If the author
+    /// does not exist, None is returned.
+    async fn get_author_thoughts(
+        &self,
+        author_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtEnvelope>>>;
+
+    /// Link a thought to another one, whatever their threads. The kind is one of `see_also`,
+    /// `contradicts` or `supersedes`. If the same link already exists, it is returned as is. If
+    /// one of the thoughts does not exist or is deleted, None is returned.
+    async fn create_link(
+        &self,
+        thought_id: &str,
+        to_thought_id: &str,
+        kind: &str,
+    ) -> ThoughtServiceResult<Option<ThoughtLink>>;
+
+    /// Remove a link and return it. If the link does not exist or one of its thoughts is
+    /// deleted, None is returned.
+    async fn remove_link(&self, link_id: &str) -> ThoughtServiceResult<Option<ThoughtLink>>;
+
+    /// List the links from a thought, oldest first. Links to deleted thoughts are left out. If
+    /// the thought does not exist, None is returned.
+    async fn get_links(&self, thought_id: &str) -> ThoughtServiceResult<Option<Vec<ThoughtLink>>>;
+
+    /// List the links pointing to a thought, oldest first. Links from deleted thoughts are left
+    /// out. If the thought does not exist, None is returned.
+    async fn get_backlinks(
+        &self,
+        thought_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtLink>>>;
+
+    /// Attach the file found at the given location to a thought, the file is moved to the blob
+    /// store, it is copied only when it cannot be moved. Only the last component of the file
+    /// name is kept. If the thought does not exist or is deleted, None is returned.
+    async fn add_attachment(
+        &self,
+        thought_id: &str,
+        file_name: &str,
+        mime_type: &str,
+        path: &Path,
+    ) -> ThoughtServiceResult<Option<Attachment>>;
+
+    /// List the attachments of a thought, oldest first. If the thought does not exist or is
+    /// deleted, None is returned.
+    async fn get_attachments(
+        &self,
+        thought_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<Attachment>>>;
+
+    /// Fetch an attachment with the location of its content. If the attachment does not exist
+    /// or its thought is deleted, None is returned.
+    async fn get_attachment(
+        &self,
+        attachment_id: &str,
+    ) -> ThoughtServiceResult<Option<(Attachment, PathBuf)>>;
+
+    /// Remove an attachment and return it. Its content is left to the garbage collector, other
+    /// attachments may share it. If the attachment does not exist or its thought is deleted,
+    /// None is returned.
+    async fn remove_attachment(
+        &self,
+        attachment_id: &str,
+    ) -> ThoughtServiceResult<Option<Attachment>>;
+
+    /// Remove the contents of the blob store no attachment refers to anymore, the attachments of
+    /// the thoughts in the trash included. The hashes of the removed contents are returned.
+    async fn collect_orphan_blobs(&self) -> ThoughtServiceResult<Vec<String>>;
 }
 
 pub struct BackendThoughtService {
     config: Arc<ThoughtServiceConfig>,
     thought_store: Arc<dyn ThoughtStore>,
+    blob_store: BlobStore,
     event_sender: Arc<Mutex<UnboundedSender<EventMessage>>>,
 }
 
@@ -57,47 +476,2051 @@ impl BackendThoughtService {
         thought_store: Arc<dyn ThoughtStore>,
         event_sender: Arc<Mutex<UnboundedSender<EventMessage>>>,
     ) -> Self {
+        let blob_store = BlobStore::new(config.get_attachment_directory().to_path_buf());
+
         Self {
             config,
             thought_store,
+            blob_store,
             event_sender,
         }
     }
+
+    /// Parse a public thought identifier.
+    fn parse_thought_id(&self, thought_id: &str) -> Result<Uuid, ThoughtServiceError> {
+        Uuid::parse_str(thought_id)
+            .map_err(|_| ThoughtServiceError::InvalidIdentifier(thought_id.to_string()))
+    }
+
+    /// Parse a public source identifier.
+    fn parse_source_id(&self, source_id: &str) -> Result<Uuid, ThoughtServiceError> {
+        Uuid::parse_str(source_id)
+            .map_err(|_| ThoughtServiceError::InvalidSourceIdentifier(source_id.to_string()))
+    }
+
+    /// Parse a public link identifier.
+    fn parse_link_id(&self, link_id: &str) -> Result<Uuid, ThoughtServiceError> {
+        Uuid::parse_str(link_id)
+            .map_err(|_| ThoughtServiceError::InvalidLinkIdentifier(link_id.to_string()))
+    }
+
+    /// Parse a public attachment identifier.
+    fn parse_attachment_id(&self, attachment_id: &str) -> Result<Uuid, ThoughtServiceError> {
+        Uuid::parse_str(attachment_id).map_err(|_| {
+            ThoughtServiceError::InvalidAttachmentIdentifier(attachment_id.to_string())
+        })
+    }
+
+    /// Fetch an attachment if its thought is live.
+    async fn get_live_attachment(
+        &self,
+        attachment_id: &Uuid,
+    ) -> ThoughtServiceResult<Option<Attachment>> {
+        let attachment = match self.thought_store.get_attachment(attachment_id).await? {
+            Some(attachment) => attachment,
+            None => return Ok(None),
+        };
+
+        if self
+            .thought_store
+            .get_thought(&attachment.thought_id)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(attachment))
+    }
+
+    /// Parse a public author identifier.
+    fn parse_author_id(&self, author_id: &str) -> Result<Uuid, ThoughtServiceError> {
+        Uuid::parse_str(author_id)
+            .map_err(|_| ThoughtServiceError::InvalidAuthorIdentifier(author_id.to_string()))
+    }
+
+    /// Identifiers of the indexed authors having the given names, the authors unknown so far
+    /// are indexed. Names only made of punctuation and spacing are ignored.
+    async fn resolve_authors(&self, names: &[String]) -> ThoughtServiceResult<Vec<Uuid>> {
+        let mut author_ids: Vec<Uuid> = Vec::with_capacity(names.len());
+
+        for name in names.iter().filter(|n| !Author::alias_key(n).is_empty()) {
+            let author = self
+                .thought_store
+                .insert_author(Author {
+                    author_id: Uuid::new_v4(),
+                    name: name.trim().to_string(),
+                    aliases: Vec::new(),
+                })
+                .await?;
+
+            if !author_ids.contains(&author.author_id) {
+                author_ids.push(author.author_id);
+            }
+        }
+
+        Ok(author_ids)
+    }
+
+    /// Check the parts of a thought sent by a client and build it. The thought must not be in
+    /// the trash, its categories must be valid, its sources and parent must exist.
+    async fn build_thought(
+        &self,
+        thought_id: Uuid,
+        parent_thought_id: Option<String>,
+        draft: ThoughtDraft,
+    ) -> ThoughtServiceResult<ThoughtEnvelope> {
+        let ThoughtDraft {
+            content,
+            content_format,
+            keywords,
+            categories,
+            source_ids,
+        } = draft;
+
+        if self
+            .thought_store
+            .get_trashed_thought(&thought_id)
+            .await?
+            .is_some()
+        {
+            return Err(ThoughtServiceError::ThoughtIsDeleted(
+                thought_id.to_string(),
+            ));
+        }
+
+        if let Some(category) = categories.iter().find(|c| !is_valid_category_path(c)) {
+            return Err(ThoughtServiceError::InvalidCategory(category.to_string()));
+        }
+
+        let mut cited_source_ids: Vec<Uuid> = Vec::with_capacity(source_ids.len());
+
+        for source_id in source_ids {
+            let parsed_id = self.parse_source_id(&source_id)?;
+
+            if self.thought_store.get_source(&parsed_id).await?.is_none() {
+                return Err(ThoughtServiceError::SourceDoesNotExist(source_id));
+            }
+            cited_source_ids.push(parsed_id);
+        }
+
+        let content = match parent_thought_id {
+            Some(parent_thought_id) => {
+                let parent_id = self.parse_thought_id(&parent_thought_id)?;
+
+                if parent_id == thought_id
+                    || self.thought_store.get_thought(&parent_id).await?.is_none()
+                {
+                    return Err(ThoughtServiceError::ParentNodeDoesNotExist(
+                        parent_thought_id,
+                    ));
+                }
+
+                ThoughtContent::Node {
+                    parent_thought_id: parent_id,
+                    thought: content,
+                }
+            }
+            None => ThoughtContent::Thread { title: content },
+        };
+
+        Ok(ThoughtEnvelope {
+            thought_id,
+            keywords,
+            categories,
+            source_ids: cited_source_ids,
+            created_at: Utc::now(),
+            content,
+            content_format,
+        })
+    }
+
+    /// Build the tree below the given thought. One more level than asked is fetched to tell
+    /// which thoughts have children left out.
+    async fn build_tree(
+        &self,
+        thought_id: &Uuid,
+        max_depth: Option<u32>,
+        order: SiblingOrder,
+    ) -> ThoughtServiceResult<Option<ThoughtTree>> {
+        let max_depth = max_depth.unwrap_or(MAX_TREE_DEPTH).min(MAX_TREE_DEPTH);
+        let subtree = self
+            .thought_store
+            .get_subtree(thought_id, Some(max_depth + 1))
+            .await?;
+
+        Ok(subtree.and_then(|thoughts| ThoughtTree::build(thoughts, Some(max_depth), order)))
+    }
+
+    /// Decode an opaque listing cursor.
+    fn parse_cursor(
+        &self,
+        cursor: Option<&str>,
+    ) -> Result<Option<ListingCursor>, ThoughtServiceError> {
+        cursor
+            .map(|c| {
+                ListingCursor::decode(c)
+                    .ok_or_else(|| ThoughtServiceError::InvalidCursor(c.to_string()))
+            })
+            .transpose()
+    }
+
+    /// Advertise a state modification to the other services.
+    async fn notify(&self, action: StateModification) -> ThoughtServiceResult<()> {
+        self.send_event(EVENT_SUBJECT, action).await
+    }
+
+    /// Advertise the creation or removal of a link, identified by its link identifier.
+    async fn notify_link(&self, action: StateModification) -> ThoughtServiceResult<()> {
+        self.send_event(LINK_EVENT_SUBJECT, action).await
+    }
+
+    /// Advertise the creation or removal of an attachment, identified by its attachment
+    /// identifier.
+    async fn notify_attachment(&self, action: StateModification) -> ThoughtServiceResult<()> {
+        self.send_event(ATTACHMENT_EVENT_SUBJECT, action).await
+    }
+
+    async fn send_event(
+        &self,
+        subject: &str,
+        action: StateModification,
+    ) -> ThoughtServiceResult<()> {
+        self.event_sender
+            .lock()
+            .await
+            .send(EventMessage::new(SERVICE_ID, subject, action))
+            .map_err(|e| {
+                ThoughtServiceError::EventDispatch(
+                    anyhow!(e).context(format!("Could not send {subject} event.")),
+                )
+            })
+    }
 }
 
 #[async_trait]
 impl ThoughtService for BackendThoughtService {
-    async fn get_thread(&self, thought_id: &str) -> StdResult<Option<Vec<ThoughtEnvelope>>> {
+    async fn get_thread(
+        &self,
+        thought_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtEnvelope>>> {
         trace!("THOUGHT SERVICE: get_thread({thought_id})");
-        todo!()
+        let thought_id = self.parse_thought_id(thought_id)?;
+
+        self.thought_store
+            .get_thread(&thought_id)
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_thread_tree(
+        &self,
+        thought_id: &str,
+        max_depth: Option<u32>,
+        order: SiblingOrder,
+    ) -> ThoughtServiceResult<Option<ThoughtTree>> {
+        trace!("THOUGHT SERVICE: get_thread_tree({thought_id}, max_depth={max_depth:?}, order={order:?})");
+        let thought_id = self.parse_thought_id(thought_id)?;
+        let root_id = match self.thought_store.get_thread(&thought_id).await? {
+            Some(thread) => thread[0].thought_id,
+            None => return Ok(None),
+        };
+
+        self.build_tree(&root_id, max_depth, order).await
+    }
+
+    async fn get_subtree(
+        &self,
+        thought_id: &str,
+        max_depth: Option<u32>,
+        order: SiblingOrder,
+    ) -> ThoughtServiceResult<Option<ThoughtTree>> {
+        trace!(
+            "THOUGHT SERVICE: get_subtree({thought_id}, max_depth={max_depth:?}, order={order:?})"
+        );
+        let thought_id = self.parse_thought_id(thought_id)?;
+
+        self.build_tree(&thought_id, max_depth, order).await
     }
 
     async fn post_thought(
         &self,
         thought_id: String,
         parent_thought_id: Option<String>,
-        keywords: Vec<String>,
-        categories: Vec<String>,
-        sources: Vec<ThoughtSource>,
-    ) -> StdResult<ThoughtEnvelope> {
+        draft: ThoughtDraft,
+    ) -> ThoughtServiceResult<ThoughtEnvelope> {
         trace!("THOUGHT SERVICE: post_thought(thought_id='{thought_id}')");
-        todo!()
+        let thought_id = self.parse_thought_id(&thought_id)?;
+
+        let envelope = self
+            .build_thought(thought_id, parent_thought_id, draft)
+            .await?;
+        let existing = self.thought_store.get_thought(&thought_id).await?;
+
+        let (thought, action) = if let Some(existing) = existing {
+            if existing.parent_thought_id() != envelope.parent_thought_id() {
+                return Err(ThoughtServiceError::ParentChangedByUpdate(
+                    thought_id.to_string(),
+                ));
+            }
+            // the store only updates the thought if it still has the same parent
+            let thought = self
+                .thought_store
+                .update_thought(envelope)
+                .await?
+                .ok_or_else(|| {
+                    ThoughtStoreError::Conflict(format!(
+                        "Thought '{thought_id}' was moved or deleted while being updated."
+                    ))
+                })?;
+
+            (thought, StateModification::Update(thought_id.to_string()))
+        } else {
+            let thought = self.thought_store.insert_thought(envelope).await?;
+
+            (thought, StateModification::Creation(thought_id.to_string()))
+        };
+        self.notify(action).await?;
+
+        Ok(thought)
+    }
+
+    async fn create_thought(
+        &self,
+        thought_id: String,
+        parent_thought_id: Option<String>,
+        draft: ThoughtDraft,
+    ) -> ThoughtServiceResult<ThoughtEnvelope> {
+        trace!("THOUGHT SERVICE: create_thought(thought_id='{thought_id}')");
+        let thought_id = self.parse_thought_id(&thought_id)?;
+        let envelope = self
+            .build_thought(thought_id, parent_thought_id, draft)
+            .await?;
+        // the store refuses an existing identifier, there is no window between a check and the
+        // insert
+        let thought = match self.thought_store.insert_thought(envelope).await {
+            Ok(thought) => thought,
+            Err(ThoughtStoreError::Conflict(_)) => {
+                return Err(ThoughtServiceError::ThoughtAlreadyExists(
+                    thought_id.to_string(),
+                ))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        self.notify(StateModification::Creation(thought_id.to_string()))
+            .await?;
+
+        Ok(thought)
     }
 
-    async fn get_thought(&self, thought_id: &Uuid) -> StdResult<Option<ThoughtEnvelope>> {
+    async fn get_thought(
+        &self,
+        thought_id: &Uuid,
+    ) -> ThoughtServiceResult<Option<ThoughtEnvelope>> {
         trace!("THOUGHT SERVICE: get_thought({thought_id})");
-        let thought = ThoughtEntity {
-            thought_id: thought_id.to_owned(),
-            parent_thought_id: None,
-            keywords: vec!["keyword_a".to_string(), "keyword_b".to_string()],
-            categories: vec!["cat_1".to_string(), "cat_2".to_string()],
-            sources: vec![
-                r#"{"name": "source_name", "authors": ["source_author"], "description": "source description"}"#.to_string(),
-            ],
-            created_at: chrono::DateTime::UNIX_EPOCH,
-            content: r#"This is the thought content"#.to_string(),
-        };
-
-        Ok(Some(thought.into()))
+
+        self.thought_store
+            .get_thought(thought_id)
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> ThoughtServiceResult<Vec<ThoughtSearchResult>> {
+        trace!("THOUGHT SERVICE: search(query='{query}', limit={limit})");
+
+        if query.trim().is_empty() {
+            return Err(ThoughtServiceError::EmptySearchQuery);
+        }
+
+        self.thought_store
+            .search(query, limit.min(MAX_SEARCH_RESULTS))
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_category_tree(&self) -> ThoughtServiceResult<Vec<CategoryNode>> {
+        trace!("THOUGHT SERVICE: get_category_tree()");
+        let counts = self.thought_store.get_category_counts().await?;
+
+        Ok(CategoryNode::build_tree(counts))
+    }
+
+    async fn get_category_thoughts(
+        &self,
+        path: &str,
+    ) -> ThoughtServiceResult<Vec<ThoughtEnvelope>> {
+        trace!("THOUGHT SERVICE: get_category_thoughts({path})");
+
+        if !is_valid_category_path(path) {
+            return Err(ThoughtServiceError::InvalidCategory(path.to_string()));
+        }
+
+        self.thought_store
+            .get_thoughts_by_category(path)
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn match_category_thoughts(
+        &self,
+        pattern: &str,
+    ) -> ThoughtServiceResult<Vec<ThoughtEnvelope>> {
+        trace!("THOUGHT SERVICE: match_category_thoughts({pattern})");
+
+        if !is_valid_category_pattern(pattern) {
+            return Err(ThoughtServiceError::InvalidCategory(pattern.to_string()));
+        }
+
+        self.thought_store
+            .get_thoughts_by_category_pattern(pattern)
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn list_threads(
+        &self,
+        order: ThreadOrder,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> ThoughtServiceResult<Page<ThreadSummary>> {
+        trace!("THOUGHT SERVICE: list_threads(order={order:?}, cursor={cursor:?}, limit={limit})");
+        if cursor.is_some() && !order.is_pageable() {
+            return Err(ThoughtServiceError::UnpageableOrder);
+        }
+        let after = self.parse_cursor(cursor)?;
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let threads = self
+            .thought_store
+            .list_threads(order, after.as_ref(), limit + 1)
+            .await?;
+        let mut page = Page::from_items(threads, limit, |summary| summary.cursor(order));
+
+        if !order.is_pageable() {
+            page.next_cursor = None;
+        }
+
+        Ok(page)
+    }
+
+    async fn list_thread_thoughts(
+        &self,
+        thread_id: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> ThoughtServiceResult<Option<Page<ThoughtEnvelope>>> {
+        trace!(
+            "THOUGHT SERVICE: list_thread_thoughts({thread_id}, cursor={cursor:?}, limit={limit})"
+        );
+        let thread_id = self.parse_thought_id(thread_id)?;
+        let after = self.parse_cursor(cursor)?;
+        let limit = limit.min(MAX_PAGE_SIZE);
+
+        match self.thought_store.get_thought(&thread_id).await? {
+            Some(thought) if thought.parent_thought_id().is_none() => (),
+            _ => return Ok(None),
+        }
+        let thoughts = self
+            .thought_store
+            .list_thread_thoughts(&thread_id, after.as_ref(), limit + 1)
+            .await?;
+
+        Ok(Some(Page::from_items(thoughts, limit, |thought| {
+            ListingCursor::new(thought.created_at, thought.thought_id)
+        })))
+    }
+
+    async fn delete_thought(
+        &self,
+        thought_id: &str,
+        recursive: bool,
+    ) -> ThoughtServiceResult<Option<Vec<Uuid>>> {
+        trace!("THOUGHT SERVICE: delete_thought({thought_id}, recursive={recursive})");
+        let thought_id = self.parse_thought_id(thought_id)?;
+
+        if self.thought_store.get_thought(&thought_id).await?.is_none() {
+            return Ok(None);
+        }
+        let deleted = self
+            .thought_store
+            .delete_thought(&thought_id, recursive)
+            .await?;
+
+        if deleted.is_empty() {
+            return match recursive {
+                true => Ok(None),
+                false => Err(ThoughtServiceError::ThoughtHasChildren(
+                    thought_id.to_string(),
+                )),
+            };
+        }
+        for deleted_id in &deleted {
+            self.notify(StateModification::Delete(deleted_id.to_string()))
+                .await?;
+        }
+
+        Ok(Some(deleted))
+    }
+
+    async fn move_thought(
+        &self,
+        thought_id: &str,
+        parent_thought_id: Option<&str>,
+    ) -> ThoughtServiceResult<Option<Vec<Uuid>>> {
+        trace!("THOUGHT SERVICE: move_thought({thought_id}, parent={parent_thought_id:?})");
+        let thought_id = self.parse_thought_id(thought_id)?;
+        let parent_thought_id = parent_thought_id
+            .map(|p| self.parse_thought_id(p))
+            .transpose()?;
+        let subtree = match self.thought_store.get_subtree(&thought_id, None).await? {
+            Some(subtree) => subtree,
+            None => return Ok(None),
+        };
+
+        if let Some(parent_id) = parent_thought_id {
+            if subtree.iter().any(|t| t.thought_id == parent_id) {
+                return Err(ThoughtServiceError::ThoughtMovedUnderItself(
+                    thought_id.to_string(),
+                ));
+            }
+            if self.thought_store.get_thought(&parent_id).await?.is_none() {
+                return Err(ThoughtServiceError::ParentNodeDoesNotExist(
+                    parent_id.to_string(),
+                ));
+            }
+        }
+        // the store checks again within its transaction, the tree may have changed meanwhile
+        let moved = match self
+            .thought_store
+            .move_thought(&thought_id, parent_thought_id.as_ref())
+            .await?
+        {
+            Some(moved) => moved,
+            None => return Ok(None),
+        };
+
+        for moved_id in &moved {
+            self.notify(StateModification::Update(moved_id.to_string()))
+                .await?;
+        }
+
+        Ok(Some(moved))
+    }
+
+    async fn merge_threads(
+        &self,
+        thread_id: &str,
+        into_thought_id: &str,
+        mode: ThreadMergeMode,
+    ) -> ThoughtServiceResult<Option<ThreadMerge>> {
+        trace!(
+            "THOUGHT SERVICE: merge_threads({thread_id}, into={into_thought_id}, mode={mode:?})"
+        );
+        let thread_id = self.parse_thought_id(thread_id)?;
+        let into_thought_id = self.parse_thought_id(into_thought_id)?;
+
+        match self.thought_store.get_thought(&thread_id).await? {
+            Some(thought) if thought.parent_thought_id().is_some() => {
+                return Err(ThoughtServiceError::NotAThreadRoot(thread_id.to_string()));
+            }
+            Some(_) => (),
+            None => return Ok(None),
+        }
+        let into_root_id = match self.thought_store.get_thread(&into_thought_id).await? {
+            Some(thread) => thread[0].thought_id,
+            None => return Ok(None),
+        };
+
+        if into_root_id == thread_id {
+            return Err(ThoughtServiceError::ThreadMergedIntoItself(
+                thread_id.to_string(),
+            ));
+        }
+        let merge = match mode {
+            ThreadMergeMode::Graft => self
+                .thought_store
+                .move_thought(&thread_id, Some(&into_thought_id))
+                .await?
+                .map(|moved| ThreadMerge {
+                    updated_thought_ids: moved,
+                    ..ThreadMerge::default()
+                }),
+            ThreadMergeMode::Combine => {
+                self.thought_store
+                    .combine_threads(&into_root_id, &thread_id)
+                    .await?
+            }
+        };
+        let merge = match merge {
+            Some(merge) => merge,
+            None => return Ok(None),
+        };
+
+        for updated_id in &merge.updated_thought_ids {
+            self.notify(StateModification::Update(updated_id.to_string()))
+                .await?;
+        }
+        if let Some(deleted_id) = merge.deleted_thought_id {
+            self.notify(StateModification::Delete(deleted_id.to_string()))
+                .await?;
+        }
+        for link_id in &merge.redirected_link_ids {
+            self.notify_link(StateModification::Update(link_id.to_string()))
+                .await?;
+        }
+        for link_id in &merge.removed_link_ids {
+            self.notify_link(StateModification::Delete(link_id.to_string()))
+                .await?;
+        }
+        for attachment_id in &merge.moved_attachment_ids {
+            self.notify_attachment(StateModification::Update(attachment_id.to_string()))
+                .await?;
+        }
+
+        Ok(Some(merge))
+    }
+
+    async fn get_trash(&self) -> ThoughtServiceResult<Vec<TrashedThought>> {
+        trace!("THOUGHT SERVICE: get_trash()");
+
+        self.thought_store
+            .list_trash()
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn restore_thought(&self, thought_id: &str) -> ThoughtServiceResult<Option<Vec<Uuid>>> {
+        trace!("THOUGHT SERVICE: restore_thought({thought_id})");
+        let thought_id = self.parse_thought_id(thought_id)?;
+        let trashed = match self.thought_store.get_trashed_thought(&thought_id).await? {
+            Some(trashed) => trashed,
+            None => return Ok(None),
+        };
+
+        if let Some(parent_id) = trashed.thought.parent_thought_id() {
+            if self.thought_store.get_thought(&parent_id).await?.is_none() {
+                return Err(ThoughtServiceError::ParentNodeDoesNotExist(
+                    parent_id.to_string(),
+                ));
+            }
+        }
+        let restored = self.thought_store.restore_thought(&thought_id).await?;
+
+        for restored_id in &restored {
+            self.notify(StateModification::Creation(restored_id.to_string()))
+                .await?;
+        }
+
+        Ok(Some(restored))
+    }
+
+    async fn purge_trash(&self) -> ThoughtServiceResult<Vec<Uuid>> {
+        let retention =
+            chrono::Duration::from_std(self.config.get_trash_retention()).map_err(|e| {
+                ThoughtServiceError::InvalidConfiguration(format!("trash retention, {e}"))
+            })?;
+        let before = Utc::now().checked_sub_signed(retention).ok_or_else(|| {
+            ThoughtServiceError::InvalidConfiguration("trash retention, out of range".to_string())
+        })?;
+        trace!("THOUGHT SERVICE: purge_trash(before={before})");
+        // the attachments are removed along with their thought, they are collected beforehand
+        // to advertise their removal
+        let mut attachments: Vec<Attachment> = Vec::new();
+
+        for trashed in self.thought_store.list_trash().await? {
+            if trashed.deleted_at < before {
+                attachments.extend(
+                    self.thought_store
+                        .get_attachments(&trashed.thought.thought_id)
+                        .await?,
+                );
+            }
+        }
+        let purged = self.thought_store.purge_trash(&before).await?;
+
+        for purged_id in &purged {
+            self.notify(StateModification::Delete(purged_id.to_string()))
+                .await?;
+        }
+        for attachment in attachments
+            .iter()
+            .filter(|a| purged.contains(&a.thought_id))
+        {
+            self.notify_attachment(StateModification::Delete(
+                attachment.attachment_id.to_string(),
+            ))
+            .await?;
+        }
+
+        Ok(purged)
+    }
+
+    async fn get_revisions(
+        &self,
+        thought_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtRevision>>> {
+        trace!("THOUGHT SERVICE: get_revisions({thought_id})");
+        let thought_id = self.parse_thought_id(thought_id)?;
+        let thought = match self.thought_store.get_thought(&thought_id).await? {
+            Some(thought) => thought,
+            None => return Ok(None),
+        };
+        let mut revisions = self.thought_store.get_revisions(&thought_id).await?;
+        let current = ThoughtRevision::current(&thought, &revisions);
+        revisions.push(current);
+
+        Ok(Some(revisions))
+    }
+
+    async fn get_revision(
+        &self,
+        thought_id: &str,
+        revision: u32,
+    ) -> ThoughtServiceResult<Option<ThoughtRevision>> {
+        trace!("THOUGHT SERVICE: get_revision({thought_id}, {revision})");
+
+        Ok(self
+            .get_revisions(thought_id)
+            .await?
+            .and_then(|revisions| revisions.into_iter().find(|r| r.revision == revision)))
+    }
+
+    async fn diff_revisions(
+        &self,
+        thought_id: &str,
+        from_revision: u32,
+        to_revision: u32,
+    ) -> ThoughtServiceResult<Option<RevisionDiff>> {
+        trace!("THOUGHT SERVICE: diff_revisions({thought_id}, {from_revision}, {to_revision})");
+        let revisions = match self.get_revisions(thought_id).await? {
+            Some(revisions) => revisions,
+            None => return Ok(None),
+        };
+        let find = |revision: u32| revisions.iter().find(|r| r.revision == revision);
+
+        Ok(find(from_revision)
+            .zip(find(to_revision))
+            .map(|(from, to)| RevisionDiff::new(from, to)))
+    }
+
+    async fn register_source(
+        &self,
+        name: String,
+        authors: Vec<String>,
+        description: String,
+    ) -> ThoughtServiceResult<ThoughtSource> {
+        trace!("THOUGHT SERVICE: register_source(name='{name}')");
+        let source_id = Uuid::new_v4();
+        let author_ids = self.resolve_authors(&authors).await?;
+        let source = self
+            .thought_store
+            .insert_source(ThoughtSource {
+                source_id,
+                name,
+                authors,
+                description,
+                author_ids,
+            })
+            .await?;
+
+        if source.source_id != source_id {
+            debug!(
+                "Source '{}' is already registered as '{}'.",
+                source.name, source.source_id
+            );
+        }
+
+        Ok(source)
+    }
+
+    async fn get_source(&self, source_id: &str) -> ThoughtServiceResult<Option<ThoughtSource>> {
+        trace!("THOUGHT SERVICE: get_source({source_id})");
+        let source_id = self.parse_source_id(source_id)?;
+
+        self.thought_store
+            .get_source(&source_id)
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_sources(&self) -> ThoughtServiceResult<Vec<ThoughtSource>> {
+        trace!("THOUGHT SERVICE: get_sources()");
+
+        self.thought_store
+            .get_sources()
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn find_duplicate_sources(&self) -> ThoughtServiceResult<Vec<Vec<ThoughtSource>>> {
+        trace!("THOUGHT SERVICE: find_duplicate_sources()");
+        let sources = self.thought_store.get_sources().await?;
+
+        Ok(ThoughtSource::group_duplicates(sources))
+    }
+
+    async fn merge_sources(
+        &self,
+        source_id: &str,
+        into_source_id: &str,
+    ) -> ThoughtServiceResult<Option<ThoughtSource>> {
+        trace!("THOUGHT SERVICE: merge_sources({source_id}, into={into_source_id})");
+        let absorbed_id = self.parse_source_id(source_id)?;
+        let kept_id = self.parse_source_id(into_source_id)?;
+
+        if absorbed_id == kept_id {
+            return Err(ThoughtServiceError::SourceMergedIntoItself(
+                source_id.to_string(),
+            ));
+        }
+        let kept = match (
+            self.thought_store.get_source(&absorbed_id).await?,
+            self.thought_store.get_source(&kept_id).await?,
+        ) {
+            (Some(_), Some(kept)) => kept,
+            _ => return Ok(None),
+        };
+        let updated = self
+            .thought_store
+            .merge_sources(&kept_id, &absorbed_id)
+            .await?;
+
+        for updated_id in &updated {
+            self.notify(StateModification::Update(updated_id.to_string()))
+                .await?;
+        }
+
+        Ok(Some(kept))
+    }
+
+    async fn get_source_thoughts(
+        &self,
+        source_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtEnvelope>>> {
+        trace!("THOUGHT SERVICE: get_source_thoughts({source_id})");
+        let source_id = self.parse_source_id(source_id)?;
+
+        if self.thought_store.get_source(&source_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.thought_store
+            .get_thoughts_by_source(&source_id)
+            .await
+            .map(Some)
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_authors(&self) -> ThoughtServiceResult<Vec<Author>> {
+        trace!("THOUGHT SERVICE: get_authors()");
+
+        self.thought_store
+            .get_authors()
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_author(&self, author_id: &str) -> ThoughtServiceResult<Option<Author>> {
+        trace!("THOUGHT SERVICE: get_author({author_id})");
+        let author_id = self.parse_author_id(author_id)?;
+
+        self.thought_store
+            .get_author(&author_id)
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn add_author_alias(
+        &self,
+        author_id: &str,
+        alias: &str,
+    ) -> ThoughtServiceResult<Option<Author>> {
+        trace!("THOUGHT SERVICE: add_author_alias({author_id}, alias='{alias}')");
+        let author_id = self.parse_author_id(author_id)?;
+
+        if Author::alias_key(alias).is_empty() {
+            return Err(ThoughtServiceError::InvalidAuthorName(alias.to_string()));
+        }
+        if self.thought_store.get_author(&author_id).await?.is_none() {
+            return Ok(None);
+        }
+        self.thought_store
+            .add_author_alias(&author_id, alias.trim())
+            .await?;
+
+        self.thought_store
+            .get_author(&author_id)
+            .await
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_author_sources(
+        &self,
+        author_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtSource>>> {
+        trace!("THOUGHT SERVICE: get_author_sources({author_id})");
+        let author_id = self.parse_author_id(author_id)?;
+
+        if self.thought_store.get_author(&author_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.thought_store
+            .get_sources_by_author(&author_id)
+            .await
+            .map(Some)
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_author_thoughts(
+        &self,
+        author_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtEnvelope>>> {
+        trace!("THOUGHT SERVICE: get_author_thoughts({author_id})");
+        let author_id = self.parse_author_id(author_id)?;
+
+        if self.thought_store.get_author(&author_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.thought_store
+            .get_thoughts_by_author(&author_id)
+            .await
+            .map(Some)
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn create_link(
+        &self,
+        thought_id: &str,
+        to_thought_id: &str,
+        kind: &str,
+    ) -> ThoughtServiceResult<Option<ThoughtLink>> {
+        trace!("THOUGHT SERVICE: create_link({thought_id}, to={to_thought_id}, kind={kind})");
+        let from_thought_id = self.parse_thought_id(thought_id)?;
+        let to_thought_id = self.parse_thought_id(to_thought_id)?;
+        let kind = LinkKind::parse(kind)
+            .ok_or_else(|| ThoughtServiceError::InvalidLinkKind(kind.to_string()))?;
+
+        if from_thought_id == to_thought_id {
+            return Err(ThoughtServiceError::ThoughtLinkedToItself(
+                thought_id.to_string(),
+            ));
+        }
+        if self
+            .thought_store
+            .get_thought(&from_thought_id)
+            .await?
+            .is_none()
+            || self
+                .thought_store
+                .get_thought(&to_thought_id)
+                .await?
+                .is_none()
+        {
+            return Ok(None);
+        }
+        let existing = self
+            .thought_store
+            .get_outgoing_links(&from_thought_id)
+            .await?
+            .into_iter()
+            .find(|link| link.to_thought_id == to_thought_id && link.kind == kind);
+
+        if existing.is_some() {
+            return Ok(existing);
+        }
+        let link = self
+            .thought_store
+            .insert_link(ThoughtLink {
+                link_id: Uuid::new_v4(),
+                from_thought_id,
+                to_thought_id,
+                kind,
+                created_at: Utc::now(),
+            })
+            .await?;
+        self.notify_link(StateModification::Creation(link.link_id.to_string()))
+            .await?;
+
+        Ok(Some(link))
+    }
+
+    async fn remove_link(&self, link_id: &str) -> ThoughtServiceResult<Option<ThoughtLink>> {
+        trace!("THOUGHT SERVICE: remove_link({link_id})");
+        let link_id = self.parse_link_id(link_id)?;
+        let link = match self.thought_store.get_link(&link_id).await? {
+            Some(link) => link,
+            None => return Ok(None),
+        };
+
+        if !self.thought_store.delete_link(&link_id).await? {
+            return Ok(None);
+        }
+        self.notify_link(StateModification::Delete(link_id.to_string()))
+            .await?;
+
+        Ok(Some(link))
+    }
+
+    async fn get_links(&self, thought_id: &str) -> ThoughtServiceResult<Option<Vec<ThoughtLink>>> {
+        trace!("THOUGHT SERVICE: get_links({thought_id})");
+        let thought_id = self.parse_thought_id(thought_id)?;
+
+        if self.thought_store.get_thought(&thought_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.thought_store
+            .get_outgoing_links(&thought_id)
+            .await
+            .map(Some)
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_backlinks(
+        &self,
+        thought_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<ThoughtLink>>> {
+        trace!("THOUGHT SERVICE: get_backlinks({thought_id})");
+        let thought_id = self.parse_thought_id(thought_id)?;
+
+        if self.thought_store.get_thought(&thought_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.thought_store
+            .get_backlinks(&thought_id)
+            .await
+            .map(Some)
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn add_attachment(
+        &self,
+        thought_id: &str,
+        file_name: &str,
+        mime_type: &str,
+        path: &Path,
+    ) -> ThoughtServiceResult<Option<Attachment>> {
+        trace!(
+            "THOUGHT SERVICE: add_attachment({thought_id}, file_name={file_name}, mime_type={mime_type})"
+        );
+        let thought_id = self.parse_thought_id(thought_id)?;
+        let file_name = sanitize_file_name(file_name)
+            .ok_or_else(|| ThoughtServiceError::InvalidFileName(file_name.to_string()))?;
+
+        if !is_valid_mime_type(mime_type) {
+            return Err(ThoughtServiceError::InvalidMimeType(mime_type.to_string()));
+        }
+        if self.thought_store.get_thought(&thought_id).await?.is_none() {
+            return Ok(None);
+        }
+        let blob = self
+            .blob_store
+            .store_file(path)
+            .await
+            .map_err(ThoughtServiceError::BlobStore)?;
+        debug!(
+            "Stored attachment content '{}' ({} bytes).",
+            blob.hash, blob.size
+        );
+        let attachment = self
+            .thought_store
+            .insert_attachment(Attachment {
+                attachment_id: Uuid::new_v4(),
+                thought_id,
+                file_name,
+                mime_type: mime_type.trim().to_string(),
+                size: blob.size,
+                hash: blob.hash,
+                created_at: Utc::now(),
+            })
+            .await?;
+        self.notify_attachment(StateModification::Creation(
+            attachment.attachment_id.to_string(),
+        ))
+        .await?;
+
+        Ok(Some(attachment))
+    }
+
+    async fn get_attachments(
+        &self,
+        thought_id: &str,
+    ) -> ThoughtServiceResult<Option<Vec<Attachment>>> {
+        trace!("THOUGHT SERVICE: get_attachments({thought_id})");
+        let thought_id = self.parse_thought_id(thought_id)?;
+
+        if self.thought_store.get_thought(&thought_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.thought_store
+            .get_attachments(&thought_id)
+            .await
+            .map(Some)
+            .map_err(ThoughtServiceError::Store)
+    }
+
+    async fn get_attachment(
+        &self,
+        attachment_id: &str,
+    ) -> ThoughtServiceResult<Option<(Attachment, PathBuf)>> {
+        trace!("THOUGHT SERVICE: get_attachment({attachment_id})");
+        let attachment_id = self.parse_attachment_id(attachment_id)?;
+        let attachment = match self.get_live_attachment(&attachment_id).await? {
+            Some(attachment) => attachment,
+            None => return Ok(None),
+        };
+        let path = self
+            .blob_store
+            .get_path(&attachment.hash)
+            .map_err(ThoughtServiceError::BlobStore)?;
+
+        Ok(Some((attachment, path)))
+    }
+
+    async fn remove_attachment(
+        &self,
+        attachment_id: &str,
+    ) -> ThoughtServiceResult<Option<Attachment>> {
+        trace!("THOUGHT SERVICE: remove_attachment({attachment_id})");
+        let attachment_id = self.parse_attachment_id(attachment_id)?;
+        let attachment = match self.get_live_attachment(&attachment_id).await? {
+            Some(attachment) => attachment,
+            None => return Ok(None),
+        };
+
+        if !self.thought_store.delete_attachment(&attachment_id).await? {
+            return Ok(None);
+        }
+        self.notify_attachment(StateModification::Delete(attachment_id.to_string()))
+            .await?;
+
+        Ok(Some(attachment))
+    }
+
+    async fn collect_orphan_blobs(&self) -> ThoughtServiceResult<Vec<String>> {
+        trace!("THOUGHT SERVICE: collect_orphan_blobs()");
+        let referenced: HashSet<String> = self
+            .thought_store
+            .get_attachment_hashes()
+            .await?
+            .into_iter()
+            .collect();
+        let removed = self
+            .blob_store
+            .collect_garbage(&referenced, BLOB_GRACE_PERIOD)
+            .await
+            .map_err(ThoughtServiceError::BlobStore)?;
+        debug!("Removed {} orphaned attachment contents.", removed.len());
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flat_config::{pool::SimpleFlatPool, ConfigBuilder};
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    use crate::{
+        thoughts::{model::MemoryThoughtStore, ThoughtServiceConfigBuilder},
+        StdResult,
+    };
+
+    use super::*;
+
+    fn get_service() -> (BackendThoughtService, UnboundedReceiver<EventMessage>) {
+        get_service_with_store(Arc::new(MemoryThoughtStore::default()))
+    }
+
+    fn get_service_with_store(
+        store: Arc<MemoryThoughtStore>,
+    ) -> (BackendThoughtService, UnboundedReceiver<EventMessage>) {
+        let mut flat_pool = SimpleFlatPool::default();
+        flat_pool
+            .add("database_dsn", "memory://".into())
+            .add("database_pool_size", 1_isize.into())
+            .add("database_pool_timeout", 1_isize.into())
+            .add("database_pool_recycling", "fast".into())
+            .add(
+                "attachment_directory",
+                std::env::temp_dir()
+                    .join(format!("omstasher-attachments-{}", Uuid::new_v4()))
+                    .display()
+                    .to_string()
+                    .as_str()
+                    .into(),
+            );
+        let config = ThoughtServiceConfigBuilder::default()
+            .build(&flat_pool)
+            .unwrap();
+        let (sender, receiver) = unbounded_channel::<EventMessage>();
+        let service =
+            BackendThoughtService::new(Arc::new(config), store, Arc::new(Mutex::new(sender)));
+
+        (service, receiver)
+    }
+
+    fn draft(content: &str) -> ThoughtDraft {
+        ThoughtDraft {
+            content: content.to_string(),
+            ..ThoughtDraft::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn post_thought_creates_then_updates() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let thought_id = Uuid::new_v4().to_string();
+
+        service
+            .post_thought(thought_id.clone(), None, draft("title"))
+            .await?;
+        assert_eq!(
+            StateModification::Creation(thought_id.clone()),
+            receiver.recv().await.unwrap().action
+        );
+
+        let thought = service
+            .post_thought(
+                thought_id.clone(),
+                None,
+                ThoughtDraft {
+                    keywords: vec!["keyword".to_string()],
+                    ..draft("new title")
+                },
+            )
+            .await?;
+        assert_eq!(vec!["keyword".to_string()], thought.keywords);
+        assert_eq!(
+            StateModification::Update(thought_id),
+            receiver.recv().await.unwrap().action
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_thought_never_updates() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let thought_id = Uuid::new_v4().to_string();
+        let create = |title: &str| service.create_thought(thought_id.clone(), None, draft(title));
+
+        create("title").await?;
+        assert_eq!(
+            StateModification::Creation(thought_id.clone()),
+            receiver.recv().await.unwrap().action
+        );
+
+        let error = create("new title").await.unwrap_err();
+        assert!(matches!(
+            error,
+            ThoughtServiceError::ThoughtAlreadyExists(_)
+        ));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(
+            "title",
+            service
+                .get_thought(&Uuid::parse_str(&thought_id)?)
+                .await?
+                .unwrap()
+                .text()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn post_thought_with_unknown_parent() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let error = service
+            .post_thought(
+                Uuid::new_v4().to_string(),
+                Some(Uuid::new_v4().to_string()),
+                draft("content"),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            ThoughtServiceError::ParentNodeDoesNotExist(_)
+        ));
+        assert!(receiver.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_thread_from_posted_thoughts() -> StdResult<()> {
+        let (service, _receiver) = get_service();
+        let root_id = Uuid::new_v4().to_string();
+        let node_id = Uuid::new_v4().to_string();
+
+        service
+            .post_thought(root_id.clone(), None, draft("title"))
+            .await?;
+        service
+            .post_thought(node_id.clone(), Some(root_id.clone()), draft("content"))
+            .await?;
+
+        let thread = service.get_thread(&node_id).await?.unwrap();
+
+        assert_eq!(2, thread.len());
+        assert_eq!(root_id, thread[0].thought_id.to_string());
+        assert_eq!(node_id, thread[1].thought_id.to_string());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn categories_are_validated_and_browsed() -> StdResult<()> {
+        let (service, _receiver) = get_service();
+        let error = service
+            .post_thought(
+                Uuid::new_v4().to_string(),
+                None,
+                ThoughtDraft {
+                    categories: vec!["philosophy..ethics".to_string()],
+                    ..draft("title")
+                },
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ThoughtServiceError::InvalidCategory(_)));
+
+        service
+            .post_thought(
+                Uuid::new_v4().to_string(),
+                None,
+                ThoughtDraft {
+                    categories: vec!["philosophy.ethics".to_string()],
+                    ..draft("title")
+                },
+            )
+            .await?;
+        let tree = service.get_category_tree().await?;
+
+        assert_eq!(1, tree.len());
+        assert_eq!(1, tree[0].children[0].thought_count);
+        assert_eq!(1, service.get_category_thoughts("philosophy").await?.len());
+        assert_eq!(1, service.match_category_thoughts("*.ethics").await?.len());
+        service
+            .get_category_thoughts("philosophy.*")
+            .await
+            .unwrap_err();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_threads_by_pages() -> StdResult<()> {
+        let (service, _receiver) = get_service();
+
+        for title in ["first", "second", "third"] {
+            service
+                .post_thought(Uuid::new_v4().to_string(), None, draft(title))
+                .await?;
+        }
+
+        let first_page = service
+            .list_threads(ThreadOrder::CreatedAt, None, 2)
+            .await?;
+        assert_eq!(2, first_page.items.len());
+
+        let second_page = service
+            .list_threads(ThreadOrder::CreatedAt, first_page.next_cursor.as_deref(), 2)
+            .await?;
+        assert_eq!(1, second_page.items.len());
+        assert_eq!(None, second_page.next_cursor);
+
+        let error = service
+            .list_threads(ThreadOrder::CreatedAt, Some("garbage"), 2)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::InvalidCursor(_)));
+
+        let by_activity = service
+            .list_threads(ThreadOrder::LastActivity, None, 2)
+            .await?;
+        assert_eq!(2, by_activity.items.len());
+        assert_eq!(None, by_activity.next_cursor);
+
+        let error = service
+            .list_threads(
+                ThreadOrder::LastActivity,
+                first_page.next_cursor.as_deref(),
+                2,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::UnpageableOrder));
+        assert_eq!(
+            None,
+            service
+                .list_thread_thoughts(&Uuid::new_v4().to_string(), None, 2)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_and_restore_thoughts() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let root_id = Uuid::new_v4().to_string();
+        let node_id = Uuid::new_v4().to_string();
+
+        service
+            .post_thought(root_id.clone(), None, draft("title"))
+            .await?;
+        service
+            .post_thought(node_id.clone(), Some(root_id.clone()), draft("content"))
+            .await?;
+        while receiver.try_recv().is_ok() {}
+
+        let error = service.delete_thought(&root_id, false).await.unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::ThoughtHasChildren(_)));
+
+        assert_eq!(
+            Some(1),
+            service
+                .delete_thought(&node_id, false)
+                .await?
+                .map(|d| d.len())
+        );
+        assert_eq!(
+            StateModification::Delete(node_id.clone()),
+            receiver.recv().await.unwrap().action
+        );
+        assert_eq!(None, service.get_thread(&node_id).await?);
+        assert_eq!(1, service.get_trash().await?.len());
+
+        let error = service
+            .post_thought(node_id.clone(), Some(root_id.clone()), draft("content"))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::ThoughtIsDeleted(_)));
+
+        assert_eq!(
+            Some(1),
+            service.restore_thought(&node_id).await?.map(|r| r.len())
+        );
+        assert_eq!(
+            StateModification::Creation(node_id.clone()),
+            receiver.recv().await.unwrap().action
+        );
+        assert_eq!(2, service.get_thread(&node_id).await?.unwrap().len());
+        assert_eq!(None, service.restore_thought(&node_id).await?);
+        assert!(service.purge_trash().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn revisions_of_an_updated_thought() -> StdResult<()> {
+        let (service, _receiver) = get_service();
+        let thought_id = Uuid::new_v4().to_string();
+
+        for title in ["stoic ethics", "stoic physics"] {
+            service
+                .post_thought(thought_id.clone(), None, draft(title))
+                .await?;
+        }
+        let revisions = service.get_revisions(&thought_id).await?.unwrap();
+
+        assert_eq!(2, revisions.len());
+        assert_eq!("stoic physics", revisions[1].text);
+        assert_eq!(None, revisions[1].superseded_at);
+        assert_eq!(
+            Some("stoic ethics".to_string()),
+            service.get_revision(&thought_id, 1).await?.map(|r| r.text)
+        );
+        assert_eq!(None, service.get_revision(&thought_id, 3).await?);
+
+        let diff = service.diff_revisions(&thought_id, 1, 2).await?.unwrap();
+        assert_eq!(3, diff.text.len());
+        assert_eq!(None, service.diff_revisions(&thought_id, 1, 3).await?);
+        assert_eq!(
+            None,
+            service.get_revisions(&Uuid::new_v4().to_string()).await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sources_are_deduplicated_and_merged() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let meditations = service
+            .register_source(
+                "Meditations".to_string(),
+                vec!["Marcus Aurelius".to_string()],
+                String::new(),
+            )
+            .await?;
+        let same = service
+            .register_source(
+                "meditations".to_string(),
+                vec!["marcus aurelius".to_string()],
+                "duplicate".to_string(),
+            )
+            .await?;
+        assert_eq!(meditations, same);
+
+        let misspelled = service
+            .register_source(
+                "Meditation".to_string(),
+                vec!["Marcus Aurelius".to_string()],
+                String::new(),
+            )
+            .await?;
+        let thought_id = Uuid::new_v4().to_string();
+        service
+            .post_thought(
+                thought_id.clone(),
+                None,
+                ThoughtDraft {
+                    source_ids: vec![misspelled.source_id.to_string()],
+                    ..draft("title")
+                },
+            )
+            .await?;
+        while receiver.try_recv().is_ok() {}
+
+        let error = service
+            .post_thought(
+                Uuid::new_v4().to_string(),
+                None,
+                ThoughtDraft {
+                    source_ids: vec![Uuid::new_v4().to_string()],
+                    ..draft("title")
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::SourceDoesNotExist(_)));
+
+        let kept = service
+            .merge_sources(
+                &misspelled.source_id.to_string(),
+                &meditations.source_id.to_string(),
+            )
+            .await?;
+        assert_eq!(Some(meditations.clone()), kept);
+        assert_eq!(
+            StateModification::Update(thought_id.clone()),
+            receiver.recv().await.unwrap().action
+        );
+        assert_eq!(1, service.get_sources().await?.len());
+        assert_eq!(
+            None,
+            service
+                .get_source_thoughts(&misspelled.source_id.to_string())
+                .await?
+        );
+        assert_eq!(
+            1,
+            service
+                .get_source_thoughts(&meditations.source_id.to_string())
+                .await?
+                .unwrap()
+                .len()
+        );
+        assert!(service.find_duplicate_sources().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn authors_are_indexed_from_sources() -> StdResult<()> {
+        let (service, _receiver) = get_service();
+        let meditations = service
+            .register_source(
+                "Meditations".to_string(),
+                vec!["Marcus Aurelius".to_string()],
+                String::new(),
+            )
+            .await?;
+        let pensees = service
+            .register_source(
+                "Pensées pour moi-même".to_string(),
+                vec!["Marc Aurèle".to_string()],
+                String::new(),
+            )
+            .await?;
+        let thought_id = Uuid::new_v4().to_string();
+        service
+            .post_thought(
+                thought_id.clone(),
+                None,
+                ThoughtDraft {
+                    source_ids: vec![pensees.source_id.to_string()],
+                    ..draft("title")
+                },
+            )
+            .await?;
+        assert_eq!(2, service.get_authors().await?.len());
+
+        let author_id = meditations.author_ids[0].to_string();
+        let error = service
+            .add_author_alias(&author_id, " . ")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::InvalidAuthorName(_)));
+
+        let author = service
+            .add_author_alias(&author_id, "Marc Aurèle")
+            .await?
+            .unwrap();
+        assert_eq!(2, author.aliases.len());
+        assert_eq!(1, service.get_authors().await?.len());
+        assert_eq!(
+            2,
+            service.get_author_sources(&author_id).await?.unwrap().len()
+        );
+        assert_eq!(
+            vec![thought_id],
+            service
+                .get_author_thoughts(&author_id)
+                .await?
+                .unwrap()
+                .into_iter()
+                .map(|t| t.thought_id.to_string())
+                .collect::<Vec<String>>()
+        );
+
+        let letters = service
+            .register_source(
+                "Letters".to_string(),
+                vec!["marc aurèle".to_string()],
+                String::new(),
+            )
+            .await?;
+        assert_eq!(meditations.author_ids, letters.author_ids);
+        assert_eq!(
+            None,
+            service
+                .get_author_sources(&Uuid::new_v4().to_string())
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn links_are_created_listed_and_removed() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let (first_id, second_id) = (Uuid::new_v4().to_string(), Uuid::new_v4().to_string());
+
+        for thought_id in [&first_id, &second_id] {
+            service
+                .post_thought(thought_id.clone(), None, draft("title"))
+                .await?;
+        }
+        while receiver.try_recv().is_ok() {}
+
+        let error = service
+            .create_link(&first_id, &second_id, "refutes")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::InvalidLinkKind(_)));
+        let error = service
+            .create_link(&first_id, &first_id, "see_also")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ThoughtServiceError::ThoughtLinkedToItself(_)
+        ));
+        assert_eq!(
+            None,
+            service
+                .create_link(&first_id, &Uuid::new_v4().to_string(), "see_also")
+                .await?
+        );
+
+        let link = service
+            .create_link(&first_id, &second_id, "supersedes")
+            .await?
+            .unwrap();
+        let event = receiver.recv().await.unwrap();
+        assert_eq!("thought_link", event.subject);
+        assert_eq!(
+            StateModification::Creation(link.link_id.to_string()),
+            event.action
+        );
+        assert_eq!(
+            Some(link.clone()),
+            service
+                .create_link(&first_id, &second_id, "supersedes")
+                .await?
+        );
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(
+            Some(vec![link.clone()]),
+            service.get_links(&first_id).await?
+        );
+        assert_eq!(
+            Some(vec![link.clone()]),
+            service.get_backlinks(&second_id).await?
+        );
+        assert_eq!(Some(Vec::new()), service.get_backlinks(&first_id).await?);
+
+        let link_id = link.link_id.to_string();
+        assert_eq!(Some(link), service.remove_link(&link_id).await?);
+        assert_eq!(
+            StateModification::Delete(link_id.clone()),
+            receiver.recv().await.unwrap().action
+        );
+        assert_eq!(None, service.remove_link(&link_id).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn thread_tree_from_any_thought() -> StdResult<()> {
+        let store = Arc::new(MemoryThoughtStore::default());
+        let (service, _receiver) = get_service_with_store(store.clone());
+        let post = |thought_id: &str, parent_thought_id: Option<&str>| {
+            service.post_thought(
+                thought_id.to_string(),
+                parent_thought_id.map(|p| p.to_string()),
+                draft("text"),
+            )
+        };
+        let root_id = Uuid::new_v4().to_string();
+        let first_id = Uuid::new_v4().to_string();
+        let second_id = Uuid::new_v4().to_string();
+        let leaf_id = Uuid::new_v4().to_string();
+        let at = |seconds| Some(chrono::DateTime::UNIX_EPOCH + chrono::Duration::seconds(seconds));
+        store.set_clock(at(1));
+        post(&root_id, None).await?;
+        post(&first_id, Some(&root_id)).await?;
+        // siblings are sorted by creation date
+        store.set_clock(at(2));
+        post(&second_id, Some(&root_id)).await?;
+        post(&leaf_id, Some(&first_id)).await?;
+
+        let tree = service
+            .get_thread_tree(&leaf_id, None, SiblingOrder::NewestFirst)
+            .await?
+            .unwrap();
+        assert_eq!(root_id, tree.thought.thought_id.to_string());
+        assert_eq!(4, tree.thought_count());
+        assert_eq!(
+            vec![second_id.clone(), first_id.clone()],
+            tree.children
+                .iter()
+                .map(|c| c.thought.thought_id.to_string())
+                .collect::<Vec<String>>()
+        );
+
+        let tree = service
+            .get_thread_tree(&second_id, Some(1), SiblingOrder::OldestFirst)
+            .await?
+            .unwrap();
+        assert_eq!(3, tree.thought_count());
+        assert!(tree.children[0].truncated);
+        assert!(!tree.children[1].truncated);
+
+        let tree = service
+            .get_thread_tree(&root_id, Some(u32::MAX), SiblingOrder::OldestFirst)
+            .await?
+            .unwrap();
+        assert_eq!(4, tree.thought_count());
+
+        let subtree = service
+            .get_subtree(&first_id, Some(0), SiblingOrder::default())
+            .await?
+            .unwrap();
+        assert_eq!(first_id, subtree.thought.thought_id.to_string());
+        assert!(subtree.children.is_empty());
+        assert!(subtree.truncated);
+        assert_eq!(
+            None,
+            service
+                .get_subtree(&Uuid::new_v4().to_string(), None, SiblingOrder::default())
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn move_thought_between_threads() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let post = |thought_id: &str, parent_thought_id: Option<&str>| {
+            service.post_thought(
+                thought_id.to_string(),
+                parent_thought_id.map(|p| p.to_string()),
+                draft("text"),
+            )
+        };
+        let root_id = Uuid::new_v4().to_string();
+        let node_id = Uuid::new_v4().to_string();
+        let leaf_id = Uuid::new_v4().to_string();
+        let other_id = Uuid::new_v4().to_string();
+        post(&root_id, None).await?;
+        post(&node_id, Some(&root_id)).await?;
+        post(&leaf_id, Some(&node_id)).await?;
+        post(&other_id, None).await?;
+        while receiver.try_recv().is_ok() {}
+
+        // an update cannot move a thought, it would skip the checks below
+        let error = post(&node_id, Some(&other_id)).await.unwrap_err();
+        assert!(matches!(
+            error,
+            ThoughtServiceError::ParentChangedByUpdate(_)
+        ));
+        let error = post(&root_id, Some(&other_id)).await.unwrap_err();
+        assert!(matches!(
+            error,
+            ThoughtServiceError::ParentChangedByUpdate(_)
+        ));
+
+        let error = service
+            .move_thought(&node_id, Some(&leaf_id))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ThoughtServiceError::ThoughtMovedUnderItself(_)
+        ));
+        let error = service
+            .move_thought(&node_id, Some(&Uuid::new_v4().to_string()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ThoughtServiceError::ParentNodeDoesNotExist(_)
+        ));
+        assert!(receiver.try_recv().is_err());
+
+        let moved = service
+            .move_thought(&node_id, Some(&other_id))
+            .await?
+            .unwrap();
+        assert_eq!(2, moved.len());
+        for thought_id in [&node_id, &leaf_id] {
+            assert_eq!(
+                StateModification::Update(thought_id.clone()),
+                receiver.recv().await.unwrap().action
+            );
+        }
+        assert_eq!(
+            other_id,
+            service.get_thread(&leaf_id).await?.unwrap()[0]
+                .thought_id
+                .to_string()
+        );
+
+        service.move_thought(&node_id, None).await?.unwrap();
+        let thread = service.get_thread(&leaf_id).await?.unwrap();
+        assert_eq!(node_id, thread[0].thought_id.to_string());
+        assert_eq!(None, thread[0].parent_thought_id());
+        assert_eq!(
+            None,
+            service
+                .move_thought(&Uuid::new_v4().to_string(), None)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_threads_by_graft_or_combine() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let post = |thought_id: &str, parent_thought_id: Option<&str>, keywords: &[&str]| {
+            service.post_thought(
+                thought_id.to_string(),
+                parent_thought_id.map(|p| p.to_string()),
+                ThoughtDraft {
+                    keywords: keywords.iter().map(|k| k.to_string()).collect(),
+                    ..draft("text")
+                },
+            )
+        };
+        let first_id = Uuid::new_v4().to_string();
+        let node_id = Uuid::new_v4().to_string();
+        let second_id = Uuid::new_v4().to_string();
+        let third_id = Uuid::new_v4().to_string();
+        let child_id = Uuid::new_v4().to_string();
+        post(&first_id, None, &["ethics"]).await?;
+        post(&node_id, Some(&first_id), &[]).await?;
+        post(&second_id, None, &[]).await?;
+        post(&third_id, None, &["virtue"]).await?;
+        post(&child_id, Some(&third_id), &[]).await?;
+        service
+            .create_link(&second_id, &third_id, "see_also")
+            .await?
+            .unwrap();
+        while receiver.try_recv().is_ok() {}
+
+        let error = service
+            .merge_threads(&node_id, &second_id, ThreadMergeMode::Graft)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::NotAThreadRoot(_)));
+        let error = service
+            .merge_threads(&first_id, &node_id, ThreadMergeMode::Combine)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ThoughtServiceError::ThreadMergedIntoItself(_)
+        ));
+
+        let merge = service
+            .merge_threads(&second_id, &node_id, ThreadMergeMode::Graft)
+            .await?
+            .unwrap();
+        assert_eq!(1, merge.updated_thought_ids.len());
+        assert_eq!(
+            first_id,
+            service.get_thread(&second_id).await?.unwrap()[0]
+                .thought_id
+                .to_string()
+        );
+        while receiver.try_recv().is_ok() {}
+
+        let merge = service
+            .merge_threads(&third_id, &second_id, ThreadMergeMode::Combine)
+            .await?
+            .unwrap();
+        assert_eq!(2, merge.updated_thought_ids.len());
+        assert_eq!(1, merge.redirected_link_ids.len());
+        let root = service.get_thread(&child_id).await?.unwrap().remove(0);
+        assert_eq!(first_id, root.thought_id.to_string());
+        assert_eq!(vec!["ethics", "virtue"], root.keywords);
+        assert_eq!(
+            first_id,
+            service.get_links(&second_id).await?.unwrap()[0]
+                .to_thought_id
+                .to_string()
+        );
+
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            events.push((event.subject, event.action));
+        }
+        assert_eq!(4, events.len());
+        assert!(events.contains(&(
+            "thought".to_string(),
+            StateModification::Delete(third_id.clone())
+        )));
+        assert_eq!(
+            None,
+            service
+                .merge_threads(&third_id, &first_id, ThreadMergeMode::Combine)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn markdown_thoughts_are_rendered() -> StdResult<()> {
+        let (service, _receiver) = get_service();
+        let thought = service
+            .post_thought(
+                Uuid::new_v4().to_string(),
+                None,
+                ThoughtDraft {
+                    content_format: ContentFormat::Markdown,
+                    ..draft("*Stoic* <img src=x onerror=alert(1)>")
+                },
+            )
+            .await?;
+
+        assert_eq!(ContentFormat::Markdown, thought.content_format);
+        assert_eq!("Stoic", thought.plain_text());
+        assert!(thought.html().contains("<em>Stoic</em>"));
+        assert!(!thought.html().contains("onerror"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn attachments_are_stored_and_removed() -> StdResult<()> {
+        let (service, mut receiver) = get_service();
+        let thought_id = Uuid::new_v4().to_string();
+        service
+            .post_thought(thought_id.clone(), None, draft("title"))
+            .await?;
+        while receiver.try_recv().is_ok() {}
+        let upload = std::env::temp_dir().join(format!("omstasher-upload-{}", Uuid::new_v4()));
+        tokio::fs::write(&upload, "%PDF-1.7").await?;
+
+        let error = service
+            .add_attachment(&thought_id, "notes.pdf", "pdf", &upload)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ThoughtServiceError::InvalidMimeType(_)));
+        assert_eq!(
+            None,
+            service
+                .add_attachment(
+                    &Uuid::new_v4().to_string(),
+                    "notes.pdf",
+                    "application/pdf",
+                    &upload
+                )
+                .await?
+        );
+
+        let attachment = service
+            .add_attachment(&thought_id, "../notes.pdf", "application/pdf", &upload)
+            .await?
+            .unwrap();
+        // the upload is moved to the blob store
+        assert!(!tokio::fs::try_exists(&upload).await?);
+        tokio::fs::write(&upload, "%PDF-1.7").await?;
+        let copy = service
+            .add_attachment(&thought_id, "copy.pdf", "application/pdf", &upload)
+            .await?
+            .unwrap();
+        let event = receiver.recv().await.unwrap();
+        assert_eq!("thought_attachment", event.subject);
+        assert_eq!(
+            StateModification::Creation(attachment.attachment_id.to_string()),
+            event.action
+        );
+        assert_eq!("notes.pdf", attachment.file_name);
+        assert_eq!(8, attachment.size);
+        assert_eq!(attachment.hash, copy.hash);
+        assert_eq!(
+            Some(vec![attachment.clone(), copy.clone()]),
+            service.get_attachments(&thought_id).await?
+        );
+
+        let attachment_id = attachment.attachment_id.to_string();
+        let (_, path) = service.get_attachment(&attachment_id).await?.unwrap();
+        assert_eq!("%PDF-1.7", tokio::fs::read_to_string(&path).await?);
+
+        assert_eq!(
+            Some(attachment),
+            service.remove_attachment(&attachment_id).await?
+        );
+        receiver.recv().await.unwrap();
+        assert_eq!(
+            StateModification::Delete(attachment_id.clone()),
+            receiver.recv().await.unwrap().action
+        );
+        assert_eq!(None, service.get_attachment(&attachment_id).await?);
+        assert!(service.collect_orphan_blobs().await?.is_empty());
+        assert!(tokio::fs::try_exists(&path).await?);
+
+        tokio::fs::remove_dir_all(service.config.get_attachment_directory()).await?;
+
+        Ok(())
     }
 }